@@ -0,0 +1,165 @@
+use crate::database::{quote_identifier, split_statements, CellValue, Driver};
+use super::export::sql_literal;
+
+/// Clause of the browse query edited from the results grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrowseClause {
+    /// The WHERE condition
+    Where,
+    /// The ORDER BY list
+    OrderBy,
+}
+
+impl BrowseClause {
+    /// Gets the SQL key words that start the clause
+    pub fn label(self) -> &'static str {
+        match self {
+            BrowseClause::Where => "WHERE",
+            BrowseClause::OrderBy => "ORDER BY",
+        }
+    }
+}
+
+/// A table opened from the schema browser, read one page at a time by a
+/// generated query that is shown in the editor
+#[derive(Debug, Clone)]
+pub struct TableBrowser {
+    /// Driver of the database, whose quoting rules the query follows
+    pub driver: Driver,
+    /// Schema name
+    pub schema: String,
+    /// Table, view or materialized view name
+    pub table: String,
+    /// Primary key columns, which order the rows unless another order is given
+    pub key_columns: Vec<String>,
+    /// WHERE condition, empty for all rows
+    pub condition: String,
+    /// ORDER BY list, empty to order by the primary key
+    pub order: String,
+    /// Zero-based page number
+    pub page: usize,
+    /// Rows per page
+    pub page_size: usize,
+    /// The planner's estimate of the rows in the table, from `pg_class.reltuples`
+    pub estimated_rows: Option<i64>,
+}
+
+impl TableBrowser {
+    /// Creates a browser on the first page of a table
+    pub fn new(driver: Driver, schema: String, table: String, key_columns: Vec<String>, page_size: usize) -> Self {
+        Self {
+            driver,
+            schema,
+            table,
+            key_columns,
+            condition: String::new(),
+            order: String::new(),
+            page: 0,
+            page_size: page_size.max(1),
+            estimated_rows: None,
+        }
+    }
+
+    /// Gets the quoted, schema-qualified table name
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", quote_identifier(&self.schema, self.driver), quote_identifier(&self.table, self.driver))
+    }
+
+    /// Gets the text of a clause
+    pub fn clause(&self, clause: BrowseClause) -> &str {
+        match clause {
+            BrowseClause::Where => &self.condition,
+            BrowseClause::OrderBy => &self.order,
+        }
+    }
+
+    /// Replaces a clause and goes back to the first page. Returns false, leaving the
+    /// query unchanged, when the clause would end the query and start another statement.
+    pub fn set_clause(&mut self, clause: BrowseClause, text: String) -> bool {
+        let mut changed = self.clone();
+        let text = text.trim().to_string();
+        match clause {
+            BrowseClause::Where => changed.condition = text,
+            BrowseClause::OrderBy => changed.order = text,
+        }
+        changed.page = 0;
+
+        if split_statements(&changed.sql(), self.driver).len() > 1 {
+            return false;
+        }
+        *self = changed;
+        true
+    }
+
+    /// Narrows the rows to those where a column equals a cell value, or is NULL.
+    /// Returns false for cells whose value could not be decoded.
+    pub fn add_cell_condition(&mut self, column: &str, cell: &CellValue, column_type: &str) -> bool {
+        let condition = match cell {
+            CellValue::Null => format!("{} IS NULL", quote_identifier(column, self.driver)),
+            CellValue::Value(_) => format!("{} = {}", quote_identifier(column, self.driver), sql_literal(cell, column_type, self.driver)),
+            CellValue::Undecodable(_) => return false,
+        };
+        self.condition = if self.condition.is_empty() {
+            condition
+        } else {
+            format!("({}) AND {}", self.condition, condition)
+        };
+        self.page = 0;
+        true
+    }
+
+    /// Generates the query for the current page. Rows are ordered by the primary
+    /// key by default, so pages do not overlap or skip rows between queries.
+    pub fn sql(&self) -> String {
+        let mut sql = format!("SELECT *\nFROM {}", self.qualified_name());
+        if !self.condition.is_empty() {
+            sql.push_str(&format!("\nWHERE {}", self.condition));
+        }
+        let order = if self.order.is_empty() {
+            self.key_columns.iter().map(|column| quote_identifier(column, self.driver)).collect::<Vec<_>>().join(", ")
+        } else {
+            self.order.clone()
+        };
+        if !order.is_empty() {
+            sql.push_str(&format!("\nORDER BY {}", order));
+        }
+        sql.push_str(&format!("\nLIMIT {}", self.page_size));
+        if self.page > 0 {
+            sql.push_str(&format!(" OFFSET {}", self.page * self.page_size));
+        }
+        sql.push(';');
+        sql
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_browse_sql() {
+        let mut browser = TableBrowser::new(Driver::Postgres, "public".to_string(), "Order".to_string(), vec!["id".to_string()], 50);
+        assert_eq!(browser.sql(), "SELECT *\nFROM public.\"Order\"\nORDER BY id\nLIMIT 50;");
+
+        browser.set_clause(BrowseClause::Where, " total > 10 ".to_string());
+        assert!(browser.add_cell_condition("note", &CellValue::Value("it's".to_string()), "TEXT"));
+        assert!(browser.add_cell_condition("user", &CellValue::Null, "INT4"));
+        browser.set_clause(BrowseClause::OrderBy, "total DESC".to_string());
+        browser.page = 3;
+        assert_eq!(
+            browser.sql(),
+            "SELECT *\nFROM public.\"Order\"\nWHERE ((total > 10) AND note = 'it''s') AND \"user\" IS NULL\nORDER BY total DESC\nLIMIT 50 OFFSET 150;"
+        );
+
+        // Clauses are pasted into the query, so they must not smuggle in more statements
+        assert!(!browser.set_clause(BrowseClause::Where, "true; DELETE FROM t; SELECT 1 WHERE true".to_string()));
+        assert!(!browser.set_clause(BrowseClause::OrderBy, "1; DROP TABLE t".to_string()));
+        assert!(browser.set_clause(BrowseClause::Where, "note = ';'".to_string()));
+        assert_eq!(browser.page, 0);
+
+        browser.key_columns.clear();
+        browser.set_clause(BrowseClause::OrderBy, String::new());
+        browser.set_clause(BrowseClause::Where, String::new());
+        assert_eq!(browser.sql(), "SELECT *\nFROM public.\"Order\"\nLIMIT 50;");
+    }
+}
@@ -0,0 +1,184 @@
+use std::io::{self, Write};
+use std::ops::Range;
+
+use base64::Engine;
+
+use crate::database::{CellValue, Driver};
+use super::export::{json_value, ExportFormat, Exporter};
+use super::query::QueryResult;
+
+/// Part of the results grid to copy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CopyTarget {
+    /// The selected cell
+    Cell,
+    /// The selected row, or the rows of the rectangular selection
+    Row,
+    /// The selected column, or the columns of the rectangular selection, over all shown rows
+    Column,
+    /// The rectangular selection, or the selected cell without one
+    Selection,
+}
+
+/// How copied cells are written
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CopyFormat {
+    /// Values only, separated by tabs and line breaks, with NULL as an empty value
+    #[default]
+    Text,
+    /// Tab-separated values with a header line, quoted like an export
+    Tsv,
+    /// A value, an object per row or an array, depending on the shape
+    Json,
+}
+
+impl CopyFormat {
+    /// Gets the next format, for cycling in the copy popup
+    pub fn next(self) -> Self {
+        match self {
+            CopyFormat::Text => CopyFormat::Tsv,
+            CopyFormat::Tsv => CopyFormat::Json,
+            CopyFormat::Json => CopyFormat::Text,
+        }
+    }
+
+    /// Gets the name shown in the copy popup
+    pub fn label(self) -> &'static str {
+        match self {
+            CopyFormat::Text => "Text",
+            CopyFormat::Tsv => "TSV",
+            CopyFormat::Json => "JSON",
+        }
+    }
+}
+
+/// Formats a block of shown cells for the clipboard. In JSON a single cell is a bare
+/// value, a single column an array of values, a `Row` target an object (or an
+/// array of objects for several rows) and anything else an array of objects.
+pub fn copy_text(
+    result: &QueryResult,
+    rows: Range<usize>,
+    columns: Range<usize>,
+    target: CopyTarget,
+    format: CopyFormat,
+) -> String {
+    let rows: Vec<&[CellValue]> = rows.filter_map(|row| result.visible_row(row)).collect();
+    let columns = columns.start.min(result.headers.len())..columns.end.min(result.headers.len());
+
+    match format {
+        CopyFormat::Text => rows
+            .iter()
+            .map(|row| {
+                row[columns.clone()]
+                    .iter()
+                    .map(|cell| match cell {
+                        CellValue::Null => String::new(),
+                        cell => cell.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        CopyFormat::Tsv => tsv(result, &rows, columns).unwrap_or_default(),
+        CopyFormat::Json => {
+            let column_type = |column: usize| result.column_types.get(column).map_or("", String::as_str);
+            let object = |row: &[CellValue]| {
+                let fields: Vec<String> = columns
+                    .clone()
+                    .map(|column| {
+                        let name = serde_json::Value::from(result.headers[column].as_str());
+                        format!("{}:{}", name, json_value(&row[column], column_type(column)))
+                    })
+                    .collect();
+                format!("{{{}}}", fields.join(","))
+            };
+
+            match (rows.as_slice(), columns.len()) {
+                ([row], 1) if target != CopyTarget::Row => json_value(&row[columns.start], column_type(columns.start)),
+                ([row], _) if target == CopyTarget::Row => object(row),
+                (rows, 1) if target != CopyTarget::Row => {
+                    let values: Vec<String> = rows
+                        .iter()
+                        .map(|row| json_value(&row[columns.start], column_type(columns.start)))
+                        .collect();
+                    format!("[{}]", values.join(","))
+                }
+                (rows, _) => {
+                    let objects: Vec<String> = rows.iter().map(|row| object(row)).collect();
+                    format!("[{}]", objects.join(","))
+                }
+            }
+        }
+    }
+}
+
+/// Formats cells as tab-separated values with a header line
+fn tsv(result: &QueryResult, rows: &[&[CellValue]], columns: Range<usize>) -> io::Result<String> {
+    let mut output = Vec::new();
+    let headers = result.headers[columns.clone()].to_vec();
+    let mut exporter = Exporter::new(&mut output, ExportFormat::Tsv, headers, Vec::new(), String::new(), Driver::default())?;
+    for row in rows {
+        exporter.write_row(&row[columns.clone()])?;
+    }
+    exporter.finish()?;
+    Ok(String::from_utf8_lossy(&output).trim_end_matches('\n').to_string())
+}
+
+/// Builds the OSC 52 escape sequence that asks the terminal to put text on the
+/// system clipboard. Inside tmux the sequence is also wrapped for passthrough,
+/// so it reaches the outer terminal when tmux does not handle OSC 52 itself.
+pub fn osc52_sequence(text: &str, in_tmux: bool) -> String {
+    let sequence = format!(
+        "\x1b]52;c;{}\x07",
+        base64::engine::general_purpose::STANDARD.encode(text)
+    );
+    if in_tmux {
+        format!("{}\x1bPtmux;{}\x1b\\", sequence, sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+/// Copies text to the system clipboard of the terminal through OSC 52
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let in_tmux = std::env::var_os("TMUX").is_some();
+    let mut stdout = io::stdout();
+    stdout.write_all(osc52_sequence(text, in_tmux).as_bytes())?;
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_text() {
+        let value = |text: &str| CellValue::Value(text.to_string());
+        let mut result = QueryResult::new(
+            vec!["id".to_string(), "name".to_string()],
+            vec![vec![value("1"), value("a\tb")], vec![value("2"), CellValue::Null]],
+            None,
+            0,
+        );
+        result.column_types = vec!["INT4".to_string(), "TEXT".to_string()];
+        let copy = |rows, columns, target, format| copy_text(&result, rows, columns, target, format);
+
+        assert_eq!(copy(0..1, 0..1, CopyTarget::Cell, CopyFormat::Text), "1");
+        assert_eq!(copy(0..2, 0..2, CopyTarget::Selection, CopyFormat::Text), "1\ta\tb\n2\t");
+        assert_eq!(copy(0..2, 0..2, CopyTarget::Selection, CopyFormat::Tsv), "id\tname\n1\t\"a\tb\"\n2\t");
+        assert_eq!(copy(0..1, 1..2, CopyTarget::Cell, CopyFormat::Json), "\"a\\tb\"");
+        assert_eq!(copy(0..1, 0..2, CopyTarget::Row, CopyFormat::Json), "{\"id\":1,\"name\":\"a\\tb\"}");
+        assert_eq!(copy(0..2, 0..1, CopyTarget::Column, CopyFormat::Json), "[1,2]");
+        assert_eq!(
+            copy(0..2, 0..2, CopyTarget::Selection, CopyFormat::Json),
+            "[{\"id\":1,\"name\":\"a\\tb\"},{\"id\":2,\"name\":null}]"
+        );
+
+        assert_eq!(osc52_sequence("hi", false), "\x1b]52;c;aGk=\x07");
+        assert_eq!(
+            osc52_sequence("hi", true),
+            "\x1b]52;c;aGk=\x07\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use crate::database::{quote_identifier, split_statements, tokenize, Catalog, CatalogTable, Driver, TokenKind, KEYWORDS};
+
+/// Most completions listed at once
+const MAX_COMPLETIONS: usize = 100;
+
+/// Kinds of completion candidates, in the order they are listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CompletionKind {
+    Column,
+    Table,
+    Schema,
+    Function,
+    Keyword,
+}
+
+impl CompletionKind {
+    /// Gets the label shown next to a candidate
+    pub fn label(&self) -> &'static str {
+        match self {
+            CompletionKind::Column => "column",
+            CompletionKind::Table => "table",
+            CompletionKind::Schema => "schema",
+            CompletionKind::Function => "function",
+            CompletionKind::Keyword => "keyword",
+        }
+    }
+}
+
+/// A completion candidate
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    /// Text inserted in place of the word being typed
+    pub text: String,
+    /// Kind of candidate
+    pub kind: CompletionKind,
+}
+
+/// The open completion popup
+#[derive(Debug, Clone)]
+pub struct CompletionState {
+    /// Candidates matching the word before the cursor
+    pub items: Vec<Completion>,
+    /// Index of the highlighted candidate
+    pub selected: usize,
+    /// Length in bytes of the word before the cursor that a candidate replaces
+    pub prefix_len: usize,
+}
+
+/// What the word at the cursor is expected to name, judging by the key word before it
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Context {
+    Tables,
+    Columns,
+    Keywords,
+    Any,
+}
+
+/// A table named in a FROM, JOIN, UPDATE or INTO clause
+#[derive(Debug, Clone, PartialEq)]
+struct TableReference {
+    schema: Option<String>,
+    name: String,
+    alias: Option<String>,
+}
+
+/// Finds the completions for the word ending at byte offset `cursor` of `text`,
+/// returning the length of that word and the matching candidates
+pub fn complete(catalog: &Catalog, driver: Driver, text: &str, cursor: usize) -> (usize, Vec<Completion>) {
+    let before = &text[..cursor];
+    let word_start = cursor - before.bytes().rev().take_while(|b| is_word_byte(*b)).count();
+    let prefix = &text[word_start..cursor];
+
+    let (statement_start, statement) = current_statement(text, cursor, driver);
+    let tokens = tokenize(statement);
+    let word_offset = word_start - statement_start;
+    let previous: Vec<(TokenKind, &str)> = tokens
+        .iter()
+        .filter(|token| token.range.end <= word_offset)
+        .map(|token| (token.kind, &statement[token.range.clone()]))
+        .collect();
+    let references = table_references(statement);
+
+    let mut candidates = Vec::new();
+    match previous.as_slice() {
+        // A qualified name: columns of a table or alias, or tables of a schema
+        [.., (TokenKind::Identifier | TokenKind::QuotedIdentifier, qualifier), (_, ".")] => {
+            let qualifier = identifier_name(qualifier);
+            let table = references
+                .iter()
+                .find(|reference| reference.alias.as_deref().unwrap_or(&reference.name) == qualifier)
+                .and_then(|reference| catalog.table(reference.schema.as_deref(), &reference.name))
+                .or_else(|| catalog.table(None, &qualifier));
+            if let Some(table) = table {
+                push_columns(&mut candidates, table, driver);
+            } else {
+                for table in catalog.tables.iter().filter(|table| table.schema == qualifier) {
+                    candidates.push(Completion { text: quote_identifier(&table.name, driver), kind: CompletionKind::Table });
+                }
+            }
+        }
+        _ => {
+            let scope: Vec<&CatalogTable> = references
+                .iter()
+                .filter_map(|reference| catalog.table(reference.schema.as_deref(), &reference.name))
+                .collect();
+            let context = context(&previous);
+            if matches!(context, Context::Columns | Context::Any) {
+                scope.iter().for_each(|table| push_columns(&mut candidates, table, driver));
+            }
+            if matches!(context, Context::Tables | Context::Any) {
+                for table in &catalog.tables {
+                    let text = if table.schema == catalog.default_schema {
+                        quote_identifier(&table.name, driver)
+                    } else {
+                        format!("{}.{}", quote_identifier(&table.schema, driver), quote_identifier(&table.name, driver))
+                    };
+                    candidates.push(Completion { text, kind: CompletionKind::Table });
+                }
+            }
+            if context == Context::Tables {
+                for schema in &catalog.schemas {
+                    candidates.push(Completion { text: quote_identifier(schema, driver), kind: CompletionKind::Schema });
+                }
+            }
+            if matches!(context, Context::Columns | Context::Any) {
+                for function in &catalog.functions {
+                    candidates.push(Completion { text: function.clone(), kind: CompletionKind::Function });
+                }
+            }
+            if context != Context::Tables {
+                // Key words follow the case the user is typing in
+                let lower = prefix.bytes().any(|b| b.is_ascii_lowercase());
+                for keyword in KEYWORDS {
+                    let text = if lower { keyword.to_ascii_lowercase() } else { keyword.to_string() };
+                    candidates.push(Completion { text, kind: CompletionKind::Keyword });
+                }
+            }
+        }
+    }
+
+    let prefix = prefix.to_lowercase();
+    candidates.retain(|candidate| {
+        let name = candidate.text.rsplit('.').next().unwrap_or(&candidate.text);
+        candidate.text.to_lowercase().starts_with(&prefix)
+            || name.trim_start_matches(['"', '`']).to_lowercase().starts_with(&prefix)
+    });
+    candidates.sort_by_key(|candidate| (candidate.kind, candidate.text.to_lowercase()));
+    candidates.dedup_by(|a, b| a.text.eq_ignore_ascii_case(&b.text));
+    candidates.truncate(MAX_COMPLETIONS);
+
+    (cursor - word_start, candidates)
+}
+
+/// Gets the statement the cursor is in, with its byte offset in the text
+fn current_statement(text: &str, cursor: usize, driver: Driver) -> (usize, &str) {
+    let mut current = (cursor, "");
+    for statement in split_statements(text, driver) {
+        let start = statement.as_ptr() as usize - text.as_ptr() as usize;
+        if start > cursor {
+            break;
+        }
+        let end = start + statement.len();
+        // Past the end of a statement it still applies, unless a semicolon closed it
+        current = if end >= cursor || !text[end..cursor].contains(';') {
+            (start, &text[start..end.max(cursor)])
+        } else {
+            (cursor, "")
+        };
+    }
+    current
+}
+
+/// Guesses what is expected next from the last key word before the cursor
+fn context(previous: &[(TokenKind, &str)]) -> Context {
+    let Some(index) = previous.iter().rposition(|(kind, _)| *kind == TokenKind::Keyword) else {
+        return Context::Any;
+    };
+
+    match previous[index].1.to_ascii_uppercase().as_str() {
+        "FROM" | "JOIN" | "INTO" | "UPDATE" | "TABLE" | "TRUNCATE" | "ONLY" => {
+            // After a table name, e.g. `FROM users u`, comes the rest of the statement
+            match previous.last() {
+                Some((TokenKind::Identifier | TokenKind::QuotedIdentifier, _)) => Context::Keywords,
+                _ => Context::Tables,
+            }
+        }
+        "SELECT" | "WHERE" | "AND" | "OR" | "ON" | "BY" | "HAVING" | "SET" | "WHEN" | "THEN" | "ELSE"
+        | "RETURNING" | "DISTINCT" | "NOT" | "CASE" => Context::Columns,
+        _ => Context::Any,
+    }
+}
+
+/// Finds the tables a statement reads or writes, with their aliases
+fn table_references(statement: &str) -> Vec<TableReference> {
+    let tokens: Vec<(TokenKind, &str)> = tokenize(statement)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .map(|token| (token.kind, &statement[token.range]))
+        .collect();
+    let is_name = |i: usize| matches!(tokens.get(i), Some((TokenKind::Identifier | TokenKind::QuotedIdentifier, _)));
+    let is_keyword = |i: usize, word: &str| {
+        matches!(tokens.get(i), Some((TokenKind::Keyword, text)) if text.eq_ignore_ascii_case(word))
+    };
+
+    let mut references = Vec::new();
+    for (index, (kind, text)) in tokens.iter().enumerate() {
+        let clause = text.to_ascii_uppercase();
+        if *kind != TokenKind::Keyword || !matches!(clause.as_str(), "FROM" | "JOIN" | "UPDATE" | "INTO") {
+            continue;
+        }
+
+        let mut i = index + 1;
+        loop {
+            if is_keyword(i, "ONLY") || is_keyword(i, "LATERAL") {
+                i += 1;
+            }
+            if !is_name(i) {
+                break;
+            }
+            let (schema, name) = if tokens.get(i + 1).map(|token| token.1) == Some(".") && is_name(i + 2) {
+                i += 3;
+                (Some(identifier_name(tokens[i - 3].1)), identifier_name(tokens[i - 1].1))
+            } else {
+                i += 1;
+                (None, identifier_name(tokens[i - 1].1))
+            };
+            if is_keyword(i, "AS") {
+                i += 1;
+            }
+            let alias = if is_name(i) {
+                i += 1;
+                Some(identifier_name(tokens[i - 1].1))
+            } else {
+                None
+            };
+            references.push(TableReference { schema, name, alias });
+
+            if clause != "FROM" || tokens.get(i).map(|token| token.1) != Some(",") {
+                break;
+            }
+            i += 1;
+        }
+    }
+    references
+}
+
+/// Adds the columns of a table to the candidates
+fn push_columns(candidates: &mut Vec<Completion>, table: &CatalogTable, driver: Driver) {
+    for column in &table.columns {
+        candidates.push(Completion { text: quote_identifier(column, driver), kind: CompletionKind::Column });
+    }
+}
+
+/// Gets the name an identifier refers to: quoted identifiers as written, others in lower case
+fn identifier_name(identifier: &str) -> String {
+    match identifier.strip_prefix('"') {
+        Some(quoted) => quoted.strip_suffix('"').unwrap_or(quoted).replace("\"\"", "\""),
+        None => identifier.to_lowercase(),
+    }
+}
+
+/// Whether a byte can be part of the word being completed
+pub fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$' || byte >= 0x80
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Catalog {
+        let table = |schema: &str, name: &str, columns: &[&str]| CatalogTable {
+            schema: schema.to_string(),
+            name: name.to_string(),
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+        };
+        Catalog {
+            schemas: vec!["public".to_string(), "sales".to_string()],
+            tables: vec![
+                table("public", "users", &["id", "name", "email"]),
+                table("public", "events", &["id", "user_id", "Payload"]),
+                table("sales", "orders", &["id", "total"]),
+            ],
+            functions: vec!["now".to_string(), "nullif".to_string()],
+            default_schema: "public".to_string(),
+        }
+    }
+
+    /// Completes at the `|` marking the cursor
+    fn texts(text: &str) -> Vec<String> {
+        let cursor = text.find('|').unwrap();
+        let text = text.replace('|', "");
+        complete(&catalog(), Driver::Postgres, &text, cursor)
+            .1
+            .into_iter()
+            .map(|completion| completion.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_complete() {
+        assert_eq!(texts("SELECT * FROM u|"), vec!["users"]);
+        assert_eq!(texts("SELECT * FROM |"), vec!["events", "sales.orders", "users", "public", "sales"]);
+        assert_eq!(texts("SELECT 1; SELECT * FROM ev|"), vec!["events"]);
+        assert_eq!(texts("SELECT na| FROM users"), vec!["name", "natural"]);
+        assert_eq!(texts("SELECT * FROM users u wh|"), vec!["when", "where"]);
+        assert_eq!(texts("SELECT NULLI|"), vec!["nullif"]);
+    }
+
+    #[test]
+    fn test_complete_qualified_names() {
+        assert_eq!(texts("SELECT e.p| FROM events e"), vec!["\"Payload\""]);
+        assert_eq!(texts("SELECT u.e| FROM users u"), vec!["email"]);
+        assert_eq!(texts("SELECT * FROM users u JOIN events e ON e.u|"), vec!["user_id"]);
+        assert_eq!(texts("SELECT x.to| FROM sales.orders AS x"), vec!["total"]);
+        assert_eq!(texts("SELECT * FROM sales.|"), vec!["orders"]);
+        assert_eq!(texts("SELECT users.|"), vec!["email", "id", "name"]);
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::database::{quote_identifier, CellValue, Driver};
+use super::export::{quote_string, sql_literal};
+use super::query::QueryResult;
+
+/// Changes made to the rows of a single-table result in edit mode, kept until
+/// they are applied in one transaction or discarded. Rows are indices into
+/// `QueryResult::rows`; inserted rows are appended after the loaded ones.
+#[derive(Debug, Clone)]
+pub struct EditSession {
+    /// The edited table as written in the query, e.g. `public.users`
+    pub table: String,
+    /// Indices of the primary key columns in the result
+    pub key_columns: Vec<usize>,
+    /// Number of rows the result had before any were inserted
+    pub loaded_rows: usize,
+    /// New cell values by row and column
+    pub changes: BTreeMap<(usize, usize), CellValue>,
+    /// Rows marked for deletion
+    pub deleted: BTreeSet<usize>,
+}
+
+impl EditSession {
+    /// Starts editing a result read from `table`
+    pub fn new(table: String, key_columns: Vec<usize>, loaded_rows: usize) -> Self {
+        Self {
+            table,
+            key_columns,
+            loaded_rows,
+            changes: BTreeMap::new(),
+            deleted: BTreeSet::new(),
+        }
+    }
+
+    /// Whether a row was inserted in edit mode
+    pub fn is_inserted(&self, row: usize) -> bool {
+        row >= self.loaded_rows
+    }
+
+    /// Whether there is nothing to apply
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.deleted.is_empty()
+    }
+
+    /// Gets the new value of a cell, if it was changed
+    pub fn change(&self, row: usize, column: usize) -> Option<&CellValue> {
+        self.changes.get(&(row, column))
+    }
+
+    /// Sets a cell of a loaded row to a new value, forgetting the change when the
+    /// value is set back to the loaded one
+    pub fn set_cell(&mut self, row: usize, column: usize, value: CellValue, loaded: &CellValue) {
+        if !self.is_inserted(row) && value == *loaded {
+            self.changes.remove(&(row, column));
+        } else {
+            self.changes.insert((row, column), value);
+        }
+    }
+
+    /// Forgets the change of a cell, or else the deletion of its row. Returns
+    /// false when neither was pending.
+    pub fn revert(&mut self, row: usize, column: usize) -> bool {
+        self.changes.remove(&(row, column)).is_some() || self.deleted.remove(&row)
+    }
+
+    /// Marks a row for deletion, or unmarks it
+    pub fn toggle_deleted(&mut self, row: usize) {
+        if !self.deleted.remove(&row) {
+            self.deleted.insert(row);
+        }
+    }
+
+    /// Counts the rows to update, insert and delete, out of `rows` rows in the result
+    pub fn counts(&self, rows: usize) -> (usize, usize, usize) {
+        let changed: BTreeSet<usize> = self.changes.keys().map(|(row, _)| *row).collect();
+        let updated = changed
+            .iter()
+            .filter(|row| !self.is_inserted(**row) && !self.deleted.contains(row))
+            .count();
+        let inserted = (self.loaded_rows..rows).filter(|row| !self.deleted.contains(row)).count();
+        let deleted = self.deleted.iter().filter(|row| !self.is_inserted(**row)).count();
+        (updated, inserted, deleted)
+    }
+
+    /// Generates the DELETE, UPDATE and INSERT statements that apply the changes,
+    /// in that order. Rows are found by the primary key values they were loaded with.
+    /// Inserted rows leave the cells that were not set to their column defaults.
+    /// Names and values are quoted as `driver` reads them.
+    pub fn statements(&self, result: &QueryResult, driver: Driver) -> Result<Vec<String>, String> {
+        let column = |index: usize| quote_identifier(&result.headers[index], driver);
+        let column_type = |index: usize| result.column_types.get(index).map_or("", String::as_str);
+        let key = |row: usize| -> Result<String, String> {
+            let conditions: Vec<String> = self
+                .key_columns
+                .iter()
+                .map(|index| match &result.rows[row][*index] {
+                    CellValue::Undecodable(type_name) => {
+                        Err(format!("Cannot find row {} by a key of type {}", row + 1, type_name))
+                    }
+                    CellValue::Null => Ok(format!("{} IS NULL", column(*index))),
+                    cell => Ok(format!("{} = {}", column(*index), sql_literal(cell, column_type(*index), driver))),
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(conditions.join(" AND "))
+        };
+        // Typed values are quoted for Postgres to convert to the column type
+        let literal = |cell: &CellValue| match cell {
+            CellValue::Value(text) => quote_string(text, driver),
+            cell => sql_literal(cell, "", driver),
+        };
+
+        let mut statements = Vec::new();
+        for row in self.deleted.iter().filter(|row| !self.is_inserted(**row)) {
+            statements.push(format!("DELETE FROM {} WHERE {};", self.table, key(*row)?));
+        }
+
+        let mut rows: BTreeMap<usize, Vec<(usize, &CellValue)>> = BTreeMap::new();
+        for ((row, index), value) in &self.changes {
+            if !self.deleted.contains(row) {
+                rows.entry(*row).or_default().push((*index, value));
+            }
+        }
+        for (row, values) in rows.iter().filter(|(row, _)| !self.is_inserted(**row)) {
+            let assignments: Vec<String> = values
+                .iter()
+                .map(|(index, value)| format!("{} = {}", column(*index), literal(value)))
+                .collect();
+            statements.push(format!("UPDATE {} SET {} WHERE {};", self.table, assignments.join(", "), key(*row)?));
+        }
+        for (_, values) in rows.iter().filter(|(row, _)| self.is_inserted(**row)) {
+            let columns: Vec<String> = values.iter().map(|(index, _)| column(*index)).collect();
+            let literals: Vec<String> = values.iter().map(|(_, value)| literal(value)).collect();
+            statements.push(format!(
+                "INSERT INTO {} ({}) VALUES ({});",
+                self.table,
+                columns.join(", "),
+                literals.join(", ")
+            ));
+        }
+        // Inserted rows without any value set take all defaults
+        let untouched = (self.loaded_rows..result.rows.len())
+            .filter(|row| !rows.contains_key(row) && !self.deleted.contains(row));
+        for _ in untouched {
+            statements.push(format!("INSERT INTO {} DEFAULT VALUES;", self.table));
+        }
+        Ok(statements)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_statements() {
+        let value = |text: &str| CellValue::Value(text.to_string());
+        let mut result = QueryResult::new(
+            ["id", "name", "Note"].map(String::from).to_vec(),
+            vec![
+                vec![value("1"), value("a"), CellValue::Null],
+                vec![value("2"), value("b"), value("x")],
+                vec![value("3"), value("c"), value("y")],
+            ],
+            None,
+            0,
+        );
+        result.column_types = ["INT4", "TEXT", "TEXT"].map(String::from).to_vec();
+        let mut edit = EditSession::new("public.t".to_string(), vec![0], 3);
+
+        edit.set_cell(0, 1, value("it's"), &value("a"));
+        edit.set_cell(0, 2, value("n"), &CellValue::Null);
+        edit.set_cell(1, 1, value("b"), &value("b"));
+        edit.set_cell(2, 1, value("z"), &value("c"));
+        edit.toggle_deleted(2);
+        result.rows.push(vec![CellValue::Null; 3]);
+        result.rows.push(vec![CellValue::Null; 3]);
+        edit.set_cell(3, 1, value("d"), &CellValue::Null);
+        edit.set_cell(3, 2, CellValue::Null, &CellValue::Null);
+
+        assert_eq!(edit.counts(result.rows.len()), (1, 2, 1));
+        assert_eq!(
+            edit.statements(&result, Driver::Postgres).unwrap(),
+            vec![
+                "DELETE FROM public.t WHERE id = 3;",
+                "UPDATE public.t SET name = 'it''s', \"Note\" = 'n' WHERE id = 1;",
+                "INSERT INTO public.t (name, \"Note\") VALUES ('d', NULL);",
+                "INSERT INTO public.t DEFAULT VALUES;",
+            ]
+        );
+        assert_eq!(
+            edit.statements(&result, Driver::Mysql).unwrap()[1],
+            "UPDATE public.t SET name = 'it''s', `Note` = 'n' WHERE id = 1;"
+        );
+
+        assert!(edit.revert(2, 0));
+        assert!(!edit.revert(2, 0));
+        assert!(edit.revert(0, 2));
+        result.rows[0][0] = CellValue::Undecodable("mystery".to_string());
+        assert!(edit.statements(&result, Driver::Postgres).is_err());
+    }
+}
@@ -0,0 +1,495 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use tokio::task::JoinHandle;
+
+use crate::database::{quote_identifier, source_table, CellValue, DatabaseManager, Driver};
+use super::query::QueryResult;
+
+/// Table name used in INSERT statements when the query does not read a single table
+const DEFAULT_TABLE: &str = "exported_rows";
+
+/// File formats results can be exported to, chosen by the file extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    /// Comma-separated values with RFC 4180 quoting
+    Csv,
+    /// Tab-separated values, quoted like CSV
+    Tsv,
+    /// A JSON array of objects
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// A Markdown table
+    Markdown,
+    /// One `INSERT INTO` statement per row
+    Sql,
+}
+
+impl ExportFormat {
+    /// Extensions recognized by `from_path`, listed in prompts
+    pub const EXTENSIONS: &'static str = "csv, tsv, json, ndjson, md, sql";
+
+    /// Picks the format from a file extension, ignoring case
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "tsv" | "tab" => Some(ExportFormat::Tsv),
+            "json" => Some(ExportFormat::Json),
+            "ndjson" | "jsonl" => Some(ExportFormat::Ndjson),
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            "sql" => Some(ExportFormat::Sql),
+            _ => None,
+        }
+    }
+}
+
+/// Writes result rows in an export format one at a time, so large results can
+/// be streamed to a file without holding them in memory
+pub struct Exporter<W: Write> {
+    writer: W,
+    format: ExportFormat,
+    headers: Vec<String>,
+    column_types: Vec<String>,
+    /// Table name written in INSERT statements
+    table: String,
+    /// Driver whose quoting rules INSERT statements follow
+    driver: Driver,
+    rows_written: usize,
+}
+
+impl<W: Write> Exporter<W> {
+    /// Creates an exporter and writes the header of the format
+    pub fn new(
+        mut writer: W,
+        format: ExportFormat,
+        headers: Vec<String>,
+        column_types: Vec<String>,
+        table: String,
+        driver: Driver,
+    ) -> io::Result<Self> {
+        match format {
+            ExportFormat::Csv | ExportFormat::Tsv => {
+                let fields: Vec<String> = headers.iter().map(|header| delimited_field(header, format)).collect();
+                writeln!(writer, "{}", fields.join(delimiter(format)))?;
+            }
+            ExportFormat::Json => writeln!(writer, "[")?,
+            ExportFormat::Markdown => {
+                let cells: Vec<String> = headers.iter().map(|header| markdown_cell(header)).collect();
+                writeln!(writer, "| {} |", cells.join(" | "))?;
+                writeln!(writer, "|{}", " --- |".repeat(headers.len()))?;
+            }
+            ExportFormat::Ndjson | ExportFormat::Sql => {}
+        }
+
+        Ok(Self {
+            writer,
+            format,
+            headers,
+            column_types,
+            table,
+            driver,
+            rows_written: 0,
+        })
+    }
+
+    /// Writes one row
+    pub fn write_row(&mut self, row: &[CellValue]) -> io::Result<()> {
+        match self.format {
+            ExportFormat::Csv | ExportFormat::Tsv => {
+                let fields: Vec<String> = row
+                    .iter()
+                    .map(|cell| match cell {
+                        CellValue::Null => String::new(),
+                        cell => delimited_field(&cell.to_string(), self.format),
+                    })
+                    .collect();
+                writeln!(self.writer, "{}", fields.join(delimiter(self.format)))?;
+            }
+            ExportFormat::Json => {
+                let separator = if self.rows_written == 0 { "" } else { ",\n" };
+                write!(self.writer, "{}  {}", separator, self.json_object(row))?;
+            }
+            ExportFormat::Ndjson => writeln!(self.writer, "{}", self.json_object(row))?,
+            ExportFormat::Markdown => {
+                let cells: Vec<String> = row.iter().map(|cell| markdown_cell(&cell.to_string())).collect();
+                writeln!(self.writer, "| {} |", cells.join(" | "))?;
+            }
+            ExportFormat::Sql => {
+                let columns: Vec<String> = self.headers.iter().map(|header| quote_identifier(header, self.driver)).collect();
+                let values: Vec<String> = row
+                    .iter()
+                    .enumerate()
+                    .map(|(index, cell)| sql_literal(cell, self.column_type(index), self.driver))
+                    .collect();
+                writeln!(
+                    self.writer,
+                    "INSERT INTO {} ({}) VALUES ({});",
+                    self.table,
+                    columns.join(", "),
+                    values.join(", ")
+                )?;
+            }
+        }
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    /// Writes the end of the format and flushes, returning the number of rows written
+    pub fn finish(mut self) -> io::Result<usize> {
+        if self.format == ExportFormat::Json {
+            let separator = if self.rows_written == 0 { "" } else { "\n" };
+            writeln!(self.writer, "{}]", separator)?;
+        }
+        self.writer.flush()?;
+        Ok(self.rows_written)
+    }
+
+    /// Gets the type name of a column, or an empty string if it is unknown
+    fn column_type(&self, index: usize) -> &str {
+        self.column_types.get(index).map_or("", String::as_str)
+    }
+
+    /// Formats a row as a JSON object with the columns in result order
+    fn json_object(&self, row: &[CellValue]) -> String {
+        let fields: Vec<String> = self
+            .headers
+            .iter()
+            .zip(row)
+            .enumerate()
+            .map(|(index, (header, cell))| {
+                format!("{}:{}", serde_json::Value::from(header.as_str()), json_value(cell, self.column_type(index)))
+            })
+            .collect();
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+/// Writes the rows of a result that are shown in the grid to a file, sorted and
+/// filtered as shown, returning the number of rows written
+pub fn export_loaded(result: &QueryResult, statement: &str, driver: Driver, path: &Path, format: ExportFormat) -> Result<usize> {
+    let mut exporter = Exporter::new(
+        BufWriter::new(File::create(path)?),
+        format,
+        result.headers.clone(),
+        result.column_types.clone(),
+        export_table(statement, driver),
+        driver,
+    )?;
+    for row in (0..result.visible_count()).filter_map(|row| result.visible_row(row)) {
+        exporter.write_row(row)?;
+    }
+    Ok(exporter.finish()?)
+}
+
+/// An export of all the rows of a result, writing on a background task. The rows
+/// are written to a `.part` file next to the target, which replaces the target
+/// once all of them are written.
+pub struct RunningExport {
+    /// The file exported to
+    path: PathBuf,
+    /// The file written to until the export completes
+    partial: PathBuf,
+    /// When the export was started
+    started: Instant,
+    handle: JoinHandle<Result<usize>>,
+}
+
+impl RunningExport {
+    /// Starts exporting all the rows of a result on a background task, see `export_all`
+    pub fn spawn(
+        db: &DatabaseManager,
+        result: &QueryResult,
+        statement: &str,
+        path: PathBuf,
+        format: ExportFormat,
+        page_size: usize,
+    ) -> Self {
+        let db = db.clone();
+        let (headers, column_types) = (result.headers.clone(), result.column_types.clone());
+        let statement = statement.to_string();
+        let mut partial = path.clone().into_os_string();
+        partial.push(".part");
+        let partial = PathBuf::from(partial);
+        let (task_path, task_partial) = (path.clone(), partial.clone());
+
+        let handle = tokio::spawn(async move {
+            match export_all(&db, headers, column_types, &statement, &task_partial, format, page_size).await {
+                Ok(rows) => {
+                    std::fs::rename(&task_partial, &task_path)?;
+                    Ok(rows)
+                }
+                Err(e) => {
+                    let _ = std::fs::remove_file(&task_partial);
+                    Err(e)
+                }
+            }
+        });
+
+        Self {
+            path,
+            partial,
+            started: Instant::now(),
+            handle,
+        }
+    }
+
+    /// Gets the file exported to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets the time since the export was started
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Whether the export has completed, successfully or not
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Waits for the export to complete and returns the number of rows written
+    pub async fn finish(self) -> Result<usize> {
+        self.handle.await?
+    }
+
+    /// Stops the export, which rolls back its read-only transaction, and removes the
+    /// partly written file. A file already at the target path is left as it was.
+    pub async fn cancel(self) {
+        self.handle.abort();
+        // Wait for the task to stop so it does not write to the file after it is removed
+        let _ = self.handle.await;
+        let _ = std::fs::remove_file(&self.partial);
+    }
+}
+
+impl std::fmt::Debug for RunningExport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RunningExport").field("path", &self.path).finish()
+    }
+}
+
+/// Runs a statement again in a read-only transaction and streams all of its rows
+/// to a file page by page, returning the number of rows written. `headers` and
+/// `column_types` are those of the loaded result, used when there are no rows.
+async fn export_all(
+    db: &DatabaseManager,
+    headers: Vec<String>,
+    column_types: Vec<String>,
+    statement: &str,
+    path: &Path,
+    format: ExportFormat,
+    page_size: usize,
+) -> Result<usize> {
+    let mut cursor = db.open_read_only_cursor(statement, page_size).await?;
+    let mut page = cursor.fetch_page().await?;
+    // Without rows the page has no column names
+    let (headers, column_types) = if page.row_count() > 0 {
+        (page.columns(), page.column_types())
+    } else {
+        (headers, column_types)
+    };
+    let mut exporter = Exporter::new(
+        BufWriter::new(File::create(path)?),
+        format,
+        headers,
+        column_types,
+        export_table(statement, db.driver()),
+        db.driver(),
+    )?;
+
+    loop {
+        for row in page.rows_as_values() {
+            exporter.write_row(&row)?;
+        }
+        if page.row_count() < page_size {
+            break;
+        }
+        page = cursor.fetch_page().await?;
+    }
+    cursor.close().await?;
+    Ok(exporter.finish()?)
+}
+
+/// Expands a leading `~` in a path to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Gets the table name for INSERT statements exported from a query
+fn export_table(statement: &str, driver: Driver) -> String {
+    source_table(statement, driver).unwrap_or_else(|| DEFAULT_TABLE.to_string())
+}
+
+/// Gets the field separator of a delimited format
+fn delimiter(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Tsv => "\t",
+        _ => ",",
+    }
+}
+
+/// Quotes a CSV or TSV field if it contains the delimiter, a quote, a line break
+/// or surrounding spaces, doubling any quotes inside
+fn delimited_field(text: &str, format: ExportFormat) -> String {
+    let needs_quotes = text.contains(delimiter(format))
+        || text.contains(['"', '\n', '\r'])
+        || text.starts_with(' ')
+        || text.ends_with(' ');
+    if needs_quotes {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Escapes a Markdown table cell, keeping it on one line
+fn markdown_cell(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\n', '\r'], "<br>")
+}
+
+/// Whether a type's values are written as bare numbers
+fn is_numeric_type(column_type: &str) -> bool {
+    matches!(column_type, "INT2" | "INT4" | "INT8" | "OID" | "FLOAT4" | "FLOAT8" | "NUMERIC")
+}
+
+/// Whether a numeric value is finite and can be written without quotes
+fn is_plain_number(text: &str) -> bool {
+    text.parse::<f64>().is_ok_and(f64::is_finite)
+}
+
+/// Converts a cell to JSON text: numbers, booleans and JSON values keep their type,
+/// everything else becomes a string. Numbers are copied as text so NUMERIC values
+/// keep their precision.
+pub fn json_value(cell: &CellValue, column_type: &str) -> String {
+    let string = |text: &str| serde_json::Value::from(text).to_string();
+    let is_json = |text: &str| serde_json::from_str::<serde_json::Value>(text).is_ok();
+    match cell {
+        CellValue::Null => "null".to_string(),
+        CellValue::Value(text) => match column_type {
+            "BOOL" => (text == "true").to_string(),
+            "JSON" | "JSONB" if is_json(text) => text.clone(),
+            column_type if is_numeric_type(column_type) && is_plain_number(text) && is_json(text) => text.clone(),
+            _ => string(text),
+        },
+        CellValue::Undecodable(_) => string(&cell.to_string()),
+    }
+}
+
+/// Converts a cell to an SQL literal. Values of types that could not be decoded
+/// are written as NULL with the type name in a comment.
+pub fn sql_literal(cell: &CellValue, column_type: &str, driver: Driver) -> String {
+    match cell {
+        CellValue::Null => "NULL".to_string(),
+        CellValue::Value(text) => match column_type {
+            "BOOL" => text.to_ascii_uppercase(),
+            column_type if is_numeric_type(column_type) && is_plain_number(text) => text.clone(),
+            _ => quote_string(text, driver),
+        },
+        CellValue::Undecodable(type_name) => format!("NULL /* {} */", type_name),
+    }
+}
+
+/// Quotes text as a string literal. MySQL takes backslash escapes in strings,
+/// so its backslashes are doubled too.
+pub fn quote_string(text: &str, driver: Driver) -> String {
+    let text = text.replace('\'', "''");
+    match driver {
+        Driver::Mysql => format!("'{}'", text.replace('\\', "\\\\")),
+        Driver::Postgres | Driver::Sqlite => format!("'{}'", text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseConfig;
+
+    fn export(format: ExportFormat, driver: Driver) -> String {
+        let headers = ["id", "name", "data", "order"].map(String::from).to_vec();
+        let column_types = ["NUMERIC", "TEXT", "JSONB", "BOOL"].map(String::from).to_vec();
+        let value = |text: &str| CellValue::Value(text.to_string());
+        let rows = [
+            vec![value("12345678901234567890.10"), value("a, \"b\"\nc|d"), value("{\"k\":[1,2]}"), value("true")],
+            vec![value("2"), value("it's"), CellValue::Null, CellValue::Null],
+        ];
+
+        let mut output = Vec::new();
+        let mut exporter = Exporter::new(&mut output, format, headers, column_types, "users".to_string(), driver).unwrap();
+        for row in &rows {
+            exporter.write_row(row).unwrap();
+        }
+        assert_eq!(exporter.finish().unwrap(), 2);
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_export_formats() {
+        assert_eq!(
+            export(ExportFormat::Csv, Driver::Postgres),
+            "id,name,data,order\n12345678901234567890.10,\"a, \"\"b\"\"\nc|d\",\"{\"\"k\"\":[1,2]}\",true\n2,it's,,\n"
+        );
+        assert_eq!(
+            export(ExportFormat::Tsv, Driver::Postgres),
+            "id\tname\tdata\torder\n12345678901234567890.10\t\"a, \"\"b\"\"\nc|d\"\t\"{\"\"k\"\":[1,2]}\"\ttrue\n2\tit's\t\t\n"
+        );
+        assert_eq!(
+            export(ExportFormat::Json, Driver::Postgres),
+            "[\n  {\"id\":12345678901234567890.10,\"name\":\"a, \\\"b\\\"\\nc|d\",\"data\":{\"k\":[1,2]},\"order\":true},\n  \
+             {\"id\":2,\"name\":\"it's\",\"data\":null,\"order\":null}\n]\n"
+        );
+        assert_eq!(export(ExportFormat::Ndjson, Driver::Postgres).lines().nth(1), Some("{\"id\":2,\"name\":\"it's\",\"data\":null,\"order\":null}"));
+        assert_eq!(
+            export(ExportFormat::Markdown, Driver::Postgres),
+            "| id | name | data | order |\n| --- | --- | --- | --- |\n\
+             | 12345678901234567890.10 | a, \"b\"<br>c\\|d | {\"k\":[1,2]} | true |\n| 2 | it's | NULL | NULL |\n"
+        );
+        assert_eq!(
+            export(ExportFormat::Sql, Driver::Postgres),
+            "INSERT INTO users (id, name, data, \"order\") VALUES (12345678901234567890.10, 'a, \"b\"\nc|d', '{\"k\":[1,2]}', TRUE);\n\
+             INSERT INTO users (id, name, data, \"order\") VALUES (2, 'it''s', NULL, NULL);\n"
+        );
+        assert_eq!(
+            export(ExportFormat::Sql, Driver::Mysql).lines().nth(2),
+            Some("INSERT INTO users (id, name, data, `order`) VALUES (2, 'it''s', NULL, NULL);")
+        );
+        assert_eq!(quote_string("C:\\temp\\it's", Driver::Mysql), "'C:\\\\temp\\\\it''s'");
+        assert_eq!(quote_string("C:\\temp\\it's", Driver::Postgres), "'C:\\temp\\it''s'");
+        assert_eq!(ExportFormat::from_path(Path::new("out/Result.JSONL")), Some(ExportFormat::Ndjson));
+        assert_eq!(ExportFormat::from_path(Path::new("result.xlsx")), None);
+    }
+
+    #[tokio::test]
+    async fn test_running_export() {
+        let db = DatabaseManager::new(DatabaseConfig::new()).await.unwrap();
+        let result = QueryResult::from_database(&Default::default(), 0);
+        let path = std::env::temp_dir().join(format!("ferrum_db-export-{}.csv", std::process::id()));
+        let spawn = |statement| RunningExport::spawn(&db, &result, statement, path.clone(), ExportFormat::Csv, 10);
+
+        let export = spawn("SELECT g FROM generate_series(1, 25) g");
+        assert_eq!(export.finish().await.unwrap(), 25);
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 26);
+
+        // Cancelling keeps the file that was there and removes the partial one
+        let export = spawn("SELECT g, pg_sleep(0.01) FROM generate_series(1, 10000) g");
+        while !export.partial.exists() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let partial = export.partial.clone();
+        export.cancel().await;
+        assert!(!partial.exists());
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 26);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,226 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use unicode_width::UnicodeWidthStr;
+
+use super::query::QueryResult;
+
+/// Widest a column grows to fit its content, in display columns
+const MAX_COLUMN_WIDTH: usize = 40;
+
+/// Space between two columns
+pub const COLUMN_SPACING: usize = 1;
+
+/// Cursor and viewport of the results grid
+#[derive(Debug, Default, Clone)]
+pub struct GridState {
+    /// Selected row
+    row: usize,
+    /// Selected column
+    column: usize,
+    /// First visible row
+    row_offset: usize,
+    /// First visible column
+    column_offset: usize,
+    /// Display width of each column
+    widths: Vec<usize>,
+    /// Number of rows that fit in the viewport, updated when drawn
+    page_rows: usize,
+    /// Row and column where the rectangular selection started, if one is active
+    anchor: Option<(usize, usize)>,
+}
+
+impl GridState {
+    /// Creates the grid state for a new result, sizing columns to their content
+    pub fn new(result: &QueryResult) -> Self {
+        let mut grid = Self {
+            widths: vec![0; result.headers.len()],
+            page_rows: 1,
+            ..Self::default()
+        };
+        grid.fit_columns(result, 0);
+        grid
+    }
+
+    /// Widens columns to fit rows from `first_row` on, e.g. after more rows were loaded
+    pub fn fit_columns(&mut self, result: &QueryResult, first_row: usize) {
+        self.widths.resize(result.headers.len(), 0);
+        let fit = |text: &str| single_line(text).width().min(MAX_COLUMN_WIDTH);
+        for (width, header) in self.widths.iter_mut().zip(&result.headers) {
+            *width = (*width).max(fit(header));
+        }
+        for row in result.rows.iter().skip(first_row) {
+            for (width, cell) in self.widths.iter_mut().zip(row) {
+                *width = (*width).max(fit(&cell.to_string()));
+            }
+        }
+    }
+
+    /// Widens a column to fit a value shown in it, e.g. an edited one
+    pub fn fit_text(&mut self, column: usize, text: &str) {
+        if let Some(width) = self.widths.get_mut(column) {
+            *width = (*width).max(single_line(text).width().min(MAX_COLUMN_WIDTH));
+        }
+    }
+
+    /// Gets the selected row
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    /// Gets the selected column
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Starts a rectangular selection at the selected cell, or ends the active one
+    pub fn toggle_selection(&mut self) {
+        self.anchor = match self.anchor {
+            Some(_) => None,
+            None => Some((self.row, self.column)),
+        };
+    }
+
+    /// Ends the rectangular selection
+    pub fn clear_selection(&mut self) {
+        self.anchor = None;
+    }
+
+    /// Gets the rows and columns of the rectangular selection, if one is active
+    pub fn selection(&self) -> Option<(Range<usize>, Range<usize>)> {
+        let (row, column) = self.anchor?;
+        Some((
+            row.min(self.row)..row.max(self.row) + 1,
+            column.min(self.column)..column.max(self.column) + 1,
+        ))
+    }
+
+    /// Gets the display width of each column
+    pub fn widths(&self) -> &[usize] {
+        &self.widths
+    }
+
+    /// Whether the selection is within a page of the last loaded row
+    pub fn is_near_end(&self, row_count: usize) -> bool {
+        self.row + self.page_rows >= row_count
+    }
+
+    /// Moves the selected row by `delta`, clamped to the result
+    pub fn move_row(&mut self, delta: isize, row_count: usize) {
+        self.row = self
+            .row
+            .saturating_add_signed(delta)
+            .min(row_count.saturating_sub(1));
+    }
+
+    /// Moves the selected column by `delta`, clamped to the result
+    pub fn move_column(&mut self, delta: isize) {
+        self.column = self
+            .column
+            .saturating_add_signed(delta)
+            .min(self.widths.len().saturating_sub(1));
+    }
+
+    /// Moves the selected row by one page
+    pub fn move_page(&mut self, pages: isize, row_count: usize) {
+        self.move_row(pages * self.page_rows.max(1) as isize, row_count);
+    }
+
+    /// Selects the first row
+    pub fn first_row(&mut self) {
+        self.row = 0;
+    }
+
+    /// Selects the last row
+    pub fn last_row(&mut self, row_count: usize) {
+        self.row = row_count.saturating_sub(1);
+    }
+
+    /// Selects the first column
+    pub fn first_column(&mut self) {
+        self.column = 0;
+    }
+
+    /// Selects the last column
+    pub fn last_column(&mut self) {
+        self.column = self.widths.len().saturating_sub(1);
+    }
+
+    /// Adjusts the viewport so the selected cell is visible, returning the rows and
+    /// columns to draw in a viewport of `height` rows and `width` display columns
+    pub fn scroll_to_cursor(&mut self, row_count: usize, height: usize, width: usize) -> (Range<usize>, Range<usize>) {
+        self.page_rows = height.max(1);
+        if self.row < self.row_offset {
+            self.row_offset = self.row;
+        } else if self.row >= self.row_offset + self.page_rows {
+            self.row_offset = self.row + 1 - self.page_rows;
+        }
+        let rows = self.row_offset..(self.row_offset + self.page_rows).min(row_count);
+
+        // Scroll right until the selected column fits, then fill the remaining width
+        self.column_offset = self.column_offset.min(self.column);
+        while self.column_offset < self.column
+            && self.span_width(self.column_offset..self.column + 1) > width
+        {
+            self.column_offset += 1;
+        }
+        let mut end = self.column_offset;
+        while end < self.widths.len() && self.span_width(self.column_offset..end) < width {
+            end += 1;
+        }
+
+        (rows, self.column_offset..end)
+    }
+
+    /// Gets the width taken by a range of columns, including spacing
+    fn span_width(&self, columns: Range<usize>) -> usize {
+        let count = columns.len();
+        self.widths[columns].iter().sum::<usize>() + count.saturating_sub(1) * COLUMN_SPACING
+    }
+}
+
+/// Flattens a cell onto one line for display in the grid
+pub fn single_line(text: &str) -> Cow<'_, str> {
+    if text.contains(['\n', '\r', '\t']) {
+        Cow::Owned(text.replace("\r\n", "↵").replace(['\n', '\r'], "↵").replace('\t', " "))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::CellValue;
+
+    #[test]
+    fn test_scroll_to_cursor() {
+        let result = QueryResult::new(
+            vec!["id".to_string(), "name".to_string(), "description".to_string()],
+            (0..100)
+                .map(|i| {
+                    [i.to_string(), "x".repeat(60), "line\nbreak".to_string()]
+                        .into_iter()
+                        .map(CellValue::Value)
+                        .collect()
+                })
+                .collect(),
+            None,
+            0,
+        );
+        let mut grid = GridState::new(&result);
+        assert_eq!(grid.widths(), &[2, MAX_COLUMN_WIDTH, 11]);
+
+        let (rows, columns) = grid.scroll_to_cursor(100, 10, 50);
+        assert_eq!((rows, columns), (0..10, 0..3));
+
+        grid.move_page(2, 100);
+        grid.last_column();
+        let (rows, columns) = grid.scroll_to_cursor(100, 10, 50);
+        assert_eq!((rows, columns), (11..21, 2..3));
+
+        grid.last_row(100);
+        let (rows, _) = grid.scroll_to_cursor(100, 10, 50);
+        assert_eq!(rows, 90..100);
+    }
+}
@@ -0,0 +1,272 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// File the query history is persisted to
+pub const HISTORY_FILE: &str = "history.jsonl";
+
+/// A single executed statement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// The executed query text
+    pub query: String,
+    /// When the query was executed
+    pub timestamp: DateTime<Local>,
+    /// Connection profile the query ran against
+    pub profile: Option<String>,
+    /// Execution time in milliseconds
+    pub duration_ms: u128,
+    /// Number of rows returned or affected, if the query succeeded
+    pub row_count: Option<u64>,
+    /// Error message, if the query failed
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Whether the query executed successfully
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Persistent query history with Up/Down recall
+#[derive(Debug, Default)]
+pub struct History {
+    /// Entries, oldest first
+    entries: Vec<HistoryEntry>,
+    /// File new entries are appended to
+    path: Option<PathBuf>,
+    /// Index of the entry being recalled, if any
+    recall_index: Option<usize>,
+    /// Buffer content saved when recall started
+    draft: String,
+}
+
+impl History {
+    /// Loads the history from a JSON-lines file, starting empty if it does not exist
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut entries = Vec::new();
+
+        if path.exists() {
+            let file = std::fs::File::open(path)?;
+            for line in BufReader::new(file).lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str(&line) {
+                    Ok(entry) => entries.push(entry),
+                    Err(e) => warn!("Skipping malformed history entry: {}", e),
+                }
+            }
+        }
+
+        Ok(Self {
+            entries,
+            path: Some(path.to_path_buf()),
+            ..Self::default()
+        })
+    }
+
+    /// Records an executed query and appends it to the history file
+    pub fn push(&mut self, entry: HistoryEntry) {
+        if let Some(path) = &self.path {
+            if let Err(e) = append_entry(path, &entry) {
+                warn!("Failed to write query history: {}", e);
+            }
+        }
+
+        self.entries.push(entry);
+        self.reset_recall();
+    }
+
+    /// Recalls the previous (older) query, saving `current` as the draft on first recall
+    pub fn recall_previous(&mut self, current: &str) -> Option<&str> {
+        let index = match self.recall_index {
+            Some(0) => return None,
+            Some(index) => index - 1,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+        };
+
+        self.recall_index = Some(index);
+        Some(&self.entries[index].query)
+    }
+
+    /// Recalls the next (newer) query, returning the saved draft after the newest entry
+    pub fn recall_next(&mut self) -> Option<String> {
+        let index = self.recall_index?;
+
+        if index + 1 < self.entries.len() {
+            self.recall_index = Some(index + 1);
+            Some(self.entries[index + 1].query.clone())
+        } else {
+            self.recall_index = None;
+            Some(std::mem::take(&mut self.draft))
+        }
+    }
+
+    /// Stops recalling, e.g. after the recalled query has been edited
+    pub fn reset_recall(&mut self) {
+        self.recall_index = None;
+        self.draft.clear();
+    }
+
+    /// Searches the history, newest and best matches first, without duplicate queries
+    pub fn search(&self, pattern: &str) -> Vec<&HistoryEntry> {
+        let mut seen = std::collections::HashSet::new();
+        let mut matches: Vec<(i64, usize, &HistoryEntry)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, entry)| seen.insert(entry.query.as_str()))
+            .filter_map(|(index, entry)| {
+                fuzzy_score(pattern, &entry.query).map(|score| (score, index, entry))
+            })
+            .collect();
+
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+        matches.into_iter().map(|(_, _, entry)| entry).collect()
+    }
+}
+
+fn append_entry(path: &Path, entry: &HistoryEntry) -> Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Scores how well `pattern` matches `text` as a case-insensitive subsequence.
+/// Consecutive matches and matches at word starts score higher; `None` means no match.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut text_chars = text.chars().map(|c| c.to_ascii_lowercase()).enumerate();
+    let mut previous_match: Option<usize> = None;
+    let mut previous_char = ' ';
+
+    for pattern_char in pattern.chars().map(|c| c.to_ascii_lowercase()) {
+        if pattern_char.is_whitespace() {
+            continue;
+        }
+
+        loop {
+            let (index, c) = text_chars.next()?;
+            let at_word_start = !previous_char.is_alphanumeric();
+            previous_char = c;
+
+            if c == pattern_char {
+                score += 1;
+                if previous_match.is_some_and(|previous| previous + 1 == index) {
+                    score += 5;
+                }
+                if at_word_start {
+                    score += 3;
+                }
+                previous_match = Some(index);
+                break;
+            }
+        }
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(query: &str) -> HistoryEntry {
+        HistoryEntry {
+            query: query.to_string(),
+            timestamp: Local::now(),
+            profile: Some("local".to_string()),
+            duration_ms: 3,
+            row_count: Some(1),
+            error: None,
+        }
+    }
+
+    fn temp_history_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ferrum_db-{}-{}.jsonl", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_persistence() {
+        let path = temp_history_file("persistence");
+
+        let mut history = History::load(&path).unwrap();
+        assert!(history.entries.is_empty());
+        history.push(entry("SELECT 1"));
+        history.push(HistoryEntry {
+            error: Some("syntax error".to_string()),
+            row_count: None,
+            ..entry("SELEC 2")
+        });
+
+        // A truncated write and a blank line must not lose the other entries
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "{{\"query\": \"SELECT").unwrap();
+        writeln!(file).unwrap();
+        drop(file);
+        history.push(entry("SELECT 3"));
+
+        let loaded = History::load(&path).unwrap();
+        let queries: Vec<&str> = loaded.entries.iter().map(|entry| entry.query.as_str()).collect();
+        assert_eq!(queries, vec!["SELECT 1", "SELEC 2", "SELECT 3"]);
+        assert!(loaded.entries[0].is_success());
+        assert_eq!(loaded.entries[1].error.as_deref(), Some("syntax error"));
+        assert_eq!(loaded.entries[2].profile.as_deref(), Some("local"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_recall() {
+        let path = temp_history_file("recall");
+        let mut history = History::load(&path).unwrap();
+        assert_eq!(history.recall_previous("draft"), None);
+        assert_eq!(history.recall_next(), None);
+
+        history.push(entry("SELECT 1"));
+        history.push(entry("SELECT 2"));
+
+        // Up walks back from the newest entry and stops at the oldest
+        assert_eq!(history.recall_previous("draft"), Some("SELECT 2"));
+        assert_eq!(history.recall_previous("SELECT 2"), Some("SELECT 1"));
+        assert_eq!(history.recall_previous("SELECT 1"), None);
+
+        // Down walks forward again and ends with the draft
+        assert_eq!(history.recall_next().as_deref(), Some("SELECT 2"));
+        assert_eq!(history.recall_next().as_deref(), Some("draft"));
+        assert_eq!(history.recall_next(), None);
+
+        // Resetting drops the draft, so the next recall saves a new one
+        assert_eq!(history.recall_previous("old draft"), Some("SELECT 2"));
+        history.reset_recall();
+        assert_eq!(history.recall_next(), None);
+        assert_eq!(history.recall_previous("new draft"), Some("SELECT 2"));
+        assert_eq!(history.recall_next().as_deref(), Some("new draft"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("slct", "SELECT * FROM users").is_some());
+        assert!(fuzzy_score("xyz", "SELECT * FROM users").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+
+        let contiguous = fuzzy_score("users", "SELECT * FROM users").unwrap();
+        let scattered = fuzzy_score("users", "UPDATE subscriptions SET enrolled = true, status = 1").unwrap();
+        assert!(contiguous > scattered);
+    }
+}
@@ -1,3 +1,6 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
 /// Available modes for the application
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Mode {
@@ -7,13 +10,72 @@ pub enum Mode {
     Insert,
 }
 
+/// Number of spaces inserted for a tab or an extra indentation level
+const INDENT: &str = "    ";
+
+/// Maximum number of undo snapshots kept
+const UNDO_LIMIT: usize = 200;
+
+/// A position in the buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Position {
+    /// Line index
+    pub line: usize,
+    /// Byte offset within the line, always on a grapheme boundary
+    pub column: usize,
+}
+
+/// Cursor motions supported by the editor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordLeft,
+    WordRight,
+    LineStart,
+    LineEnd,
+    BufferStart,
+    BufferEnd,
+}
+
+/// Kind of the last edit, used to group consecutive edits into one undo step
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EditKind {
+    Insert,
+    Delete,
+    Other,
+}
+
+/// Buffer content and cursor saved for undo/redo
+#[derive(Debug, Clone)]
+struct Snapshot {
+    lines: Vec<String>,
+    cursor: Position,
+}
+
 /// Handles input state for the application
 #[derive(Debug)]
 pub struct InputHandler {
-    /// Current input buffer
-    buffer: String,
+    /// Buffer content, one entry per line
+    lines: Vec<String>,
     /// Cursor position in the buffer
-    cursor_position: usize,
+    cursor: Position,
+    /// Display column the cursor tries to keep when moving vertically
+    preferred_column: Option<usize>,
+    /// Other end of the selection, if text is selected
+    selection_anchor: Option<Position>,
+    /// Snapshots to undo, oldest first
+    undo_stack: Vec<Snapshot>,
+    /// Snapshots to redo, most recently undone last
+    redo_stack: Vec<Snapshot>,
+    /// Kind of the last edit
+    last_edit: Option<EditKind>,
+    /// Text copied or cut most recently
+    register: String,
+    /// First visible line and column of the editor viewport
+    scroll: Position,
     /// Current mode
     mode: Mode,
 }
@@ -21,8 +83,15 @@ pub struct InputHandler {
 impl Default for InputHandler {
     fn default() -> Self {
         Self {
-            buffer: String::new(),
-            cursor_position: 0,
+            lines: vec![String::new()],
+            cursor: Position::default(),
+            preferred_column: None,
+            selection_anchor: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            last_edit: None,
+            register: String::new(),
+            scroll: Position::default(),
             mode: Mode::Normal,
         }
     }
@@ -34,14 +103,24 @@ impl InputHandler {
         Self::default()
     }
 
-    /// Gets the current buffer content
-    pub fn buffer(&self) -> &str {
-        &self.buffer
+    /// Gets the buffer content with lines joined by newlines
+    pub fn buffer(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Gets the buffer lines
+    pub fn lines(&self) -> &[String] {
+        &self.lines
     }
 
     /// Gets the current cursor position
-    pub fn cursor_position(&self) -> usize {
-        self.cursor_position
+    pub fn cursor(&self) -> Position {
+        self.cursor
+    }
+
+    /// Gets the cursor position as a byte offset into `buffer()`
+    pub fn cursor_offset(&self) -> usize {
+        self.lines[..self.cursor.line].iter().map(|line| line.len() + 1).sum::<usize>() + self.cursor.column
     }
 
     /// Gets the current mode
@@ -57,44 +136,526 @@ impl InputHandler {
         };
     }
 
+    /// Whether the cursor is on the first line
+    pub fn is_on_first_line(&self) -> bool {
+        self.cursor.line == 0
+    }
+
+    /// Whether the cursor is on the last line
+    pub fn is_on_last_line(&self) -> bool {
+        self.cursor.line + 1 == self.lines.len()
+    }
+
+    /// Replaces the buffer content, placing the cursor at the end
+    pub fn set_buffer(&mut self, buffer: String) {
+        self.checkpoint(EditKind::Other);
+        self.lines = split_lines(&buffer);
+        self.selection_anchor = None;
+        self.move_cursor(Motion::BufferEnd, false);
+    }
+
     /// Inserts a character at the current cursor position
     pub fn insert_char(&mut self, c: char) {
-        self.buffer.insert(self.cursor_position, c);
-        self.cursor_position += 1;
+        if c == '\n' {
+            self.insert_newline();
+            return;
+        }
+
+        let kind = if c.is_whitespace() { EditKind::Other } else { EditKind::Insert };
+        self.checkpoint(kind);
+        self.delete_selection();
+
+        let line = &mut self.lines[self.cursor.line];
+        line.insert(self.cursor.column, c);
+        self.cursor.column += c.len_utf8();
+        // A combining character joins the grapheme before it, so keep the cursor
+        // on a grapheme boundary
+        self.cursor.column = next_boundary(line, previous_boundary(line, self.cursor.column));
+        self.preferred_column = None;
+    }
+
+    /// Inserts text at the current cursor position, e.g. from a paste
+    pub fn insert_str(&mut self, text: &str) {
+        self.checkpoint(EditKind::Other);
+        self.delete_selection();
+
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let line = &mut self.lines[self.cursor.line];
+        let tail = line.split_off(self.cursor.column);
+
+        let mut inserted = text.split('\n');
+        if let Some(first) = inserted.next() {
+            line.push_str(first);
+            self.cursor.column = line.len();
+        }
+        for next in inserted {
+            self.cursor.line += 1;
+            self.lines.insert(self.cursor.line, next.to_string());
+            self.cursor.column = next.len();
+        }
+        self.lines[self.cursor.line].push_str(&tail);
+        self.preferred_column = None;
+    }
+
+    /// Replaces the `length` bytes before the cursor on its line with `text`, e.g. to complete a word
+    pub fn replace_before_cursor(&mut self, length: usize, text: &str) {
+        self.checkpoint(EditKind::Other);
+        self.selection_anchor = None;
+
+        let start = self.cursor.column.saturating_sub(length);
+        self.lines[self.cursor.line].replace_range(start..self.cursor.column, text);
+        self.cursor.column = start + text.len();
+        self.preferred_column = None;
     }
 
-    /// Deletes the character before the cursor
+    /// Splits the line at the cursor, keeping the current indentation and
+    /// indenting one more level after an opening parenthesis
+    pub fn insert_newline(&mut self) {
+        self.checkpoint(EditKind::Other);
+        self.delete_selection();
+
+        let line = &mut self.lines[self.cursor.line];
+        let tail = line.split_off(self.cursor.column);
+        let mut indent: String = line.chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+        if line.trim_end().ends_with('(') {
+            indent.push_str(INDENT);
+        }
+
+        self.cursor.line += 1;
+        self.cursor.column = indent.len();
+        self.lines.insert(self.cursor.line, indent + tail.trim_start());
+        self.preferred_column = None;
+    }
+
+    /// Inserts one level of indentation
+    pub fn insert_indent(&mut self) {
+        self.insert_str(INDENT);
+    }
+
+    /// Deletes the selection, or the character before the cursor
     pub fn delete_char(&mut self) {
-        if self.cursor_position > 0 {
-            self.cursor_position -= 1;
-            self.buffer.remove(self.cursor_position);
+        self.checkpoint(EditKind::Delete);
+        if self.delete_selection() {
+            return;
+        }
+
+        if self.cursor.column > 0 {
+            let line = &mut self.lines[self.cursor.line];
+            let previous = previous_boundary(line, self.cursor.column);
+            line.replace_range(previous..self.cursor.column, "");
+            self.cursor.column = previous;
+        } else if self.cursor.line > 0 {
+            let line = self.lines.remove(self.cursor.line);
+            self.cursor.line -= 1;
+            self.cursor.column = self.lines[self.cursor.line].len();
+            self.lines[self.cursor.line].push_str(&line);
         }
+        self.preferred_column = None;
     }
 
-    /// Moves the cursor left
-    pub fn move_cursor_left(&mut self) {
-        if self.cursor_position > 0 {
-            self.cursor_position -= 1;
+    /// Deletes the selection, or the character under the cursor
+    pub fn delete_char_forward(&mut self) {
+        self.checkpoint(EditKind::Delete);
+        if self.delete_selection() {
+            return;
+        }
+
+        let line_len = self.lines[self.cursor.line].len();
+        if self.cursor.column < line_len {
+            let line = &mut self.lines[self.cursor.line];
+            let next = next_boundary(line, self.cursor.column);
+            line.replace_range(self.cursor.column..next, "");
+        } else if self.cursor.line + 1 < self.lines.len() {
+            let next = self.lines.remove(self.cursor.line + 1);
+            self.lines[self.cursor.line].push_str(&next);
         }
+        self.preferred_column = None;
     }
 
-    /// Moves the cursor right
-    pub fn move_cursor_right(&mut self) {
-        if self.cursor_position < self.buffer.len() {
-            self.cursor_position += 1;
+    /// Moves the cursor, extending the selection when `select` is set
+    pub fn move_cursor(&mut self, motion: Motion, select: bool) {
+        if select {
+            self.selection_anchor.get_or_insert(self.cursor);
+        } else {
+            self.selection_anchor = None;
+        }
+
+        let line = &self.lines[self.cursor.line];
+        match motion {
+            Motion::Left => {
+                if self.cursor.column > 0 {
+                    self.cursor.column = previous_boundary(line, self.cursor.column);
+                } else if self.cursor.line > 0 {
+                    self.cursor.line -= 1;
+                    self.cursor.column = self.lines[self.cursor.line].len();
+                }
+            }
+            Motion::Right => {
+                if self.cursor.column < line.len() {
+                    self.cursor.column = next_boundary(line, self.cursor.column);
+                } else if self.cursor.line + 1 < self.lines.len() {
+                    self.cursor.line += 1;
+                    self.cursor.column = 0;
+                }
+            }
+            Motion::Up | Motion::Down => {
+                let target = self
+                    .preferred_column
+                    .unwrap_or_else(|| display_width(&line[..self.cursor.column]));
+                if motion == Motion::Up && self.cursor.line > 0 {
+                    self.cursor.line -= 1;
+                } else if motion == Motion::Down && self.cursor.line + 1 < self.lines.len() {
+                    self.cursor.line += 1;
+                }
+                self.cursor.column = byte_offset(&self.lines[self.cursor.line], target);
+                self.preferred_column = Some(target);
+                return;
+            }
+            Motion::WordLeft => self.cursor = self.word_left(),
+            Motion::WordRight => self.cursor = self.word_right(),
+            Motion::LineStart => {
+                // Toggle between the first non-blank character and the start of the line
+                let indent = line.len() - line.trim_start().len();
+                self.cursor.column = if self.cursor.column == indent { 0 } else { indent };
+            }
+            Motion::LineEnd => self.cursor.column = line.len(),
+            Motion::BufferStart => self.cursor = Position::default(),
+            Motion::BufferEnd => {
+                self.cursor.line = self.lines.len() - 1;
+                self.cursor.column = self.lines[self.cursor.line].len();
+            }
         }
+        self.preferred_column = None;
     }
 
-    /// Clears the input buffer
-    pub fn clear_buffer(&mut self) {
-        self.buffer.clear();
-        self.cursor_position = 0;
+    /// Gets the selected range, start first
+    pub fn selection(&self) -> Option<(Position, Position)> {
+        let anchor = self.selection_anchor?;
+        if anchor == self.cursor {
+            return None;
+        }
+        Some((anchor.min(self.cursor), anchor.max(self.cursor)))
     }
 
-    /// Returns and clears the buffer
-    pub fn take_buffer(&mut self) -> String {
-        let buffer = std::mem::take(&mut self.buffer);
-        self.cursor_position = 0;
-        buffer
+    /// Gets the selected text
+    pub fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.selection()?;
+        if start.line == end.line {
+            return Some(self.lines[start.line][start.column..end.column].to_string());
+        }
+
+        let mut text = self.lines[start.line][start.column..].to_string();
+        for line in &self.lines[start.line + 1..end.line] {
+            text.push('\n');
+            text.push_str(line);
+        }
+        text.push('\n');
+        text.push_str(&self.lines[end.line][..end.column]);
+        Some(text)
     }
-}
\ No newline at end of file
+
+    /// Selects the whole buffer
+    pub fn select_all(&mut self) {
+        self.selection_anchor = Some(Position::default());
+        self.move_cursor(Motion::BufferEnd, true);
+    }
+
+    /// Copies the selection, or the current line if nothing is selected
+    pub fn copy(&mut self) {
+        self.register = self
+            .selected_text()
+            .unwrap_or_else(|| format!("{}\n", self.lines[self.cursor.line]));
+    }
+
+    /// Cuts the selection, or the current line if nothing is selected
+    pub fn cut(&mut self) {
+        self.copy();
+        self.checkpoint(EditKind::Other);
+        if self.delete_selection() {
+            return;
+        }
+
+        if self.lines.len() > 1 {
+            self.lines.remove(self.cursor.line);
+            self.cursor.line = self.cursor.line.min(self.lines.len() - 1);
+        } else {
+            self.lines[0].clear();
+        }
+        self.cursor.column = 0;
+    }
+
+    /// Pastes the most recently copied or cut text
+    pub fn paste(&mut self) {
+        if !self.register.is_empty() {
+            let text = self.register.clone();
+            self.insert_str(&text);
+        }
+    }
+
+    /// Undoes the last edit
+    pub fn undo(&mut self) {
+        if let Some(snapshot) = self.undo_stack.pop() {
+            let current = self.snapshot();
+            self.redo_stack.push(current);
+            self.restore(snapshot);
+        }
+    }
+
+    /// Redoes the last undone edit
+    pub fn redo(&mut self) {
+        if let Some(snapshot) = self.redo_stack.pop() {
+            let current = self.snapshot();
+            self.undo_stack.push(current);
+            self.restore(snapshot);
+        }
+    }
+
+    /// Adjusts the viewport so the cursor is visible and returns its top-left position,
+    /// with the column in display columns
+    pub fn scroll_to_cursor(&mut self, height: usize, width: usize) -> Position {
+        let height = height.max(1);
+        let width = width.max(1);
+
+        if self.cursor.line < self.scroll.line {
+            self.scroll.line = self.cursor.line;
+        } else if self.cursor.line >= self.scroll.line + height {
+            self.scroll.line = self.cursor.line + 1 - height;
+        }
+
+        // Horizontal scrolling is tracked in display columns, keeping the whole
+        // grapheme under the cursor visible
+        let line = &self.lines[self.cursor.line];
+        let column = display_width(&line[..self.cursor.column]);
+        let cursor_width = line[self.cursor.column..]
+            .graphemes(true)
+            .next()
+            .map(display_width)
+            .unwrap_or(1)
+            .max(1);
+        if column < self.scroll.column {
+            self.scroll.column = column;
+        } else if column + cursor_width > self.scroll.column + width {
+            self.scroll.column = (column + cursor_width).saturating_sub(width);
+        }
+
+        self.scroll
+    }
+
+    /// Records the buffer before an edit, grouping consecutive edits of the same kind
+    fn checkpoint(&mut self, kind: EditKind) {
+        let grouped = kind != EditKind::Other && self.last_edit == Some(kind) && self.selection().is_none();
+        self.last_edit = Some(kind);
+        if grouped {
+            return;
+        }
+
+        let snapshot = self.snapshot();
+        self.undo_stack.push(snapshot);
+        if self.undo_stack.len() > UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            lines: self.lines.clone(),
+            cursor: self.cursor,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.lines = snapshot.lines;
+        self.cursor = snapshot.cursor;
+        self.selection_anchor = None;
+        self.preferred_column = None;
+        self.last_edit = None;
+    }
+
+    /// Deletes the selected text, returning whether anything was selected
+    fn delete_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection() else {
+            self.selection_anchor = None;
+            return false;
+        };
+
+        let tail = self.lines[end.line][end.column..].to_string();
+        self.lines.drain(start.line + 1..=end.line);
+        let line = &mut self.lines[start.line];
+        line.truncate(start.column);
+        line.push_str(&tail);
+
+        self.cursor = start;
+        self.selection_anchor = None;
+        true
+    }
+
+    /// Finds the start of the previous word, crossing line boundaries
+    fn word_left(&self) -> Position {
+        let Position { mut line, mut column } = self.cursor;
+        if column == 0 {
+            if line == 0 {
+                return self.cursor;
+            }
+            line -= 1;
+            column = self.lines[line].len();
+        }
+
+        let text = &self.lines[line][..column];
+        let graphemes: Vec<(usize, &str)> = text.grapheme_indices(true).collect();
+        let mut index = graphemes.len();
+        while index > 0 && is_blank(graphemes[index - 1].1) {
+            index -= 1;
+        }
+        if index > 0 {
+            let word = is_word(graphemes[index - 1].1);
+            while index > 0 && !is_blank(graphemes[index - 1].1) && is_word(graphemes[index - 1].1) == word {
+                index -= 1;
+            }
+        }
+
+        Position {
+            line,
+            column: graphemes.get(index).map(|(offset, _)| *offset).unwrap_or(text.len()),
+        }
+    }
+
+    /// Finds the start of the next word, crossing line boundaries
+    fn word_right(&self) -> Position {
+        let Position { line, column } = self.cursor;
+        let text = &self.lines[line];
+        if column == text.len() {
+            if line + 1 < self.lines.len() {
+                return Position { line: line + 1, column: 0 };
+            }
+            return self.cursor;
+        }
+
+        let mut graphemes = text[column..].grapheme_indices(true).peekable();
+        if let Some((_, first)) = graphemes.next() {
+            if !is_blank(first) {
+                let word = is_word(first);
+                while graphemes.next_if(|(_, g)| !is_blank(g) && is_word(g) == word).is_some() {}
+            }
+        }
+        while graphemes.next_if(|(_, g)| is_blank(g)).is_some() {}
+
+        Position {
+            line,
+            column: graphemes.peek().map(|(offset, _)| column + offset).unwrap_or(text.len()),
+        }
+    }
+}
+
+/// Splits text into editor lines, always returning at least one line
+fn split_lines(text: &str) -> Vec<String> {
+    text.replace("\r\n", "\n").split('\n').map(String::from).collect()
+}
+
+/// Whether a grapheme is part of an identifier, judged by its base character
+fn is_word(grapheme: &str) -> bool {
+    grapheme.chars().next().is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn is_blank(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+/// Gets the number of terminal columns a string occupies in the editor, where
+/// control characters such as tabs are drawn as a single space
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(|grapheme| grapheme.width().max(1)).sum()
+}
+
+/// Gets the byte offset of the grapheme before `column`
+fn previous_boundary(line: &str, column: usize) -> usize {
+    line[..column].grapheme_indices(true).next_back().map(|(offset, _)| offset).unwrap_or(0)
+}
+
+/// Gets the byte offset of the grapheme after `column`
+fn next_boundary(line: &str, column: usize) -> usize {
+    line[column..].graphemes(true).next().map(|g| column + g.len()).unwrap_or(line.len())
+}
+
+/// Converts a display column into the byte offset of the grapheme covering it,
+/// clamped to the line length
+fn byte_offset(line: &str, target: usize) -> usize {
+    let mut width = 0;
+    for (offset, grapheme) in line.grapheme_indices(true) {
+        width += display_width(grapheme);
+        if width > target {
+            return offset;
+        }
+    }
+    line.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiline_editing_and_undo() {
+        let mut input = InputHandler::new();
+        input.insert_str("SELECT (");
+        input.insert_newline();
+        input.insert_str("1");
+        assert_eq!(input.buffer(), "SELECT (\n    1");
+
+        input.move_cursor(Motion::Up, false);
+        input.move_cursor(Motion::LineStart, true);
+        assert_eq!(input.selected_text().as_deref(), Some("SELEC"));
+
+        input.undo();
+        assert_eq!(input.buffer(), "SELECT (\n    ");
+        input.redo();
+        assert_eq!(input.buffer(), "SELECT (\n    1");
+    }
+
+    #[test]
+    fn test_word_motions() {
+        let mut input = InputHandler::new();
+        input.set_buffer("SELECT id, name\nFROM users".to_string());
+
+        input.move_cursor(Motion::BufferStart, false);
+        input.move_cursor(Motion::WordRight, false);
+        assert_eq!(input.cursor(), Position { line: 0, column: 7 });
+        input.move_cursor(Motion::WordRight, false);
+        assert_eq!(input.cursor(), Position { line: 0, column: 9 });
+
+        input.move_cursor(Motion::BufferEnd, false);
+        input.move_cursor(Motion::WordLeft, false);
+        assert_eq!(input.cursor(), Position { line: 1, column: 5 });
+    }
+
+    #[test]
+    fn test_unicode_graphemes_and_width() {
+        let mut input = InputHandler::new();
+        input.insert_str("'cafe");
+        input.insert_char('\u{301}');
+        input.insert_str("' 日本");
+        assert_eq!(input.buffer(), "'cafe\u{301}' 日本");
+
+        // A combining accent moves and deletes together with its base letter
+        input.move_cursor(Motion::LineStart, false);
+        input.move_cursor(Motion::WordRight, false);
+        input.move_cursor(Motion::Right, false);
+        input.move_cursor(Motion::Right, false);
+        input.move_cursor(Motion::Right, false);
+        input.move_cursor(Motion::Right, false);
+        input.delete_char();
+        assert_eq!(input.buffer(), "'caf' 日本");
+
+        // Wide characters take two columns when moving between lines
+        input.set_buffer("日本語\nabcdef".to_string());
+        input.move_cursor(Motion::LineStart, false);
+        input.move_cursor(Motion::Right, false);
+        input.move_cursor(Motion::Up, false);
+        assert_eq!(input.cursor(), Position { line: 0, column: 0 });
+        input.move_cursor(Motion::Right, false);
+        input.move_cursor(Motion::Right, false);
+        input.move_cursor(Motion::Down, false);
+        assert_eq!(input.cursor(), Position { line: 1, column: 4 });
+        assert_eq!(display_width("日本語"), 6);
+    }
+}
@@ -1,14 +1,36 @@
 use anyhow::Result;
 use std::time::Duration;
 use crossterm::event::{self, Event, KeyCode, KeyModifiers};
-use ratatui::Terminal;
-use tracing::debug;
+use ratatui::{backend::CrosstermBackend, Terminal};
+use std::io::Stdout;
+use tracing::{debug, warn};
+
+use crate::config::AppConfig;
+use crate::database::{quote_identifier, CellValue};
 
 mod state;
+pub mod browse;
+pub mod clipboard;
+pub mod completion;
+pub mod edit;
+pub mod export;
+pub mod grid;
+pub mod history;
 pub mod input;
+pub mod plan;
 pub mod query;
-pub use state::AppState;
+pub mod record;
+pub mod schema_tree;
+pub mod view;
+pub use state::{AppState, ConfirmAction, Focus, Popup};
 pub use input::Mode;
+use input::Motion;
+use browse::BrowseClause;
+use clipboard::CopyTarget;
+use schema_tree::NodeKind;
+
+/// Lines scrolled by PageUp/PageDown in the record view
+const RECORD_PAGE_LINES: usize = 10;
 
 /// The main application struct for FerrumDB
 pub struct App {
@@ -20,30 +42,35 @@ pub struct App {
 
 impl App {
     /// Creates a new instance of the application
-    pub fn new() -> Result<Self> {
+    pub fn new(config: AppConfig) -> Result<Self> {
         Ok(Self {
-            state: AppState::new(),
+            state: AppState::new(config),
             should_quit: false,
         })
     }
 
-    /// Initializes the database connection
-    pub async fn init_database(&mut self, config: crate::database::DatabaseConfig) -> Result<()> {
-        self.state.init_database(config).await
+    /// Connects to the database using a named connection profile
+    pub async fn connect(&mut self, profile: &str) -> Result<()> {
+        self.state.connect_profile(profile).await
     }
 
     /// Runs the main application loop
     pub async fn run(&mut self) -> Result<()> {
         // Initialize terminal
         self.init_terminal()?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(std::io::stdout()))?;
 
         // Main event loop
         while !self.should_quit {
-            self.draw()?;
+            self.state.poll_running_query().await;
+            self.state.poll_running_export().await;
+            self.draw(&mut terminal)?;
 
             if let Ok(true) = event::poll(Duration::from_millis(100)) {
-                if let Event::Key(key) = event::read()? {
-                    self.handle_input(key.code, key.modifiers).await?;
+                match event::read()? {
+                    Event::Key(key) => self.handle_input(key.code, key.modifiers).await?,
+                    Event::Paste(text) => self.handle_paste(&text),
+                    _ => {}
                 }
             }
         }
@@ -53,8 +80,37 @@ impl App {
         Ok(())
     }
 
+    /// Inserts pasted text into the editor
+    fn handle_paste(&mut self, text: &str) {
+        if self.state.popup.is_none() {
+            if self.state.mode() == Mode::Normal {
+                self.state.input.toggle_mode();
+            }
+            self.state.focus = Focus::Editor;
+            self.state.input.insert_str(text);
+            self.state.history.reset_recall();
+        }
+    }
+
     /// Handles keyboard input based on current mode
     async fn handle_input(&mut self, key: KeyCode, modifiers: KeyModifiers) -> Result<()> {
+        if self.state.popup.is_some() {
+            self.handle_popup(key, modifiers).await;
+            return Ok(());
+        }
+
+        // Esc cancels a running export before anything else it does
+        if self.state.running_export.is_some() && key == KeyCode::Esc {
+            self.state.cancel_export().await;
+            return Ok(());
+        }
+
+        // Ctrl+C cancels a running query before it quits or copies
+        if self.state.running_query.is_some() && key == KeyCode::Char('c') && modifiers == KeyModifiers::CONTROL {
+            self.state.cancel_query().await;
+            return Ok(());
+        }
+
         match self.state.mode() {
             Mode::Normal => self.handle_normal_mode(key, modifiers).await,
             Mode::Insert => self.handle_insert_mode(key, modifiers).await,
@@ -65,74 +121,687 @@ impl App {
     async fn handle_normal_mode(&mut self, key: KeyCode, modifiers: KeyModifiers) -> Result<()> {
         match (key, modifiers) {
             (KeyCode::Char('q'), _) => {
-                self.should_quit = true;
+                self.request_quit();
             }
             (KeyCode::Char('i'), _) => {
                 self.state.input.toggle_mode();
+                self.state.focus = Focus::Editor;
             }
             (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
-                self.should_quit = true;
+                self.request_quit();
+            }
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                self.open_history_search();
+            }
+            (KeyCode::Char('l'), KeyModifiers::CONTROL) => {
+                self.state.refresh_catalog().await;
+            }
+            (KeyCode::Char('T'), _) => {
+                self.state.toggle_transaction_mode().await;
+            }
+            (KeyCode::Char('C'), _) => {
+                self.state.commit_transaction().await;
+            }
+            (KeyCode::Char('R'), _) => {
+                self.state.rollback_transaction().await;
+            }
+            (KeyCode::Char('S'), _) => {
+                self.state.create_savepoint().await;
+            }
+            (KeyCode::Char('U'), _) => {
+                self.state.rollback_to_savepoint().await;
             }
             (KeyCode::Char('r'), _) => {
                 // Clear current results
-                self.state.query_result = None;
+                self.state.close_result_cursor().await;
+                self.state.result_tabs.clear();
                 self.state.set_status("Results cleared".to_string());
             }
+            (KeyCode::Char('c'), _) => {
+                // Open the connection picker on the active profile
+                let selected = self
+                    .state
+                    .active_profile
+                    .as_ref()
+                    .and_then(|active| self.state.config.profile_names().iter().position(|name| name == active))
+                    .unwrap_or(0);
+                self.state.popup = Some(Popup::ConnectionPicker { selected });
+            }
             (KeyCode::Char('d'), _) => {
-                // Toggle database list (to be implemented)
-                self.state.set_status("Database list toggle (not implemented)".to_string());
+                // Toggle database list
+                self.state.show_sidebar = !self.state.show_sidebar;
+                if !self.state.show_sidebar && self.state.focus == Focus::Sidebar {
+                    self.state.focus = Focus::Results;
+                }
+            }
+            (KeyCode::Enter, _) if self.state.focus == Focus::Editor => {
+                self.execute_editor_query().await;
+            }
+            (KeyCode::F(6), _) => {
+                self.explain_editor_query(false).await;
+            }
+            (KeyCode::F(7), _) => {
+                self.explain_editor_query(true).await;
+            }
+            (KeyCode::Char('+'), _) => {
+                self.state.resize_editor(1);
+            }
+            (KeyCode::Char('-'), _) => {
+                self.state.resize_editor(-1);
+            }
+            (KeyCode::Tab, _) => {
+                self.state.focus = self.state.focus.next();
+                if !self.state.show_sidebar && self.state.focus == Focus::Sidebar {
+                    self.state.focus = self.state.focus.next();
+                }
+            }
+            (KeyCode::BackTab, _) => {
+                self.state.focus = self.state.focus.previous();
+                if !self.state.show_sidebar && self.state.focus == Focus::Sidebar {
+                    self.state.focus = self.state.focus.previous();
+                }
+            }
+            _ if self.state.focus == Focus::Sidebar => {
+                self.handle_sidebar_keys(key).await;
+            }
+            _ if self.state.focus == Focus::Results => {
+                self.handle_results_keys(key, modifiers).await;
             }
             _ => {}
         }
         Ok(())
     }
 
+    /// Quits, asking for confirmation first while a transaction is open
+    fn request_quit(&mut self) {
+        if self.state.has_open_transaction() {
+            self.state.popup = Some(Popup::Confirm {
+                message: "A transaction is open. Roll it back and quit?".to_string(),
+                action: ConfirmAction::Quit,
+            });
+        } else {
+            self.should_quit = true;
+        }
+    }
+
+    /// Handles navigation keys while the results grid is focused, loading
+    /// more rows when the selection gets close to the last loaded row
+    async fn handle_results_keys(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        if self.handle_plan_keys(key, modifiers) {
+            return;
+        }
+        match key {
+            KeyCode::Char('[') => return self.state.select_tab(-1),
+            KeyCode::Char(']') => return self.state.select_tab(1),
+            KeyCode::Char('y') => {
+                self.state.popup = Some(Popup::Copy);
+                return;
+            }
+            KeyCode::Char('e') => return self.state.open_export(false),
+            KeyCode::Char('E') => return self.state.open_export(true),
+            KeyCode::Char('n') if self.state.browsing().is_some() => return self.state.browse_page(1).await,
+            KeyCode::Char('p') if self.state.browsing().is_some() => return self.state.browse_page(-1).await,
+            KeyCode::Char('w') => return self.state.open_browse_clause(BrowseClause::Where),
+            KeyCode::Char('o') => return self.state.open_browse_clause(BrowseClause::OrderBy),
+            KeyCode::Char('=') if self.state.browsing().is_some() => return self.state.browse_matching_cell().await,
+            KeyCode::Char('m') => return self.state.toggle_edit_mode().await,
+            KeyCode::Char('I') => return self.state.open_result_structure().await,
+            KeyCode::Enter if self.state.editing() => return self.state.open_cell_editor(),
+            KeyCode::Char('A') if self.state.editing() => return self.state.add_edited_row(),
+            KeyCode::Char('D') if self.state.editing() => return self.state.toggle_deleted_row(),
+            KeyCode::Backspace if self.state.editing() => return self.state.revert_edited_cell(),
+            KeyCode::Char('P') if self.state.editing() => return self.state.open_edit_preview(),
+            KeyCode::Char('s') => return self.state.sort_by_column(false),
+            KeyCode::Char('a') => return self.state.sort_by_column(true),
+            KeyCode::Char('/') => {
+                // Reopen the bar on the active filter, to edit it
+                if let Some(result) = self.state.query_result() {
+                    let (pattern, column) = result
+                        .filter
+                        .as_ref()
+                        .map_or((String::new(), None), |filter| (filter.pattern.clone(), filter.column));
+                    self.state.popup = Some(Popup::Filter { pattern, column });
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        let Some(row_count) = self.state.query_result().map(|result| result.visible_count()) else {
+            return;
+        };
+        if matches!(key, KeyCode::Enter | KeyCode::Char('x')) && row_count > 0 {
+            self.state.popup = Some(Popup::Record { scroll: 0 });
+            return;
+        }
+        let grid = &mut self.state.result_tabs[self.state.selected_tab].grid;
+
+        match (key, modifiers) {
+            (KeyCode::Char('j'), _) | (KeyCode::Down, _) => grid.move_row(1, row_count),
+            (KeyCode::Char('k'), _) | (KeyCode::Up, _) => grid.move_row(-1, row_count),
+            (KeyCode::Char('l'), _) | (KeyCode::Right, _) => grid.move_column(1),
+            (KeyCode::Char('h'), _) | (KeyCode::Left, _) => grid.move_column(-1),
+            (KeyCode::Char('f'), KeyModifiers::CONTROL) | (KeyCode::PageDown, _) => grid.move_page(1, row_count),
+            (KeyCode::Char('b'), KeyModifiers::CONTROL) | (KeyCode::PageUp, _) => grid.move_page(-1, row_count),
+            (KeyCode::Char('g'), _) | (KeyCode::Home, _) => grid.first_row(),
+            (KeyCode::Char('G'), _) | (KeyCode::End, _) => grid.last_row(row_count),
+            (KeyCode::Char('0'), _) | (KeyCode::Char('^'), _) => grid.first_column(),
+            (KeyCode::Char('$'), _) => grid.last_column(),
+            (KeyCode::Char('v'), _) => grid.toggle_selection(),
+            (KeyCode::Esc, _) => grid.clear_selection(),
+            _ => {}
+        }
+
+        if self.state.result_cursor.is_some() && grid.is_near_end(row_count) {
+            self.state.load_more_rows().await;
+        }
+    }
+
+    /// Handles keys moving through the plan tree shown in place of the grid,
+    /// returning whether the key was used
+    fn handle_plan_keys(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
+        let Some(plan) = self.state.result_tabs.get_mut(self.state.selected_tab).and_then(|tab| tab.plan.as_mut()) else {
+            return false;
+        };
+
+        match (key, modifiers) {
+            (KeyCode::Char('j'), _) | (KeyCode::Down, _) => plan.move_selection(1),
+            (KeyCode::Char('k'), _) | (KeyCode::Up, _) => plan.move_selection(-1),
+            (KeyCode::Char('f'), KeyModifiers::CONTROL) | (KeyCode::PageDown, _) => {
+                plan.move_selection(RECORD_PAGE_LINES as isize)
+            }
+            (KeyCode::Char('b'), KeyModifiers::CONTROL) | (KeyCode::PageUp, _) => {
+                plan.move_selection(-(RECORD_PAGE_LINES as isize))
+            }
+            (KeyCode::Char('g'), _) | (KeyCode::Home, _) => plan.select_edge(false),
+            (KeyCode::Char('G'), _) | (KeyCode::End, _) => plan.select_edge(true),
+            (KeyCode::Char('l'), _) | (KeyCode::Right, _) => plan.expand(),
+            (KeyCode::Char('h'), _) | (KeyCode::Left, _) => plan.collapse(),
+            (KeyCode::Enter, _) | (KeyCode::Char(' '), _) => plan.toggle(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Handles keys in the record view: scrolling through the fields of the
+    /// selected row and moving to the next or previous row
+    async fn handle_record_view(&mut self, key: KeyCode, modifiers: KeyModifiers, scroll: usize) {
+        let Some(row_count) = self.state.query_result().map(|result| result.visible_count()) else {
+            self.state.popup = None;
+            return;
+        };
+        let grid = &mut self.state.result_tabs[self.state.selected_tab].grid;
+
+        let scroll = match (key, modifiers) {
+            (KeyCode::Esc, _) | (KeyCode::Char('q'), _) | (KeyCode::Char('x'), _) | (KeyCode::Enter, _) => {
+                self.state.popup = None;
+                return;
+            }
+            (KeyCode::Char('j'), _) | (KeyCode::Down, _) => scroll + 1,
+            (KeyCode::Char('k'), _) | (KeyCode::Up, _) => scroll.saturating_sub(1),
+            (KeyCode::Char('f'), KeyModifiers::CONTROL) | (KeyCode::PageDown, _) | (KeyCode::Char(' '), _) => {
+                scroll + RECORD_PAGE_LINES
+            }
+            (KeyCode::Char('b'), KeyModifiers::CONTROL) | (KeyCode::PageUp, _) => scroll.saturating_sub(RECORD_PAGE_LINES),
+            (KeyCode::Char('g'), _) | (KeyCode::Home, _) => 0,
+            (KeyCode::Char('n'), _) | (KeyCode::Char('l'), _) | (KeyCode::Right, _) => {
+                grid.move_row(1, row_count);
+                0
+            }
+            (KeyCode::Char('p'), _) | (KeyCode::Char('h'), _) | (KeyCode::Left, _) => {
+                grid.move_row(-1, row_count);
+                0
+            }
+            _ => scroll,
+        };
+
+        if self.state.result_cursor.is_some() && grid.is_near_end(row_count) {
+            self.state.load_more_rows().await;
+        }
+        self.state.popup = Some(Popup::Record { scroll });
+    }
+
+    /// Handles input while a popup is open
+    async fn handle_popup(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        let Some(popup) = self.state.popup.clone() else {
+            return;
+        };
+
+        match popup {
+            Popup::ConnectionPicker { selected } => {
+                let profiles = self.state.config.profile_names();
+                match key {
+                    KeyCode::Esc | KeyCode::Char('q') => {
+                        self.state.popup = None;
+                    }
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        let selected = (selected + 1).min(profiles.len().saturating_sub(1));
+                        self.state.popup = Some(Popup::ConnectionPicker { selected });
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        let selected = selected.saturating_sub(1);
+                        self.state.popup = Some(Popup::ConnectionPicker { selected });
+                    }
+                    KeyCode::Enter => {
+                        self.state.popup = None;
+                        if let Some(name) = profiles.get(selected) {
+                            match self.state.connect_profile(name).await {
+                                Ok(()) => self.state.set_status(format!("Connected to {}", name)),
+                                Err(e) => self.state.set_error(format!("Failed to connect to {}: {}", name, e)),
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Popup::HistorySearch { pattern, selected } => {
+                self.handle_history_search(key, modifiers, pattern, selected);
+            }
+            Popup::Export { mut path, full } => match key {
+                KeyCode::Enter => {
+                    self.state.popup = None;
+                    self.state.export_result(path, full).await;
+                }
+                KeyCode::Esc => {
+                    self.state.popup = None;
+                }
+                KeyCode::Backspace => {
+                    path.pop();
+                    self.state.popup = Some(Popup::Export { path, full });
+                }
+                KeyCode::Char('u') if modifiers == KeyModifiers::CONTROL => {
+                    self.state.popup = Some(Popup::Export { path: String::new(), full });
+                }
+                KeyCode::Char(c) => {
+                    path.push(c);
+                    self.state.popup = Some(Popup::Export { path, full });
+                }
+                _ => {}
+            },
+            Popup::Record { scroll } => {
+                self.handle_record_view(key, modifiers, scroll).await;
+            }
+            Popup::BrowseClause { clause, mut text } => match (key, modifiers) {
+                (KeyCode::Enter, _) => {
+                    self.state.popup = None;
+                    self.state.set_browse_clause(clause, text).await;
+                }
+                (KeyCode::Esc, _) => {
+                    self.state.popup = None;
+                }
+                (KeyCode::Backspace, _) => {
+                    text.pop();
+                    self.state.popup = Some(Popup::BrowseClause { clause, text });
+                }
+                (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
+                    self.state.popup = Some(Popup::BrowseClause { clause, text: String::new() });
+                }
+                (KeyCode::Char(c), _) => {
+                    text.push(c);
+                    self.state.popup = Some(Popup::BrowseClause { clause, text });
+                }
+                _ => {}
+            },
+            Popup::EditCell { mut text } => match (key, modifiers) {
+                (KeyCode::Enter, _) => {
+                    self.state.popup = None;
+                    self.state.set_edited_cell(CellValue::Value(text));
+                }
+                (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+                    self.state.popup = None;
+                    self.state.set_edited_cell(CellValue::Null);
+                }
+                (KeyCode::Esc, _) => {
+                    self.state.popup = None;
+                }
+                (KeyCode::Backspace, _) => {
+                    text.pop();
+                    self.state.popup = Some(Popup::EditCell { text });
+                }
+                (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
+                    self.state.popup = Some(Popup::EditCell { text: String::new() });
+                }
+                (KeyCode::Char(c), _) => {
+                    text.push(c);
+                    self.state.popup = Some(Popup::EditCell { text });
+                }
+                _ => {}
+            },
+            Popup::EditPreview { scroll } => match key {
+                KeyCode::Enter | KeyCode::Char('y') => {
+                    self.state.popup = None;
+                    self.state.apply_edits().await;
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.state.popup = Some(Popup::EditPreview { scroll: scroll + 1 });
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.state.popup = Some(Popup::EditPreview { scroll: scroll.saturating_sub(1) });
+                }
+                _ => {
+                    self.state.popup = None;
+                }
+            },
+            Popup::Structure { ddl, scroll } => {
+                let scroll = match (key, modifiers) {
+                    (KeyCode::Esc, _) | (KeyCode::Char('q'), _) | (KeyCode::Char('I'), _) => {
+                        self.state.popup = None;
+                        return;
+                    }
+                    (KeyCode::Tab, _) | (KeyCode::BackTab, _) => {
+                        self.state.popup = Some(Popup::Structure { ddl: !ddl, scroll: 0 });
+                        return;
+                    }
+                    (KeyCode::Char('y'), _) => {
+                        self.state.copy_ddl();
+                        scroll
+                    }
+                    (KeyCode::Char('j'), _) | (KeyCode::Down, _) => scroll + 1,
+                    (KeyCode::Char('k'), _) | (KeyCode::Up, _) => scroll.saturating_sub(1),
+                    (KeyCode::Char('f'), KeyModifiers::CONTROL) | (KeyCode::PageDown, _) | (KeyCode::Char(' '), _) => {
+                        scroll + RECORD_PAGE_LINES
+                    }
+                    (KeyCode::Char('b'), KeyModifiers::CONTROL) | (KeyCode::PageUp, _) => {
+                        scroll.saturating_sub(RECORD_PAGE_LINES)
+                    }
+                    (KeyCode::Char('g'), _) | (KeyCode::Home, _) => 0,
+                    // Drawing clamps the offset to the last page
+                    (KeyCode::Char('G'), _) | (KeyCode::End, _) => usize::MAX,
+                    _ => scroll,
+                };
+                self.state.popup = Some(Popup::Structure { ddl, scroll });
+            }
+            Popup::Filter { mut pattern, mut column } => {
+                match (key, modifiers) {
+                    (KeyCode::Enter, _) => {
+                        self.state.popup = None;
+                        return;
+                    }
+                    (KeyCode::Esc, _) => {
+                        self.state.popup = None;
+                        self.state.filter_rows("", None);
+                        return;
+                    }
+                    (KeyCode::Tab, _) => {
+                        column = match column {
+                            Some(_) => None,
+                            None => Some(self.state.result_tabs[self.state.selected_tab].grid.column()),
+                        };
+                    }
+                    (KeyCode::Backspace, _) => {
+                        pattern.pop();
+                    }
+                    (KeyCode::Char('u'), KeyModifiers::CONTROL) => pattern.clear(),
+                    (KeyCode::Char(c), _) => pattern.push(c),
+                    _ => return,
+                }
+                self.state.filter_rows(&pattern, column);
+                self.state.popup = Some(Popup::Filter { pattern, column });
+            }
+            Popup::Copy => {
+                let target = match key {
+                    KeyCode::Char('c') | KeyCode::Char('y') => Some(CopyTarget::Cell),
+                    KeyCode::Char('r') => Some(CopyTarget::Row),
+                    KeyCode::Char('l') => Some(CopyTarget::Column),
+                    KeyCode::Char('s') | KeyCode::Char('v') => Some(CopyTarget::Selection),
+                    KeyCode::Tab => {
+                        self.state.copy_format = self.state.copy_format.next();
+                        None
+                    }
+                    _ => {
+                        self.state.popup = None;
+                        None
+                    }
+                };
+                if let Some(target) = target {
+                    self.state.popup = None;
+                    self.state.copy_cells(target);
+                }
+            }
+            Popup::Confirm { action, .. } => match key {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                    self.state.popup = None;
+                    self.confirm(action).await;
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                    self.state.popup = None;
+                }
+                _ => {}
+            },
+        }
+    }
+
+    /// Carries out an action accepted in a confirmation popup
+    async fn confirm(&mut self, action: ConfirmAction) {
+        match action {
+            ConfirmAction::Quit => {
+                if let Some(session) = &self.state.session {
+                    if let Err(e) = session.rollback().await {
+                        warn!("Failed to roll back the open transaction: {}", e);
+                    }
+                }
+                self.should_quit = true;
+            }
+            ConfirmAction::Execute(query) => {
+                self.state.run_query(query).await;
+            }
+            ConfirmAction::DiscardEdits => {
+                self.state.discard_edits();
+            }
+        }
+    }
+
+    /// Opens the fuzzy history search popup
+    fn open_history_search(&mut self) {
+        self.state.popup = Some(Popup::HistorySearch {
+            pattern: String::new(),
+            selected: 0,
+        });
+    }
+
+    /// Handles input in the history search popup
+    fn handle_history_search(&mut self, key: KeyCode, modifiers: KeyModifiers, mut pattern: String, mut selected: usize) {
+        let match_count = self.state.history.search(&pattern).len();
+
+        match (key, modifiers) {
+            (KeyCode::Esc, _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                self.state.popup = None;
+                return;
+            }
+            (KeyCode::Enter, _) => {
+                if let Some(entry) = self.state.history.search(&pattern).get(selected) {
+                    let query = entry.query.clone();
+                    self.state.input.set_buffer(query);
+                    if self.state.mode() == Mode::Normal {
+                        self.state.input.toggle_mode();
+                    }
+                    self.state.focus = Focus::Editor;
+                }
+                self.state.popup = None;
+                return;
+            }
+            (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+                selected = selected.saturating_sub(1);
+            }
+            (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) | (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                selected = (selected + 1).min(match_count.saturating_sub(1));
+            }
+            (KeyCode::Backspace, _) => {
+                pattern.pop();
+                selected = 0;
+            }
+            (KeyCode::Char(c), _) => {
+                pattern.push(c);
+                selected = 0;
+            }
+            _ => {}
+        }
+
+        self.state.popup = Some(Popup::HistorySearch { pattern, selected });
+    }
+
+    /// Handles schema browser navigation in normal mode
+    async fn handle_sidebar_keys(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.state.schema_tree.select_next();
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.state.schema_tree.select_previous();
+            }
+            KeyCode::Enter => {
+                // Tables, views and materialized views open their rows
+                match self.state.schema_tree.selected_node().map(|node| node.kind.clone()) {
+                    Some(NodeKind::Object { schema, name, kind }) if kind.has_columns() => {
+                        self.state.open_table(schema, name).await;
+                    }
+                    _ => self.state.toggle_tree_node().await,
+                }
+            }
+            KeyCode::Char('l') | KeyCode::Right => {
+                self.state.toggle_tree_node().await;
+            }
+            KeyCode::Char('I') => {
+                if let Some(NodeKind::Object { schema, name, kind }) =
+                    self.state.schema_tree.selected_node().map(|node| node.kind.clone())
+                {
+                    if kind.has_columns() {
+                        let driver = self.state.driver();
+                        let table = format!("{}.{}", quote_identifier(&schema, driver), quote_identifier(&name, driver));
+                        self.state.open_structure(table).await;
+                    }
+                }
+            }
+            KeyCode::Char('h') | KeyCode::Left => {
+                self.state.schema_tree.collapse_selected();
+            }
+            _ => {}
+        }
+    }
+
+    /// Handles keys while the completion popup is open, returning whether the key was used
+    fn handle_completion_keys(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
+        let Some(completion) = &mut self.state.completion else {
+            return false;
+        };
+
+        match (key, modifiers) {
+            (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+                completion.selected = (completion.selected + 1) % completion.items.len();
+            }
+            (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+                completion.selected = completion.selected.checked_sub(1).unwrap_or(completion.items.len() - 1);
+            }
+            (KeyCode::Tab, _) | (KeyCode::Enter, KeyModifiers::NONE) => {
+                self.state.accept_completion();
+                self.state.history.reset_recall();
+            }
+            (KeyCode::Esc, _) => {
+                self.state.completion = None;
+            }
+            (KeyCode::Char(_), KeyModifiers::NONE | KeyModifiers::SHIFT) | (KeyCode::Backspace, KeyModifiers::NONE) => {
+                // Typing refines the candidates
+                return false;
+            }
+            _ => {
+                self.state.completion = None;
+                return false;
+            }
+        }
+        true
+    }
+
     /// Handles input in insert mode
     async fn handle_insert_mode(&mut self, key: KeyCode, modifiers: KeyModifiers) -> Result<()> {
+        if self.handle_completion_keys(key, modifiers) {
+            return Ok(());
+        }
+
+        let select = modifiers.contains(KeyModifiers::SHIFT);
+        let word = modifiers.contains(KeyModifiers::CONTROL) || modifiers.contains(KeyModifiers::ALT);
+
         match (key, modifiers) {
             (KeyCode::Esc, _) => {
                 self.state.input.toggle_mode();
             }
-            (KeyCode::Enter, KeyModifiers::CONTROL) | (KeyCode::Enter, KeyModifiers::ALT) => {
+            (KeyCode::Enter, KeyModifiers::CONTROL) | (KeyCode::Enter, KeyModifiers::ALT) | (KeyCode::F(5), _) => {
                 // Execute query without leaving insert mode
-                let query = self.state.input.take_buffer();
-                self.state.execute_query(query).await;
+                self.execute_editor_query().await;
+            }
+            (KeyCode::F(6), _) => {
+                self.explain_editor_query(false).await;
+            }
+            (KeyCode::F(7), _) => {
+                self.explain_editor_query(true).await;
             }
             (KeyCode::Enter, _) => {
-                // Execute query and return to normal mode
-                let query = self.state.input.take_buffer();
-                self.state.execute_query(query).await;
-                self.state.input.toggle_mode();
+                self.state.input.insert_newline();
+                self.state.history.reset_recall();
             }
-            (KeyCode::Char(c), _) => {
-                self.state.input.insert_char(c);
+            (KeyCode::Char(' '), KeyModifiers::CONTROL) => {
+                self.state.open_completion(true).await;
             }
-            (KeyCode::Backspace, _) => {
-                self.state.input.delete_char();
+            (KeyCode::Tab, _) if self.state.can_complete() => {
+                self.state.open_completion(true).await;
             }
-            (KeyCode::Left, _) => {
-                self.state.input.move_cursor_left();
+            (KeyCode::Tab, _) => {
+                self.state.input.insert_indent();
             }
-            (KeyCode::Right, _) => {
-                self.state.input.move_cursor_right();
+            (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+                self.open_history_search();
             }
-            (KeyCode::Delete, _) => {
-                // Delete character under cursor
-                if self.state.input.cursor_position() < self.state.input.buffer().len() {
-                    self.state.input.move_cursor_right();
-                    self.state.input.delete_char();
+            (KeyCode::Up, _) if self.state.input.is_on_first_line() && !select => {
+                // Recall the previous query from history
+                let current = self.state.input.buffer();
+                if let Some(query) = self.state.history.recall_previous(&current) {
+                    let query = query.to_string();
+                    self.state.input.set_buffer(query);
                 }
             }
-            (KeyCode::Home, _) | (KeyCode::Char('a'), KeyModifiers::CONTROL) => {
-                // Move cursor to start of line
-                while self.state.input.cursor_position() > 0 {
-                    self.state.input.move_cursor_left();
+            (KeyCode::Down, _) if self.state.input.is_on_last_line() && !select => {
+                // Recall the next query, or the original draft
+                if let Some(query) = self.state.history.recall_next() {
+                    self.state.input.set_buffer(query);
                 }
             }
+            (KeyCode::Up, _) => self.state.input.move_cursor(Motion::Up, select),
+            (KeyCode::Down, _) => self.state.input.move_cursor(Motion::Down, select),
+            (KeyCode::Left, _) if word => self.state.input.move_cursor(Motion::WordLeft, select),
+            (KeyCode::Right, _) if word => self.state.input.move_cursor(Motion::WordRight, select),
+            (KeyCode::Left, _) => self.state.input.move_cursor(Motion::Left, select),
+            (KeyCode::Right, _) => self.state.input.move_cursor(Motion::Right, select),
+            (KeyCode::Char('b'), KeyModifiers::ALT) => self.state.input.move_cursor(Motion::WordLeft, false),
+            (KeyCode::Char('f'), KeyModifiers::ALT) => self.state.input.move_cursor(Motion::WordRight, false),
+            (KeyCode::Home, _) if word => self.state.input.move_cursor(Motion::BufferStart, select),
+            (KeyCode::End, _) if word => self.state.input.move_cursor(Motion::BufferEnd, select),
+            (KeyCode::Home, _) | (KeyCode::Char('a'), KeyModifiers::CONTROL) => {
+                self.state.input.move_cursor(Motion::LineStart, select);
+            }
             (KeyCode::End, _) | (KeyCode::Char('e'), KeyModifiers::CONTROL) => {
-                // Move cursor to end of line
-                while self.state.input.cursor_position() < self.state.input.buffer().len() {
-                    self.state.input.move_cursor_right();
+                self.state.input.move_cursor(Motion::LineEnd, select);
+            }
+            (KeyCode::Backspace, _) => {
+                self.state.input.delete_char();
+                self.state.history.reset_recall();
+                self.state.update_completion();
+            }
+            (KeyCode::Delete, _) => {
+                self.state.input.delete_char_forward();
+                self.state.history.reset_recall();
+            }
+            (KeyCode::Char('z'), KeyModifiers::CONTROL) => self.state.input.undo(),
+            (KeyCode::Char('y'), KeyModifiers::CONTROL) => self.state.input.redo(),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => self.state.input.copy(),
+            (KeyCode::Char('x'), KeyModifiers::CONTROL) => self.state.input.cut(),
+            (KeyCode::Char('v'), KeyModifiers::CONTROL) => self.state.input.paste(),
+            (KeyCode::Char('l'), KeyModifiers::CONTROL) => self.state.input.select_all(),
+            (KeyCode::Char(c), modifiers) if !modifiers.contains(KeyModifiers::CONTROL) => {
+                self.state.input.insert_char(c);
+                self.state.history.reset_recall();
+                if self.state.completion.is_some() {
+                    self.state.update_completion();
+                } else if c == '.' {
+                    self.state.open_completion(false).await;
                 }
             }
             _ => {}
@@ -140,6 +809,26 @@ impl App {
         Ok(())
     }
 
+    /// Executes the selected text, or the whole editor buffer
+    async fn execute_editor_query(&mut self) {
+        let query = self
+            .state
+            .input
+            .selected_text()
+            .unwrap_or_else(|| self.state.input.buffer());
+        self.state.execute_query(query).await;
+    }
+
+    /// Shows the plan of the selected text, or else of the whole editor
+    async fn explain_editor_query(&mut self, analyze: bool) {
+        let query = self
+            .state
+            .input
+            .selected_text()
+            .unwrap_or_else(|| self.state.input.buffer());
+        self.state.explain_query(query, analyze).await;
+    }
+
     /// Initializes the terminal for the TUI
     fn init_terminal(&self) -> Result<()> {
         debug!("Initializing terminal");
@@ -147,7 +836,8 @@ impl App {
         crossterm::execute!(
             std::io::stdout(),
             crossterm::terminal::EnterAlternateScreen,
-            crossterm::event::EnableMouseCapture
+            crossterm::event::EnableMouseCapture,
+            crossterm::event::EnableBracketedPaste
         )?;
         Ok(())
     }
@@ -157,6 +847,7 @@ impl App {
         debug!("Cleaning up terminal");
         crossterm::execute!(
             std::io::stdout(),
+            crossterm::event::DisableBracketedPaste,
             crossterm::event::DisableMouseCapture,
             crossterm::terminal::LeaveAlternateScreen
         )?;
@@ -165,15 +856,44 @@ impl App {
     }
 
     /// Draws the user interface
-    fn draw(&self) -> Result<()> {
-        let mut terminal = Terminal::new(
-            ratatui::backend::CrosstermBackend::new(std::io::stdout())
-        )?;
-
+    fn draw(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
         terminal.draw(|frame| {
-            crate::ui::draw(frame, &self.state);
+            crate::ui::draw(frame, &mut self.state);
         })?;
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Catalog, CatalogTable};
+    use ratatui::backend::TestBackend;
+
+    #[tokio::test]
+    async fn test_completion_closes_on_alt_motion() {
+        let mut app = App::new(AppConfig::default()).unwrap();
+        app.state.catalog = Some(Catalog {
+            schemas: vec!["public".to_string()],
+            tables: vec![CatalogTable {
+                schema: "public".to_string(),
+                name: "users".to_string(),
+                columns: vec!["id".to_string()],
+            }],
+            functions: Vec::new(),
+            default_schema: "public".to_string(),
+        });
+        app.handle_input(KeyCode::Char('i'), KeyModifiers::NONE).await.unwrap();
+        app.state.input.set_buffer("SELECT * FROM\nuse".to_string());
+        app.state.open_completion(false).await;
+        assert!(app.state.completion.is_some());
+
+        // Alt+b moves a word left, past the start of the word being completed
+        app.handle_input(KeyCode::Char('b'), KeyModifiers::ALT).await.unwrap();
+        assert!(app.state.completion.is_none());
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| crate::ui::draw(frame, &mut app.state)).unwrap();
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::HashSet;
+
+use serde_json::{Map, Value};
+
+use crate::database::CellValue;
+use super::query::QueryResult;
+
+/// Share of the query's time (or cost, without ANALYZE) spent in a node itself
+/// above which the node is highlighted as expensive
+pub const EXPENSIVE_SHARE: f64 = 0.1;
+
+/// Factor between estimated and actual rows above which the estimate is highlighted
+pub const MISESTIMATE_FACTOR: f64 = 10.0;
+
+/// Attributes shown in the plan tree rather than among the node details
+const TREE_KEYS: &[&str] = &[
+    "Plans", "Node Type", "Startup Cost", "Total Cost", "Plan Rows", "Plan Width",
+    "Actual Startup Time", "Actual Total Time", "Actual Rows", "Actual Loops",
+    "Shared Hit Blocks", "Shared Read Blocks", "Parallel Aware", "Async Capable",
+    "Relation Name", "Alias", "Index Name", "Join Type", "Scan Direction",
+];
+
+/// A node of a query plan
+#[derive(Debug, Clone, Default)]
+pub struct PlanNode {
+    /// Description of the node, e.g. `Index Scan using users_pkey on users u`
+    pub label: String,
+    /// Estimated cost of returning the first row
+    pub startup_cost: f64,
+    /// Estimated cost of returning all rows
+    pub total_cost: f64,
+    /// Estimated rows per loop
+    pub plan_rows: f64,
+    /// Estimated average row width in bytes
+    pub plan_width: f64,
+    /// Average rows per loop, with ANALYZE
+    pub actual_rows: Option<f64>,
+    /// Number of times the node ran, with ANALYZE
+    pub actual_loops: Option<f64>,
+    /// Average time of a loop in milliseconds, with ANALYZE
+    pub actual_time: Option<f64>,
+    /// Shared buffers found in the cache, with BUFFERS
+    pub shared_hit: Option<u64>,
+    /// Shared buffers read from disk, with BUFFERS
+    pub shared_read: Option<u64>,
+    /// Other attributes, such as conditions, sort keys and memory use
+    pub details: Vec<(String, String)>,
+    /// Child nodes
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    /// Reads a node and its children from the JSON output of EXPLAIN
+    fn from_json(node: &Map<String, Value>) -> Self {
+        let number = |key: &str| node.get(key).and_then(Value::as_f64);
+
+        let details = node
+            .iter()
+            .filter(|(key, _)| !TREE_KEYS.contains(&key.as_str()))
+            // Buffer and I/O counters that stayed at zero say nothing
+            .filter(|(key, value)| !(key.ends_with("Blocks") && value.as_f64() == Some(0.0)))
+            .map(|(key, value)| (key.clone(), detail_text(value)))
+            .collect();
+        let children = node
+            .get("Plans")
+            .and_then(Value::as_array)
+            .map(|plans| plans.iter().filter_map(Value::as_object).map(PlanNode::from_json).collect())
+            .unwrap_or_default();
+
+        Self {
+            label: node_label(node),
+            startup_cost: number("Startup Cost").unwrap_or(0.0),
+            total_cost: number("Total Cost").unwrap_or(0.0),
+            plan_rows: number("Plan Rows").unwrap_or(0.0),
+            plan_width: number("Plan Width").unwrap_or(0.0),
+            actual_rows: number("Actual Rows"),
+            actual_loops: number("Actual Loops"),
+            actual_time: number("Actual Total Time"),
+            shared_hit: node.get("Shared Hit Blocks").and_then(Value::as_u64),
+            shared_read: node.get("Shared Read Blocks").and_then(Value::as_u64),
+            details,
+            children,
+        }
+    }
+
+    /// Total time spent in the node and its children over all loops, in milliseconds
+    pub fn total_time(&self) -> Option<f64> {
+        Some(self.actual_time? * self.actual_loops.unwrap_or(1.0))
+    }
+
+    /// Time spent in the node itself, without its children, in milliseconds
+    pub fn self_time(&self) -> Option<f64> {
+        let children: f64 = self.children.iter().filter_map(PlanNode::total_time).sum();
+        Some((self.total_time()? - children).max(0.0))
+    }
+
+    /// Estimated cost of the node itself, without its children
+    pub fn self_cost(&self) -> f64 {
+        let children: f64 = self.children.iter().map(|child| child.total_cost).sum();
+        (self.total_cost - children).max(0.0)
+    }
+
+    /// Whether ANALYZE found that the node never ran, e.g. the inner side of a
+    /// join whose outer side returned no rows
+    pub fn never_executed(&self) -> bool {
+        self.actual_loops == Some(0.0)
+    }
+
+    /// How many times more (above 1) or fewer (below 1) rows the node returned
+    /// than estimated, with ANALYZE. Zero rows count as one to keep the ratio finite.
+    pub fn estimate_ratio(&self) -> Option<f64> {
+        if self.never_executed() {
+            return None;
+        }
+        Some(self.actual_rows?.max(1.0) / self.plan_rows.max(1.0))
+    }
+
+    /// Whether the row estimate is off by at least `MISESTIMATE_FACTOR` either way
+    pub fn is_misestimated(&self) -> bool {
+        self.estimate_ratio()
+            .is_some_and(|ratio| ratio >= MISESTIMATE_FACTOR || ratio <= 1.0 / MISESTIMATE_FACTOR)
+    }
+}
+
+/// Describes a node the way the text format of EXPLAIN does, e.g.
+/// `Hash Left Join` or `Parallel Seq Scan on users u`
+fn node_label(node: &Map<String, Value>) -> String {
+    let text = |key: &str| node.get(key).and_then(Value::as_str);
+    let mut label = text("Node Type").unwrap_or("Unknown").to_string();
+
+    // Aggregates and set operations are named after their strategy
+    match (label.as_str(), text("Strategy")) {
+        ("Aggregate", Some("Hashed")) => label = "HashAggregate".to_string(),
+        ("Aggregate", Some("Sorted")) => label = "GroupAggregate".to_string(),
+        ("Aggregate", Some("Mixed")) => label = "MixedAggregate".to_string(),
+        ("SetOp", Some("Hashed")) => label = "HashSetOp".to_string(),
+        _ => {}
+    }
+
+    if let Some(join) = text("Join Type").filter(|join| *join != "Inner") {
+        label = match label.strip_suffix(" Join") {
+            Some(method) => format!("{} {} Join", method, join),
+            None => format!("{} {} Join", label, join),
+        };
+    }
+    if node.get("Parallel Aware").and_then(Value::as_bool) == Some(true) {
+        label = format!("Parallel {}", label);
+    }
+    if text("Scan Direction") == Some("Backward") {
+        label.push_str(" Backward");
+    }
+    if let Some(index) = text("Index Name") {
+        label.push_str(&format!(" using {}", index));
+    }
+    if let Some(relation) = text("Relation Name") {
+        label.push_str(&format!(" on {}", relation));
+        if let Some(alias) = text("Alias").filter(|alias| *alias != relation) {
+            label.push_str(&format!(" {}", alias));
+        }
+    }
+    label
+}
+
+/// Formats an attribute value, joining lists like sort keys with commas
+fn detail_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(detail_text).collect::<Vec<_>>().join(", "),
+        value => value.to_string(),
+    }
+}
+
+/// A query plan read from `EXPLAIN (FORMAT JSON)`
+#[derive(Debug, Clone, Default)]
+pub struct QueryPlan {
+    /// The top node
+    pub root: PlanNode,
+    /// Planning time in milliseconds, with ANALYZE (or SUMMARY)
+    pub planning_time: Option<f64>,
+    /// Execution time in milliseconds, with ANALYZE
+    pub execution_time: Option<f64>,
+    /// Whether the query was run to measure the actual rows and times
+    pub analyzed: bool,
+}
+
+impl QueryPlan {
+    /// Parses the JSON output of EXPLAIN, which is an array holding one plan
+    pub fn parse(json: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(json).ok()?;
+        let explain = value.as_array()?.first()?.as_object()?;
+        let root = PlanNode::from_json(explain.get("Plan")?.as_object()?);
+        Some(Self {
+            analyzed: root.actual_loops.is_some(),
+            root,
+            planning_time: explain.get("Planning Time").and_then(Value::as_f64),
+            execution_time: explain.get("Execution Time").and_then(Value::as_f64),
+        })
+    }
+
+    /// Reads the plan from the result of an `EXPLAIN (FORMAT JSON)` statement:
+    /// a single `QUERY PLAN` cell
+    pub fn from_result(result: &QueryResult) -> Option<Self> {
+        if result.headers.len() != 1 || result.headers[0] != "QUERY PLAN" || result.rows.len() != 1 {
+            return None;
+        }
+        match &result.rows[0][0] {
+            CellValue::Value(json) => Self::parse(json),
+            _ => None,
+        }
+    }
+
+    /// Share of the whole query spent in a node itself: of the time with
+    /// ANALYZE, of the estimated cost without
+    pub fn share(&self, node: &PlanNode) -> f64 {
+        let (part, whole) = match (node.self_time(), self.root.total_time()) {
+            (Some(part), Some(whole)) => (part, whole),
+            _ => (node.self_cost(), self.root.total_cost),
+        };
+        if whole > 0.0 { part / whole } else { 0.0 }
+    }
+}
+
+/// A node as displayed in the flattened plan tree
+pub struct VisiblePlanNode<'a> {
+    /// The node itself
+    pub node: &'a PlanNode,
+    /// Position of the node in the whole tree, in depth-first order
+    pub id: usize,
+    /// Nesting level, 0 for the top node
+    pub depth: usize,
+    /// Whether the children are hidden
+    pub collapsed: bool,
+}
+
+/// A plan shown as a collapsible tree in place of the results grid
+#[derive(Debug, Clone)]
+pub struct PlanView {
+    /// The plan
+    pub plan: QueryPlan,
+    /// Depth-first positions of the collapsed nodes
+    collapsed: HashSet<usize>,
+    /// Index of the selected node among the visible ones
+    selected: usize,
+    /// First visible node shown, kept by drawing to scroll as little as possible
+    pub offset: usize,
+}
+
+impl PlanView {
+    /// Shows a plan with every node expanded
+    pub fn new(plan: QueryPlan) -> Self {
+        Self {
+            plan,
+            collapsed: HashSet::new(),
+            selected: 0,
+            offset: 0,
+        }
+    }
+
+    /// Flattens the tree into the nodes whose parents are all expanded
+    pub fn visible(&self) -> Vec<VisiblePlanNode<'_>> {
+        fn walk<'a>(
+            node: &'a PlanNode,
+            depth: usize,
+            next_id: &mut usize,
+            collapsed: &HashSet<usize>,
+            output: &mut Vec<VisiblePlanNode<'a>>,
+            shown: bool,
+        ) {
+            let id = *next_id;
+            *next_id += 1;
+            let is_collapsed = collapsed.contains(&id);
+            if shown {
+                output.push(VisiblePlanNode { node, id, depth, collapsed: is_collapsed });
+            }
+            for child in &node.children {
+                walk(child, depth + 1, next_id, collapsed, output, shown && !is_collapsed);
+            }
+        }
+
+        let mut output = Vec::new();
+        walk(&self.plan.root, 0, &mut 0, &self.collapsed, &mut output, true);
+        output
+    }
+
+    /// Gets the index of the selected node among the visible ones
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Moves the selection by `delta` visible nodes
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.visible().len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Selects the first or last visible node
+    pub fn select_edge(&mut self, last: bool) {
+        self.selected = if last { self.visible().len().saturating_sub(1) } else { 0 };
+    }
+
+    /// Shows or hides the children of the selected node
+    pub fn toggle(&mut self) {
+        let Some((id, has_children)) = self.selected_node().map(|node| (node.id, !node.node.children.is_empty())) else {
+            return;
+        };
+        if has_children && !self.collapsed.remove(&id) {
+            self.collapsed.insert(id);
+        }
+    }
+
+    /// Shows the children of the selected node
+    pub fn expand(&mut self) {
+        if let Some(id) = self.selected_node().map(|node| node.id) {
+            self.collapsed.remove(&id);
+        }
+    }
+
+    /// Hides the children of the selected node, or selects its parent when
+    /// they are hidden already or it has none
+    pub fn collapse(&mut self) {
+        let visible = self.visible();
+        let Some(node) = visible.get(self.selected) else {
+            return;
+        };
+        if !node.node.children.is_empty() && !node.collapsed {
+            let id = node.id;
+            self.collapsed.insert(id);
+        } else if let Some(parent) = visible[..self.selected].iter().rposition(|parent| parent.depth < node.depth) {
+            self.selected = parent;
+        }
+    }
+
+    /// Gets the selected node
+    pub fn selected_node(&self) -> Option<VisiblePlanNode<'_>> {
+        self.visible().into_iter().nth(self.selected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_tree() {
+        let json = r#"[{"Plan": {"Node Type": "Hash Join", "Join Type": "Left", "Total Cost": 100.0,
+            "Plan Rows": 10, "Actual Total Time": 50.0, "Actual Rows": 2000, "Actual Loops": 1,
+            "Hash Cond": "(o.user_id = u.id)", "Shared Hit Blocks": 12, "Temp Read Blocks": 0,
+            "Plans": [
+                {"Node Type": "Seq Scan", "Relation Name": "orders", "Alias": "o", "Total Cost": 60.0,
+                 "Plan Rows": 2000, "Actual Total Time": 10.0, "Actual Rows": 2000, "Actual Loops": 1},
+                {"Node Type": "Aggregate", "Strategy": "Hashed", "Total Cost": 30.0, "Plan Rows": 100, "Actual Total Time": 4.0,
+                 "Actual Rows": 100, "Actual Loops": 2, "Plans": [
+                    {"Node Type": "Index Scan", "Index Name": "users_pkey", "Relation Name": "users",
+                     "Alias": "users", "Scan Direction": "Backward", "Parallel Aware": true,
+                     "Total Cost": 25.0, "Plan Rows": 100, "Actual Total Time": 3.0, "Actual Rows": 100,
+                     "Actual Loops": 2}
+                ]}
+            ]}, "Planning Time": 0.2, "Execution Time": 51.5}]"#;
+        let plan = QueryPlan::parse(json).unwrap();
+        assert!(plan.analyzed);
+        assert_eq!(plan.execution_time, Some(51.5));
+
+        let root = &plan.root;
+        assert_eq!(root.label, "Hash Left Join");
+        assert_eq!(root.details, vec![("Hash Cond".to_string(), "(o.user_id = u.id)".to_string())]);
+        assert_eq!(root.children[0].label, "Seq Scan on orders o");
+        assert_eq!(root.children[1].label, "HashAggregate");
+        assert_eq!(root.children[1].children[0].label, "Parallel Index Scan Backward using users_pkey on users");
+        // 50ms in all, 10ms in the scan and 2 loops of 4ms in the hash
+        assert_eq!(root.self_time(), Some(32.0));
+        assert_eq!(root.children[1].self_time(), Some(2.0));
+        assert_eq!(root.self_cost(), 10.0);
+        assert!(root.is_misestimated());
+        assert!(!root.children[0].is_misestimated());
+        assert!((plan.share(root) - 32.0 / 50.0).abs() < 1e-9);
+
+        let mut view = PlanView::new(plan);
+        assert_eq!(view.visible().len(), 4);
+        view.move_selection(2);
+        view.collapse();
+        assert_eq!(view.visible().len(), 3);
+        view.collapse();
+        assert_eq!(view.selected(), 0);
+        view.move_selection(10);
+        assert_eq!(view.selected_node().unwrap().id, 2);
+        view.toggle();
+        assert_eq!(view.visible().len(), 4);
+        assert!(QueryPlan::parse("[{}]").is_none());
+    }
+}
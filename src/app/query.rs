@@ -1,52 +1,167 @@
-use crate::database::DatabaseError;
+use crate::database::{CellValue, DatabaseError};
+use super::edit::EditSession;
+use super::grid::GridState;
+use super::plan::{PlanView, QueryPlan};
+use super::view::{visible_rows, RowFilter, SortKey};
 
 /// Represents the result of a query execution
 #[derive(Debug, Clone)]
 pub struct QueryResult {
     /// Column headers
     pub headers: Vec<String>,
+    /// Postgres type names of the columns, e.g. `INT4`; empty when unknown
+    pub column_types: Vec<String>,
     /// Row data
-    pub rows: Vec<Vec<String>>,
+    pub rows: Vec<Vec<CellValue>>,
     /// Number of affected rows (for UPDATE, DELETE, etc.)
     pub affected_rows: Option<u64>,
+    /// Command tag of a statement that returns no rows (CREATE TABLE, GRANT, etc.)
+    pub command_tag: Option<String>,
     /// Execution time in milliseconds
     pub execution_time: u128,
+    /// Whether the statement returned more rows than were read, which happens in scripts
+    pub more_rows: bool,
+    /// Columns the shown rows are sorted by, most significant first
+    pub sort: Vec<SortKey>,
+    /// Filter hiding rows that do not match
+    pub filter: Option<RowFilter>,
+    /// Indices into `rows` of the shown rows, in order; `None` shows all rows as loaded
+    view: Option<Vec<usize>>,
 }
 
 impl QueryResult {
     /// Creates a new query result
     pub fn new(
         headers: Vec<String>,
-        rows: Vec<Vec<String>>,
+        rows: Vec<Vec<CellValue>>,
         affected_rows: Option<u64>,
         execution_time: u128,
     ) -> Self {
         Self {
             headers,
+            column_types: Vec::new(),
             rows,
             affected_rows,
+            command_tag: None,
             execution_time,
+            more_rows: false,
+            sort: Vec::new(),
+            filter: None,
+            view: None,
         }
     }
 
+    /// Converts a database result, decoding its cells for display
+    pub fn from_database(result: &crate::database::QueryResult, execution_time: u128) -> Self {
+        let mut query_result = Self::new(
+            result.columns(),
+            result.rows_as_values(),
+            result.rows_affected(),
+            execution_time,
+        );
+        query_result.column_types = result.column_types();
+        query_result.command_tag = result.command_tag().map(String::from);
+        query_result.more_rows = result.has_more_rows();
+        query_result
+    }
+
     /// Gets the total number of rows
     pub fn row_count(&self) -> usize {
         self.rows.len()
     }
 
+    /// Gets the number of rows shown after filtering
+    pub fn visible_count(&self) -> usize {
+        self.view.as_ref().map_or(self.rows.len(), Vec::len)
+    }
+
+    /// Gets the shown row at `index`, counting in sorted and filtered order
+    pub fn visible_row(&self, index: usize) -> Option<&[CellValue]> {
+        self.rows.get(self.row_index(index)?).map(Vec::as_slice)
+    }
+
+    /// Gets the index in `rows` of the shown row at `index`
+    pub fn row_index(&self, index: usize) -> Option<usize> {
+        match &self.view {
+            Some(view) => view.get(index).copied(),
+            None => (index < self.rows.len()).then_some(index),
+        }
+    }
+
+    /// Gets where the row at `index` in `rows` is shown, if it is not filtered out
+    pub fn visible_index(&self, index: usize) -> Option<usize> {
+        match &self.view {
+            Some(view) => view.iter().position(|row| *row == index),
+            None => (index < self.rows.len()).then_some(index),
+        }
+    }
+
+    /// Sorts and filters the rows again, e.g. after the sort keys, the filter or the rows changed
+    pub fn refresh_view(&mut self) {
+        self.view = visible_rows(&self.rows, &self.column_types, &self.sort, self.filter.as_ref());
+    }
+
+    /// Describes the outcome, e.g. "3 rows", "12 rows affected" or "CREATE TABLE"
+    pub fn summary(&self) -> String {
+        if let Some(tag) = &self.command_tag {
+            tag.clone()
+        } else if let Some(affected) = self.affected_rows {
+            format!("{} rows affected", affected)
+        } else {
+            format!("{} rows", self.row_count())
+        }
+    }
+
     /// Gets the number of columns
     pub fn column_count(&self) -> usize {
         self.headers.len()
     }
 }
 
+/// One executed statement, shown as a tab of the results panel
+#[derive(Debug)]
+pub struct ResultTab {
+    /// The executed statement
+    pub statement: String,
+    /// The result, or the error message if the statement failed
+    pub result: Result<QueryResult, String>,
+    /// Cursor and viewport of the results grid
+    pub grid: GridState,
+    /// Pending changes while the grid is in edit mode
+    pub edit: Option<EditSession>,
+    /// The plan tree shown instead of the grid when the statement was an `EXPLAIN (FORMAT JSON)`
+    pub plan: Option<PlanView>,
+}
+
+impl ResultTab {
+    /// Creates a tab for an executed statement
+    pub fn new(statement: String, result: Result<QueryResult, String>) -> Self {
+        let grid = result.as_ref().map(GridState::new).unwrap_or_default();
+        let plan = result.as_ref().ok().and_then(QueryPlan::from_result).map(PlanView::new);
+        Self {
+            statement,
+            result,
+            grid,
+            edit: None,
+            plan,
+        }
+    }
+
+    /// Gets the short label shown in the tab bar
+    pub fn label(&self) -> String {
+        match &self.result {
+            Ok(result) => result.summary(),
+            Err(_) => "error".to_string(),
+        }
+    }
+}
+
 /// Formats a query execution error
 pub fn format_error(error: &DatabaseError) -> String {
     match error {
         DatabaseError::ConnectionError(msg) => format!("Connection error: {}", msg),
         DatabaseError::QueryError(msg) => format!("Query error: {}", msg),
         DatabaseError::ConfigError(msg) => format!("Configuration error: {}", msg),
-        DatabaseError::ResultError(msg) => format!("Result error: {}", msg),
         DatabaseError::Unknown(msg) => format!("Unknown error: {}", msg),
     }
 }
\ No newline at end of file
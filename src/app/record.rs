@@ -0,0 +1,133 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::database::CellValue;
+use super::input::display_width;
+
+/// Gets the text shown for a cell in the record view, with JSON values pretty-printed
+pub fn field_text(cell: &CellValue, column_type: &str) -> String {
+    match cell {
+        CellValue::Value(text) if matches!(column_type, "JSON" | "JSONB") => pretty_json(text),
+        cell => cell.to_string(),
+    }
+}
+
+/// Indents JSON text with two spaces per level. The text is re-indented rather than
+/// parsed, so key order and number precision are kept exactly as the server sent them.
+pub fn pretty_json(text: &str) -> String {
+    let mut output = String::with_capacity(text.len() * 2);
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.trim().chars().peekable();
+
+    let newline = |output: &mut String, depth: usize| {
+        output.push('\n');
+        output.push_str(&"  ".repeat(depth));
+    };
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                output.push(c);
+            }
+            '{' | '[' => {
+                output.push(c);
+                // Keep empty objects and arrays on one line
+                while chars.peek().is_some_and(|next| next.is_whitespace()) {
+                    chars.next();
+                }
+                if chars.peek().is_some_and(|next| matches!(next, '}' | ']')) {
+                    output.extend(chars.next());
+                } else {
+                    depth += 1;
+                    newline(&mut output, depth);
+                }
+            }
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                newline(&mut output, depth);
+                output.push(c);
+            }
+            ',' => {
+                output.push(c);
+                newline(&mut output, depth);
+            }
+            ':' => output.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+/// Wraps text to lines of at most `width` display columns, keeping its line breaks
+/// and breaking after spaces where possible
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let paragraph = paragraph.strip_suffix('\r').unwrap_or(paragraph).replace('\t', "    ");
+        let mut line = String::new();
+        let mut line_width = 0;
+        // Byte offset in `line` after its last space, where it can be broken
+        let mut break_at = None;
+
+        for grapheme in paragraph.graphemes(true) {
+            let grapheme_width = display_width(grapheme);
+            if line_width + grapheme_width > width && !line.is_empty() {
+                match break_at.filter(|offset| *offset < line.len()) {
+                    Some(offset) => {
+                        let rest = line.split_off(offset);
+                        lines.push(std::mem::replace(&mut line, rest));
+                        line_width = display_width(&line);
+                    }
+                    None => {
+                        lines.push(std::mem::take(&mut line));
+                        line_width = 0;
+                    }
+                }
+                break_at = None;
+            }
+
+            line.push_str(grapheme);
+            line_width += grapheme_width;
+            if grapheme == " " {
+                break_at = Some(line.len());
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_formatting() {
+        assert_eq!(
+            pretty_json(r#"{"b":[1, 2.50],"a":{"s":"x,{\"y\":1}"},"e":{}, "f":[ ]}"#),
+            "{\n  \"b\": [\n    1,\n    2.50\n  ],\n  \"a\": {\n    \"s\": \"x,{\\\"y\\\":1}\"\n  },\n  \"e\": {},\n  \"f\": []\n}"
+        );
+        assert_eq!(field_text(&CellValue::Value("[]".to_string()), "TEXT"), "[]");
+        assert_eq!(field_text(&CellValue::Null, "JSONB"), "NULL");
+
+        assert_eq!(wrap("the quick brown fox", 10), vec!["the quick ", "brown fox"]);
+        assert_eq!(wrap("abcdefghij klm\n\nxy", 4), vec!["abcd", "efgh", "ij ", "klm", "", "xy"]);
+        assert_eq!(wrap("日本語テキスト", 5), vec!["日本", "語テ", "キス", "ト"]);
+    }
+}
@@ -0,0 +1,372 @@
+use anyhow::Result;
+
+use crate::database::{DatabaseManager, ObjectKind};
+
+/// Kind of a node in the schema browser, carrying what is needed to load its children
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeKind {
+    /// A database on the server
+    Database(String),
+    /// A schema in the current database
+    Schema(String),
+    /// A group of objects of one kind within a schema
+    Category { schema: String, kind: ObjectKind },
+    /// A table, view, function, etc.
+    Object {
+        schema: String,
+        name: String,
+        kind: ObjectKind,
+    },
+    /// A column of a table or view
+    Column,
+    /// An index of a table or materialized view
+    Index,
+    /// A constraint of a table
+    Constraint,
+}
+
+impl NodeKind {
+    /// Whether the node can have children
+    pub fn is_expandable(&self) -> bool {
+        match self {
+            NodeKind::Database(_) | NodeKind::Schema(_) | NodeKind::Category { .. } => true,
+            NodeKind::Object { kind, .. } => kind.has_columns(),
+            NodeKind::Column | NodeKind::Index | NodeKind::Constraint => false,
+        }
+    }
+}
+
+/// A node of the schema browser tree
+#[derive(Debug, Clone)]
+pub struct TreeNode {
+    /// Text displayed for the node
+    pub label: String,
+    /// What the node represents
+    pub kind: NodeKind,
+    /// Whether the children are shown
+    pub expanded: bool,
+    /// Children, `None` until they have been loaded
+    pub children: Option<Vec<TreeNode>>,
+}
+
+impl TreeNode {
+    fn new(label: String, kind: NodeKind) -> Self {
+        Self {
+            label,
+            kind,
+            expanded: false,
+            children: None,
+        }
+    }
+}
+
+/// A node as displayed in the flattened tree
+pub struct VisibleNode<'a> {
+    /// The node itself
+    pub node: &'a TreeNode,
+    /// Nesting level, 0 for databases
+    pub depth: usize,
+}
+
+/// Lazily loaded tree of databases, schemas and their objects
+#[derive(Debug, Default)]
+pub struct SchemaTree {
+    roots: Vec<TreeNode>,
+    selected: usize,
+}
+
+impl SchemaTree {
+    /// Creates an empty tree
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the tree with the given databases
+    pub fn set_databases(&mut self, databases: Vec<String>) {
+        self.roots = databases
+            .into_iter()
+            .map(|name| TreeNode::new(name.clone(), NodeKind::Database(name)))
+            .collect();
+        self.selected = 0;
+    }
+
+    /// Collapses and unloads every database except `current`, since their children
+    /// were loaded through a different connection, and selects `current` again
+    /// because the collapsed nodes shift the visible indexes
+    pub fn reset_other_databases(&mut self, current: &str) {
+        let current = NodeKind::Database(current.to_string());
+        for root in &mut self.roots {
+            if root.kind != current {
+                root.expanded = false;
+                root.children = None;
+            }
+        }
+
+        let anchor = self
+            .roots
+            .iter()
+            .position(|root| root.kind == current)
+            .and_then(|index| self.visible_index(&[index]));
+        self.selected = match anchor {
+            Some(index) => index,
+            None => self.selected.min(self.visible().len().saturating_sub(1)),
+        };
+    }
+
+    /// Gets the nodes currently visible, in display order
+    pub fn visible(&self) -> Vec<VisibleNode<'_>> {
+        let mut visible = Vec::new();
+        for root in &self.roots {
+            collect_visible(root, 0, &mut visible);
+        }
+        visible
+    }
+
+    /// Gets the index of the selected visible node
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Gets the selected node
+    pub fn selected_node(&self) -> Option<&TreeNode> {
+        self.visible().get(self.selected).map(|visible| visible.node)
+    }
+
+    /// Moves the selection down
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.visible().len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Moves the selection up
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Expands the selected node, loading its children on first expansion.
+    /// Expanding an already expanded node collapses it.
+    pub async fn toggle_selected(&mut self, db: &DatabaseManager) -> Result<()> {
+        let Some(path) = self.selected_path() else {
+            return Ok(());
+        };
+        let Some(node) = self.node_mut(&path) else {
+            return Ok(());
+        };
+
+        if !node.kind.is_expandable() {
+            return Ok(());
+        }
+        if node.expanded {
+            node.expanded = false;
+            return Ok(());
+        }
+        if node.children.is_none() {
+            node.children = Some(load_children(&node.kind, db).await?);
+        }
+        node.expanded = true;
+        Ok(())
+    }
+
+    /// Collapses the selected node, or moves to its parent if it is already collapsed
+    pub fn collapse_selected(&mut self) {
+        let Some(path) = self.selected_path() else {
+            return;
+        };
+
+        if let Some(node) = self.node_mut(&path) {
+            if node.expanded {
+                node.expanded = false;
+                return;
+            }
+        }
+
+        if path.len() > 1 {
+            let parent = &path[..path.len() - 1];
+            if let Some(index) = self.visible_index(parent) {
+                self.selected = index;
+            }
+        }
+    }
+
+    /// Gets the child-index path from the roots to the selected node
+    fn selected_path(&self) -> Option<Vec<usize>> {
+        let mut remaining = self.selected;
+        let mut path = Vec::new();
+        if find_path(&self.roots, &mut remaining, &mut path) {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the visible index of the node at `path`
+    fn visible_index(&self, path: &[usize]) -> Option<usize> {
+        let target = self.node_at(path)?;
+        self.visible()
+            .iter()
+            .position(|visible| std::ptr::eq(visible.node, target))
+    }
+
+    fn node_at(&self, path: &[usize]) -> Option<&TreeNode> {
+        let (first, rest) = path.split_first()?;
+        let mut node = self.roots.get(*first)?;
+        for index in rest {
+            node = node.children.as_ref()?.get(*index)?;
+        }
+        Some(node)
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> Option<&mut TreeNode> {
+        let (first, rest) = path.split_first()?;
+        let mut node = self.roots.get_mut(*first)?;
+        for index in rest {
+            node = node.children.as_mut()?.get_mut(*index)?;
+        }
+        Some(node)
+    }
+}
+
+fn collect_visible<'a>(node: &'a TreeNode, depth: usize, visible: &mut Vec<VisibleNode<'a>>) {
+    visible.push(VisibleNode { node, depth });
+    if node.expanded {
+        for child in node.children.iter().flatten() {
+            collect_visible(child, depth + 1, visible);
+        }
+    }
+}
+
+/// Walks the visible nodes in display order until `remaining` reaches the target
+fn find_path(nodes: &[TreeNode], remaining: &mut usize, path: &mut Vec<usize>) -> bool {
+    for (index, node) in nodes.iter().enumerate() {
+        path.push(index);
+        if *remaining == 0 {
+            return true;
+        }
+        *remaining -= 1;
+        if node.expanded {
+            if let Some(children) = &node.children {
+                if find_path(children, remaining, path) {
+                    return true;
+                }
+            }
+        }
+        path.pop();
+    }
+    false
+}
+
+/// Loads the children of an expandable node from the database
+async fn load_children(kind: &NodeKind, db: &DatabaseManager) -> Result<Vec<TreeNode>> {
+    let children = match kind {
+        NodeKind::Database(_) => db
+            .list_schemas()
+            .await?
+            .into_iter()
+            .map(|schema| TreeNode::new(schema.clone(), NodeKind::Schema(schema)))
+            .collect(),
+        NodeKind::Schema(schema) => db
+            .driver()
+            .object_kinds()
+            .iter()
+            .map(|kind| {
+                TreeNode::new(
+                    kind.label().to_string(),
+                    NodeKind::Category {
+                        schema: schema.clone(),
+                        kind: *kind,
+                    },
+                )
+            })
+            .collect(),
+        NodeKind::Category { schema, kind } => db
+            .list_objects(schema, *kind)
+            .await?
+            .into_iter()
+            .map(|name| {
+                TreeNode::new(
+                    name.clone(),
+                    NodeKind::Object {
+                        schema: schema.clone(),
+                        name,
+                        kind: *kind,
+                    },
+                )
+            })
+            .collect(),
+        NodeKind::Object { schema, name, kind } => {
+            let mut children: Vec<TreeNode> = db
+                .list_columns(schema, name)
+                .await?
+                .into_iter()
+                .map(|column| {
+                    let null = if column.nullable { "" } else { " not null" };
+                    TreeNode::new(
+                        format!("{}: {}{}", column.name, column.data_type, null),
+                        NodeKind::Column,
+                    )
+                })
+                .collect();
+
+            if *kind != ObjectKind::View {
+                children.extend(
+                    db.list_indexes(schema, name)
+                        .await?
+                        .into_iter()
+                        .map(|index| TreeNode::new(format!("{} (INDEX)", index), NodeKind::Index)),
+                );
+            }
+            if *kind == ObjectKind::Table {
+                children.extend(
+                    db.list_constraints(schema, name)
+                        .await?
+                        .into_iter()
+                        .map(|constraint| {
+                            TreeNode::new(
+                                format!("{} ({})", constraint.name, constraint.kind),
+                                NodeKind::Constraint,
+                            )
+                        }),
+                );
+            }
+            children
+        }
+        NodeKind::Column | NodeKind::Index | NodeKind::Constraint => Vec::new(),
+    };
+
+    Ok(children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reset_other_databases_reselects_current() {
+        let mut tree = SchemaTree::new();
+        tree.set_databases(vec!["app".to_string(), "analytics".to_string()]);
+
+        // Expand the first database as if it had been loaded through the old connection
+        tree.roots[0].expanded = true;
+        tree.roots[0].children = Some(vec![
+            TreeNode::new("public".to_string(), NodeKind::Schema("public".to_string())),
+            TreeNode::new("audit".to_string(), NodeKind::Schema("audit".to_string())),
+        ]);
+        tree.select_next();
+        tree.select_next();
+        tree.select_next();
+        assert_eq!(
+            tree.selected_node().map(|node| &node.kind),
+            Some(&NodeKind::Database("analytics".to_string()))
+        );
+
+        tree.reset_other_databases("analytics");
+        assert_eq!(tree.visible().len(), 2);
+        assert_eq!(tree.selected(), 1);
+        assert_eq!(
+            tree.selected_node().map(|node| &node.kind),
+            Some(&NodeKind::Database("analytics".to_string()))
+        );
+        assert!(tree.roots[0].children.is_none());
+    }
+}
@@ -1,38 +1,193 @@
-use std::time::Instant;
-use crate::database::{DatabaseManager, DatabaseConfig, DatabaseError};
+use chrono::Local;
+use tracing::warn;
+use crate::config::AppConfig;
+use crate::database::{
+    destructive, modifies_data, source_table, split_statements, transaction_control, Catalog, CellValue, DatabaseManager, DatabaseConfig, DatabaseError, Driver, QueryOutput, RowCursor,
+    DestructiveStatement, RunningQuery, Session, TableStructure, TransactionControl, TransactionStatus,
+};
+use super::browse::{BrowseClause, TableBrowser};
+use super::clipboard::{copy_text, copy_to_clipboard, CopyFormat, CopyTarget};
+use super::completion::{complete, is_word_byte, CompletionState};
+use super::edit::EditSession;
+use super::export::{expand_home, export_loaded, ExportFormat, RunningExport};
+use super::history::{History, HistoryEntry, HISTORY_FILE};
 use super::input::{InputHandler, Mode};
-use super::query::{QueryResult, format_error};
+use super::query::{QueryResult, ResultTab, format_error};
+use super::schema_tree::{NodeKind, SchemaTree};
+use super::view::{RowFilter, SortKey};
+
+/// Panel that receives navigation keys in Normal mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Focus {
+    /// The schema browser
+    Sidebar,
+    /// The query results
+    Results,
+    /// The query editor
+    Editor,
+}
+
+impl Focus {
+    /// Gets the next panel in tab order
+    pub fn next(self) -> Self {
+        match self {
+            Focus::Sidebar => Focus::Results,
+            Focus::Results => Focus::Editor,
+            Focus::Editor => Focus::Sidebar,
+        }
+    }
+
+    /// Gets the previous panel in tab order
+    pub fn previous(self) -> Self {
+        match self {
+            Focus::Sidebar => Focus::Editor,
+            Focus::Results => Focus::Sidebar,
+            Focus::Editor => Focus::Results,
+        }
+    }
+}
+
+/// Initial height of the editor pane
+const DEFAULT_EDITOR_HEIGHT: u16 = 10;
+
+/// Smallest height of the editor pane
+const MIN_EDITOR_HEIGHT: u16 = 4;
+
+/// Path offered in the export prompt before anything was exported
+const DEFAULT_EXPORT_PATH: &str = "result.csv";
+
+/// Popup dialogs drawn over the main layout
+#[derive(Debug, Clone, PartialEq)]
+pub enum Popup {
+    /// Connection profile picker with the selected profile index
+    ConnectionPicker { selected: usize },
+    /// Fuzzy history search with the search text and selected match index
+    HistorySearch { pattern: String, selected: usize },
+    /// Yes/no question guarding an action
+    Confirm { message: String, action: ConfirmAction },
+    /// Path prompt for exporting the shown result, all of its rows when `full`
+    Export { path: String, full: bool },
+    /// Choice of what to copy from the results grid, in `AppState::copy_format`
+    Copy,
+    /// Expanded view of the selected row, scrolled down by `scroll` lines
+    Record { scroll: usize },
+    /// Filter bar over the results grid, searching one column or every column when `None`
+    Filter { pattern: String, column: Option<usize> },
+    /// Prompt editing a clause of the browsed table's query
+    BrowseClause { clause: BrowseClause, text: String },
+    /// Prompt for the new value of the selected cell in edit mode
+    EditCell { text: String },
+    /// The statements that apply the pending edits, scrolled down by `scroll` lines
+    EditPreview { scroll: usize },
+    /// Definition of the table in `AppState::structure`, as its parts or as DDL when
+    /// `ddl`, scrolled down by `scroll` lines
+    Structure { ddl: bool, scroll: usize },
+}
+
+/// Action carried out when a confirmation popup is accepted
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfirmAction {
+    /// Roll back the open transaction and quit
+    Quit,
+    /// Run a query containing destructive statements
+    Execute(String),
+    /// Leave edit mode, dropping the pending changes
+    DiscardEdits,
+}
 
 /// Represents the current state of the application
 #[derive(Debug)]
 pub struct AppState {
+    /// The application configuration
+    pub config: AppConfig,
+    /// Name of the connection profile in use
+    pub active_profile: Option<String>,
+    /// The open popup, if any
+    pub popup: Option<Popup>,
     /// The input handler
     pub input: InputHandler,
+    /// Executed query history
+    pub history: History,
     /// The currently selected database
     pub current_database: Option<String>,
     /// The currently selected schema
     pub current_schema: Option<String>,
     /// The database connection manager
     pub db_manager: Option<DatabaseManager>,
-    /// The last query result
-    pub query_result: Option<QueryResult>,
+    /// Results of the last execution, one tab per statement
+    pub result_tabs: Vec<ResultTab>,
+    /// Index of the result tab shown
+    pub selected_tab: usize,
+    /// Server-side cursor over the rows of the last result that are not loaded yet
+    pub result_cursor: Option<RowCursor>,
+    /// The query executing in the background, if any
+    pub running_query: Option<RunningQuery>,
+    /// The full export writing in the background, if any
+    pub running_export: Option<RunningExport>,
+    /// Number of edit statements applied, reported once the running query reloads their rows
+    pub applied_edits: Option<usize>,
+    /// The pinned connection of transaction mode, if it is on
+    pub session: Option<Session>,
+    /// The table opened for browsing, while its generated query is the one shown
+    pub browser: Option<TableBrowser>,
+    /// The table shown in the structure view
+    pub structure: Option<TableStructure>,
+    /// Cached object names for completion, loaded on first use
+    pub catalog: Option<Catalog>,
+    /// The open completion popup, if any
+    pub completion: Option<CompletionState>,
+    /// Path of the last export, offered again in the export prompt
+    pub export_path: String,
+    /// Format of the last copy from the results grid
+    pub copy_format: CopyFormat,
     /// Status message to display
     pub status_message: Option<String>,
     /// Last error message
     pub last_error: Option<String>,
+    /// The schema browser tree
+    pub schema_tree: SchemaTree,
+    /// The focused panel
+    pub focus: Focus,
+    /// Whether the sidebar is shown
+    pub show_sidebar: bool,
+    /// Height of the editor pane in lines, including borders
+    pub editor_height: u16,
 }
 
 impl AppState {
     /// Creates a new application state
-    pub fn new() -> Self {
+    pub fn new(config: AppConfig) -> Self {
         Self {
+            config,
+            active_profile: None,
+            popup: None,
             input: InputHandler::new(),
+            history: History::load(&AppConfig::dir().join(HISTORY_FILE)).unwrap_or_else(|e| {
+                warn!("Failed to load query history: {}", e);
+                History::default()
+            }),
             current_database: None,
             current_schema: None,
             db_manager: None,
-            query_result: None,
+            result_tabs: Vec::new(),
+            selected_tab: 0,
+            result_cursor: None,
+            running_query: None,
+            running_export: None,
+            applied_edits: None,
+            session: None,
+            browser: None,
+            structure: None,
+            catalog: None,
+            completion: None,
+            export_path: DEFAULT_EXPORT_PATH.to_string(),
+            copy_format: CopyFormat::default(),
             status_message: None,
             last_error: None,
+            schema_tree: SchemaTree::new(),
+            focus: Focus::Editor,
+            show_sidebar: true,
+            editor_height: DEFAULT_EDITOR_HEIGHT,
         }
     }
 
@@ -58,52 +213,1197 @@ impl AppState {
         self.status_message = None;
     }
 
+    /// Grows or shrinks the editor pane by `delta` lines
+    pub fn resize_editor(&mut self, delta: i16) {
+        self.editor_height = self
+            .editor_height
+            .saturating_add_signed(delta)
+            .max(MIN_EDITOR_HEIGHT);
+    }
+
     /// Initializes the database connection
     pub async fn init_database(&mut self, config: DatabaseConfig) -> anyhow::Result<()> {
-        self.db_manager = Some(DatabaseManager::new(config).await?);
+        self.ensure_can_reconnect()?;
+        let db = DatabaseManager::new(config).await?;
+        let databases = db.list_databases().await?;
+        let session = Self::reopen_session(&db, self.session.is_some()).await?;
+
+        self.set_database(db.current_database().to_string());
+        self.current_schema = None;
+        self.schema_tree.set_databases(databases);
+        self.result_cursor = None;
+        self.session = session;
+        self.catalog = None;
+        self.db_manager = Some(db);
         Ok(())
     }
 
+    /// Connects using a named connection profile, replacing the current connection.
+    /// The previous connection is kept if the new one cannot be established.
+    pub async fn connect_profile(&mut self, name: &str) -> anyhow::Result<()> {
+        let Some(profile) = self.config.profile(name).cloned() else {
+            anyhow::bail!("Unknown connection profile: {}", name);
+        };
+
+        self.init_database(profile.into()).await?;
+        self.active_profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Reconnects to another database on the same server
+    pub async fn switch_database(&mut self, database: String) -> anyhow::Result<()> {
+        let Some(db) = &self.db_manager else {
+            anyhow::bail!("Not connected to database");
+        };
+        self.ensure_can_reconnect()?;
+
+        let mut config = db.config().clone();
+        config.database = database;
+        let db = DatabaseManager::new(config).await?;
+        let session = Self::reopen_session(&db, self.session.is_some()).await?;
+
+        self.set_database(db.current_database().to_string());
+        self.current_schema = None;
+        self.schema_tree.reset_other_databases(db.current_database());
+        self.result_cursor = None;
+        self.session = session;
+        self.catalog = None;
+        self.db_manager = Some(db);
+        Ok(())
+    }
+
+    /// Fails while a query is running or a transaction is open, which replacing
+    /// the connection would silently drop
+    fn ensure_can_reconnect(&self) -> anyhow::Result<()> {
+        if self.running_query.is_some() {
+            anyhow::bail!("Wait for the running query to finish");
+        }
+        self.ensure_no_open_transaction()
+    }
+
+    /// Pins a connection of a new database when transaction mode is on, so it stays on
+    async fn reopen_session(db: &DatabaseManager, transaction_mode: bool) -> anyhow::Result<Option<Session>> {
+        if !transaction_mode {
+            return Ok(None);
+        }
+        Ok(Some(db.open_session().await?))
+    }
+
+    /// Expands or collapses the selected schema browser node, switching
+    /// databases first when a database other than the current one is expanded
+    pub async fn toggle_tree_node(&mut self) {
+        let Some(kind) = self.schema_tree.selected_node().map(|node| node.kind.clone()) else {
+            return;
+        };
+
+        match &kind {
+            NodeKind::Database(name) if self.current_database.as_deref() != Some(name.as_str()) => {
+                if let Err(e) = self.ensure_can_reconnect() {
+                    self.set_error(e.to_string());
+                    return;
+                }
+                if let Err(e) = self.switch_database(name.clone()).await {
+                    let db_error: DatabaseError = e.into();
+                    self.set_error(format_error(&db_error));
+                    return;
+                }
+                self.set_status(format!("Switched to database {}", name));
+            }
+            NodeKind::Schema(schema) => self.set_schema(schema.clone()),
+            _ => {}
+        }
+
+        match &self.db_manager {
+            Some(db) => {
+                if let Err(e) = self.schema_tree.toggle_selected(db).await {
+                    let db_error: DatabaseError = e.into();
+                    self.set_error(format_error(&db_error));
+                }
+            }
+            None => {
+                self.set_error("Not connected to database".to_string());
+            }
+        }
+    }
+
     /// Gets the current mode
     pub fn mode(&self) -> Mode {
         self.input.mode()
     }
 
-    /// Executes a query
+    /// Gets the driver of the connected database, whose quoting rules queries
+    /// are read with, Postgres when not connected
+    pub fn driver(&self) -> Driver {
+        self.db_manager.as_ref().map_or(Driver::default(), |db| db.driver())
+    }
+
+    /// Starts executing a query in the background, asking for confirmation first
+    /// when the profile guards destructive statements and the query has any
     pub async fn execute_query(&mut self, query: String) {
-        let start_time = Instant::now();
-        
+        if self.running_query.is_some() {
+            self.set_error("A query is already running".to_string());
+            return;
+        }
+
+        let confirm = self
+            .active_profile
+            .as_deref()
+            .and_then(|name| self.config.profile(name))
+            .is_some_and(|profile| profile.confirm_destructive);
+        if confirm {
+            let driver = self.driver();
+            let statements: Vec<DestructiveStatement> = split_statements(&query, driver)
+                .into_iter()
+                .filter_map(|statement| destructive(statement, driver))
+                .collect();
+            if !statements.is_empty() {
+                let message = self.describe_destructive(&statements).await;
+                self.popup = Some(Popup::Confirm {
+                    message,
+                    action: ConfirmAction::Execute(query),
+                });
+                return;
+            }
+        }
+
+        self.run_query(query).await;
+    }
+
+    /// Runs `EXPLAIN (FORMAT JSON)` on a statement to show its plan tree, adding
+    /// ANALYZE and BUFFERS when `analyze`. ANALYZE runs the statement, so it asks
+    /// for confirmation first when the statement can change data.
+    pub async fn explain_query(&mut self, query: String, analyze: bool) {
+        if let Some(driver) = self.db_manager.as_ref().map(|db| db.driver()).filter(|&driver| driver != Driver::Postgres) {
+            return self.set_error(format!("Plans can only be shown for Postgres, not {}", driver));
+        }
+        let statements = split_statements(&query, Driver::Postgres);
+        let statement = match statements.as_slice() {
+            [] => return self.set_error("Nothing to explain".to_string()),
+            [statement] => statement,
+            _ => return self.set_error("Select a single statement to explain".to_string()),
+        };
+
+        let options = if analyze { "FORMAT JSON, ANALYZE, BUFFERS" } else { "FORMAT JSON" };
+        let explain = format!("EXPLAIN ({}) {}", options, statement);
+        if analyze && modifies_data(statement) {
+            self.popup = Some(Popup::Confirm {
+                message: "EXPLAIN ANALYZE runs the statement, so its changes are applied. Run it?".to_string(),
+                action: ConfirmAction::Execute(explain),
+            });
+            return;
+        }
+        self.run_query(explain).await;
+    }
+
+    /// Builds the confirmation message listing what destructive statements affect,
+    /// with the estimated row counts of the tables they empty or drop
+    async fn describe_destructive(&self, statements: &[DestructiveStatement]) -> String {
+        let profile = self.active_profile.as_deref().unwrap_or("this connection");
+        let mut lines = vec![format!("The query runs destructive statements on {}:", profile)];
+
+        for statement in statements {
+            let mut targets = Vec::new();
+            for target in &statement.targets {
+                let estimate = match &self.db_manager {
+                    Some(db) if statement.affects_rows => db.estimate_rows(target).await.ok().flatten(),
+                    _ => None,
+                };
+                targets.push(match estimate {
+                    Some(rows) => format!("{} (~{} rows)", target, rows),
+                    None => target.clone(),
+                });
+            }
+            lines.push(format!("{} on {}", statement.action, targets.join(", ")));
+        }
+
+        lines.push(String::new());
+        lines.push("Run it anyway?".to_string());
+        lines.join("\n")
+    }
+
+    /// Starts executing a query in the background. Only the first page of rows
+    /// is loaded; the rest is fetched by `load_more_rows`.
+    pub async fn run_query(&mut self, query: String) {
+        if self.running_query.is_some() {
+            self.set_error("A query is already running".to_string());
+            return;
+        }
+
+        self.close_result_cursor().await;
+        // Running any other query ends browsing
+        if self.browser.as_ref().is_some_and(|browser| browser.sql() != query) {
+            self.browser = None;
+        }
+
+        // A lone BEGIN would open its transaction on a pooled connection, so it
+        // switches to transaction mode to run the following statements on the same one
+        let statements = split_statements(&query, self.driver());
+        if self.session.is_none()
+            && statements.len() == 1
+            && transaction_control(statements[0]) == Some(TransactionControl::Begin)
+        {
+            self.toggle_transaction_mode().await;
+            if self.session.is_none() {
+                return;
+            }
+        }
+
         match &self.db_manager {
             Some(db) => {
-                match db.execute_query(&query).await {
-                    Ok(result) => {
-                        let execution_time = start_time.elapsed().as_millis();
-                        let headers = result.columns();
-                        let rows = result.rows_as_strings();
-                        let row_count = rows.len();
-                        
-                        self.query_result = Some(QueryResult::new(
-                            headers,
-                            rows,
-                            None,
-                            execution_time,
-                        ));
-
-                        self.set_status(format!(
-                            "Query executed successfully in {}ms ({} rows)",
-                            execution_time,
-                            row_count
-                        ));
-                    }
-                    Err(e) => {
-                        let db_error: DatabaseError = e.into();
-                        self.set_error(format_error(&db_error));
+                self.running_query = Some(db.spawn_query(
+                    query,
+                    self.config.page_size,
+                    self.config.continue_on_error,
+                    self.session.clone(),
+                ));
+                self.set_status("Running query...".to_string());
+            }
+            None => {
+                self.set_error("Not connected to database".to_string());
+            }
+        }
+    }
+
+    /// Shows the result of the background query once it has completed and
+    /// records it in the query history
+    pub async fn poll_running_query(&mut self) {
+        if !self.running_query.as_ref().is_some_and(RunningQuery::is_finished) {
+            return;
+        }
+        let Some(running) = self.running_query.take() else {
+            return;
+        };
+
+        let query = running.query().to_string();
+        let execution_time = running.elapsed().as_millis();
+        let mut row_count = None;
+        let mut error = None;
+
+        match running.finish().await {
+            Ok(QueryOutput::Single(result, mut cursor)) => {
+                // A browse page holds all the rows its LIMIT allows, so the cursor has no more
+                if self.browser.as_ref().is_some_and(|browser| browser.sql() == query) {
+                    if let Some(cursor) = cursor.take() {
+                        if let Err(e) = cursor.close().await {
+                            warn!("Failed to close result cursor: {}", e);
+                        }
                     }
                 }
+                let query_result = QueryResult::from_database(&result, execution_time);
+                row_count = Some(query_result.affected_rows.unwrap_or(query_result.row_count() as u64));
+
+                let more = if cursor.is_some() { ", more available" } else { "" };
+                self.result_cursor = cursor;
+                self.set_status(format!(
+                    "Query executed successfully in {}ms ({}{})",
+                    execution_time,
+                    query_result.summary(),
+                    more
+                ));
+                self.show_results(vec![ResultTab::new(query.clone(), Ok(query_result))]);
             }
-            None => {
+            Ok(QueryOutput::Script(results, rolled_back)) => {
+                let total = split_statements(&query, self.driver()).len();
+                let tabs: Vec<ResultTab> = results
+                    .into_iter()
+                    .map(|statement| {
+                        let result = statement
+                            .result
+                            .map(|result| QueryResult::from_database(&result, statement.execution_time))
+                            .map_err(|e| format_error(&e.into()));
+                        ResultTab::new(statement.statement, result)
+                    })
+                    .collect();
+
+                let failed = tabs.iter().filter(|tab| tab.result.is_err()).count();
+                error = tabs.iter().find_map(|tab| tab.result.as_ref().err().cloned());
+                let mut message = format!(
+                    "Ran {} of {} statements in {}ms, {} failed",
+                    tabs.len(),
+                    total,
+                    execution_time,
+                    failed
+                );
+                if rolled_back {
+                    message.push_str("; the open transaction was rolled back");
+                }
+                if failed > 0 {
+                    self.set_error(message);
+                } else {
+                    self.set_status(message);
+                }
+                self.show_results(tabs);
+            }
+            Err(e) => {
+                let db_error: DatabaseError = e.into();
+                let message = format_error(&db_error);
+                error = Some(message.clone());
+                self.set_error(message.clone());
+                self.show_results(vec![ResultTab::new(query.clone(), Err(message))]);
+            }
+        }
+
+        // The reload after applying edits reports the edits along with its own outcome
+        if let Some(applied) = self.applied_edits.take() {
+            match &error {
+                None => self.set_status(format!("Applied {} statements and reloaded the rows", applied)),
+                Some(e) => self.set_error(format!("Applied {} statements, but reloading the rows failed: {}", applied, e)),
+            }
+        }
+
+        if !query.trim().is_empty() {
+            self.history.push(HistoryEntry {
+                query,
+                timestamp: Local::now(),
+                profile: self.active_profile.clone(),
+                duration_ms: execution_time,
+                row_count,
+                error,
+            });
+        }
+    }
+
+    /// Replaces the results panel tabs, showing the first failed statement or else the last one
+    fn show_results(&mut self, tabs: Vec<ResultTab>) {
+        self.selected_tab = tabs
+            .iter()
+            .position(|tab| tab.result.is_err())
+            .unwrap_or(tabs.len().saturating_sub(1));
+        self.result_tabs = tabs;
+    }
+
+    /// Gets the result shown in the results panel, if it succeeded
+    pub fn query_result(&self) -> Option<&QueryResult> {
+        self.result_tabs.get(self.selected_tab)?.result.as_ref().ok()
+    }
+
+    /// Selects the next (or with a negative `delta`, previous) result tab
+    pub fn select_tab(&mut self, delta: isize) {
+        self.selected_tab = self
+            .selected_tab
+            .saturating_add_signed(delta)
+            .min(self.result_tabs.len().saturating_sub(1));
+    }
+
+    /// Opens the export prompt for the shown result. A `full` export runs the
+    /// statement again to write all of its rows, not only the loaded ones.
+    pub fn open_export(&mut self, full: bool) {
+        match self.query_result() {
+            Some(result) if !result.headers.is_empty() => {
+                self.popup = Some(Popup::Export {
+                    path: self.export_path.clone(),
+                    full,
+                });
+            }
+            _ => self.set_error("No rows to export".to_string()),
+        }
+    }
+
+    /// Exports the shown result to a file, picking the format from the extension
+    pub async fn export_result(&mut self, path: String, full: bool) {
+        let (Some(tab), Some(result)) = (self.result_tabs.get(self.selected_tab), self.query_result()) else {
+            return;
+        };
+        let file = expand_home(&path);
+        let Some(format) = ExportFormat::from_path(&file) else {
+            self.set_error(format!("Unknown export format, use one of: {}", ExportFormat::EXTENSIONS));
+            return;
+        };
+
+        if full {
+            let Some(db) = &self.db_manager else {
                 self.set_error("Not connected to database".to_string());
+                return;
+            };
+            if self.running_export.is_some() {
+                self.set_error("An export is already running".to_string());
+                return;
+            }
+            let export = RunningExport::spawn(db, result, &tab.statement, file, format, self.config.page_size);
+            self.set_status(format!("Exporting all rows to {}...", export.path().display()));
+            self.running_export = Some(export);
+            self.export_path = path;
+            return;
+        }
+
+        let written = export_loaded(result, &tab.statement, self.driver(), &file, format);
+        self.export_path = path;
+        match written {
+            Ok(rows) => self.set_status(format!("Exported {} rows to {}", rows, file.display())),
+            Err(e) => self.set_error(format!("Export failed: {}", e)),
+        }
+    }
+
+    /// Reports the outcome of the background export once it has completed
+    pub async fn poll_running_export(&mut self) {
+        if !self.running_export.as_ref().is_some_and(RunningExport::is_finished) {
+            return;
+        }
+        let Some(export) = self.running_export.take() else {
+            return;
+        };
+
+        let path = export.path().display().to_string();
+        match export.finish().await {
+            Ok(rows) => self.set_status(format!("Exported {} rows to {}", rows, path)),
+            Err(e) => self.set_error(format!("Export failed: {}", e)),
+        }
+    }
+
+    /// Stops the background export and removes the partly written file
+    pub async fn cancel_export(&mut self) {
+        if let Some(export) = self.running_export.take() {
+            let path = export.path().display().to_string();
+            export.cancel().await;
+            self.set_status(format!("Export to {} cancelled", path));
+        }
+    }
+
+    /// Copies part of the shown result to the system clipboard in `copy_format`,
+    /// ending the rectangular selection
+    pub fn copy_cells(&mut self, target: CopyTarget) {
+        let Some(ResultTab { result: Ok(result), grid, .. }) = self.result_tabs.get_mut(self.selected_tab) else {
+            self.set_error("No result to copy".to_string());
+            return;
+        };
+        if result.visible_count() == 0 {
+            self.set_error("No rows to copy".to_string());
+            return;
+        }
+
+        let cell = (grid.row()..grid.row() + 1, grid.column()..grid.column() + 1);
+        let selection = grid.selection();
+        let (rows, columns) = match target {
+            CopyTarget::Cell => cell,
+            CopyTarget::Row => (selection.map_or(cell.0, |(rows, _)| rows), 0..result.headers.len()),
+            CopyTarget::Column => (0..result.visible_count(), selection.map_or(cell.1, |(_, columns)| columns)),
+            CopyTarget::Selection => selection.unwrap_or(cell),
+        };
+        grid.clear_selection();
+
+        let format = self.copy_format;
+        let text = copy_text(result, rows.clone(), columns.clone(), target, format);
+        match copy_to_clipboard(&text) {
+            Ok(()) => {
+                let count = |count: usize, noun: &str| match count {
+                    1 => format!("1 {}", noun),
+                    count => format!("{} {}s", count, noun),
+                };
+                self.set_status(format!(
+                    "Copied {} × {} as {} ({})",
+                    count(rows.len(), "row"),
+                    count(columns.len(), "column"),
+                    format.label(),
+                    count(text.len(), "byte")
+                ));
+            }
+            Err(e) => self.set_error(format!("Failed to copy: {}", e)),
+        }
+    }
+
+    /// Opens a table, view or materialized view for browsing, showing the first
+    /// page of its rows ordered by the primary key
+    pub async fn open_table(&mut self, schema: String, table: String) {
+        let Some(db) = &self.db_manager else {
+            self.set_error("Not connected to database".to_string());
+            return;
+        };
+
+        let mut browser = TableBrowser::new(db.driver(), schema, table, Vec::new(), self.config.page_size);
+        let name = browser.qualified_name();
+        match db.primary_key(&name).await {
+            Ok(columns) => browser.key_columns = columns,
+            Err(e) => {
+                let db_error: DatabaseError = e.into();
+                self.set_error(format_error(&db_error));
+                return;
+            }
+        }
+        browser.estimated_rows = db.estimate_rows(&name).await.ok().flatten();
+
+        self.browser = Some(browser);
+        self.run_browser().await;
+    }
+
+    /// Reads the definition of a table, view or materialized view and shows it
+    /// in the structure view
+    pub async fn open_structure(&mut self, table: String) {
+        let Some(db) = &self.db_manager else {
+            self.set_error("Not connected to database".to_string());
+            return;
+        };
+
+        match db.table_structure(&table).await {
+            Ok(structure) => {
+                self.structure = Some(structure);
+                self.popup = Some(Popup::Structure { ddl: false, scroll: 0 });
+            }
+            Err(e) => {
+                let db_error: DatabaseError = e.into();
+                self.set_error(format_error(&db_error));
+            }
+        }
+    }
+
+    /// Shows the structure of the table the shown result was read from
+    pub async fn open_result_structure(&mut self) {
+        let table = match self.browsing() {
+            Some(browser) => Some(browser.qualified_name()),
+            None => self.result_tabs.get(self.selected_tab).and_then(|tab| source_table(&tab.statement, self.driver())),
+        };
+        match table {
+            Some(table) => self.open_structure(table).await,
+            None => self.set_error("The result does not come from a single table".to_string()),
+        }
+    }
+
+    /// Copies the DDL of the table in the structure view to the clipboard
+    pub fn copy_ddl(&mut self) {
+        let Some(structure) = &self.structure else {
+            return;
+        };
+        let ddl = structure.ddl();
+        match copy_to_clipboard(&ddl) {
+            Ok(()) => self.set_status(format!("Copied the DDL of {}", structure.qualified_name())),
+            Err(e) => self.set_error(format!("Failed to copy: {}", e)),
+        }
+    }
+
+    /// Gets the browsed table if the shown result is its current page
+    pub fn browsing(&self) -> Option<&TableBrowser> {
+        let tab = self.result_tabs.get(self.selected_tab)?;
+        self.browser.as_ref().filter(|browser| browser.sql() == tab.statement)
+    }
+
+    /// Shows the browse query in the editor and runs it like a typed query
+    async fn run_browser(&mut self) {
+        let Some(browser) = &self.browser else {
+            return;
+        };
+        let sql = browser.sql();
+        self.input.set_buffer(sql.clone());
+        self.execute_query(sql).await;
+    }
+
+    /// Moves to the next (or with a negative `delta`, previous) page of the browsed table
+    pub async fn browse_page(&mut self, delta: isize) {
+        let rows = self.query_result().map_or(0, |result| result.row_count());
+        let Some(browser) = &mut self.browser else {
+            return;
+        };
+        if delta > 0 && rows < browser.page_size {
+            self.set_status("Already on the last page".to_string());
+            return;
+        }
+        if delta < 0 && browser.page == 0 {
+            self.set_status("Already on the first page".to_string());
+            return;
+        }
+        browser.page = browser.page.saturating_add_signed(delta);
+        self.run_browser().await;
+    }
+
+    /// Opens the prompt for a clause of the browse query, filled with its current text
+    pub fn open_browse_clause(&mut self, clause: BrowseClause) {
+        match self.browsing() {
+            Some(browser) => {
+                let text = browser.clause(clause).to_string();
+                self.popup = Some(Popup::BrowseClause { clause, text });
+            }
+            None => self.set_error("Open a table from the schema browser with Enter first".to_string()),
+        }
+    }
+
+    /// Replaces a clause of the browse query and runs it from the first page
+    pub async fn set_browse_clause(&mut self, clause: BrowseClause, text: String) {
+        if let Some(browser) = &mut self.browser {
+            if !browser.set_clause(clause, text) {
+                self.set_error(format!("The {} clause cannot contain another statement", clause.label()));
+                return;
+            }
+            self.run_browser().await;
+        }
+    }
+
+    /// Narrows the browsed rows to those matching the selected cell's value
+    pub async fn browse_matching_cell(&mut self) {
+        let Some(ResultTab { result: Ok(result), grid, .. }) = self.result_tabs.get(self.selected_tab) else {
+            return;
+        };
+        let (Some(row), Some(column)) = (result.visible_row(grid.row()), result.headers.get(grid.column())) else {
+            return;
+        };
+        let column_type = result.column_types.get(grid.column()).map_or("", String::as_str);
+        let (cell, column, column_type) = (row[grid.column()].clone(), column.clone(), column_type.to_string());
+
+        let Some(browser) = &mut self.browser else {
+            return;
+        };
+        if browser.add_cell_condition(&column, &cell, &column_type) {
+            self.run_browser().await;
+        } else {
+            self.set_error("Cannot filter by a value that could not be decoded".to_string());
+        }
+    }
+
+    /// Whether the shown result is in edit mode
+    pub fn editing(&self) -> bool {
+        self.result_tabs.get(self.selected_tab).is_some_and(|tab| tab.edit.is_some())
+    }
+
+    /// Starts editing the shown result if it comes from a single table and includes
+    /// its primary key, or leaves edit mode, asking first when changes are pending
+    pub async fn toggle_edit_mode(&mut self) {
+        let Some(tab) = self.result_tabs.get_mut(self.selected_tab) else {
+            return;
+        };
+        if let Some(edit) = &tab.edit {
+            if edit.is_empty() {
+                tab.edit = None;
+                self.set_status("Left edit mode".to_string());
+            } else {
+                self.popup = Some(Popup::Confirm {
+                    message: "Discard the pending changes?".to_string(),
+                    action: ConfirmAction::DiscardEdits,
+                });
+            }
+            return;
+        }
+
+        let (Some(db), Ok(result)) = (&self.db_manager, &tab.result) else {
+            return;
+        };
+        let Some(table) = source_table(&tab.statement, db.driver()) else {
+            self.set_error("Only results selected from a single table can be edited".to_string());
+            return;
+        };
+        let key = match db.primary_key(&table).await {
+            Ok(key) => key,
+            Err(e) => {
+                let db_error: DatabaseError = e.into();
+                self.set_error(format_error(&db_error));
+                return;
+            }
+        };
+        if key.is_empty() {
+            self.set_error(format!("{} has no primary key to find edited rows by", table));
+            return;
+        }
+        let Some(key_columns) = key
+            .iter()
+            .map(|column| result.headers.iter().position(|header| header == column))
+            .collect::<Option<Vec<usize>>>()
+        else {
+            self.set_error(format!("Select the primary key ({}) to edit {}", key.join(", "), table));
+            return;
+        };
+
+        tab.edit = Some(EditSession::new(table.clone(), key_columns, result.rows.len()));
+        self.set_status(format!(
+            "Editing {}: Enter edits a cell, A adds a row, D deletes one, P previews the changes",
+            table
+        ));
+    }
+
+    /// Leaves edit mode, dropping the pending changes and inserted rows
+    pub fn discard_edits(&mut self) {
+        let Some(ResultTab { result: Ok(result), grid, edit, .. }) = self.result_tabs.get_mut(self.selected_tab) else {
+            return;
+        };
+        if let Some(edit) = edit.take() {
+            result.rows.truncate(edit.loaded_rows);
+            result.refresh_view();
+            grid.move_row(0, result.visible_count());
+            self.set_status("Discarded the pending changes".to_string());
+        }
+    }
+
+    /// Opens the prompt for a new value of the selected cell in edit mode
+    pub fn open_cell_editor(&mut self) {
+        let Some(ResultTab { result: Ok(result), grid, edit: Some(edit), .. }) = self.result_tabs.get(self.selected_tab) else {
+            return;
+        };
+        let Some(row) = result.row_index(grid.row()) else {
+            return;
+        };
+        let cell = edit.change(row, grid.column()).unwrap_or(&result.rows[row][grid.column()]);
+        let text = match cell {
+            CellValue::Value(text) => text.clone(),
+            _ => String::new(),
+        };
+        self.popup = Some(Popup::EditCell { text });
+    }
+
+    /// Sets the selected cell to a new value in edit mode
+    pub fn set_edited_cell(&mut self, value: CellValue) {
+        let Some(ResultTab { result: Ok(result), grid, edit: Some(edit), .. }) = self.result_tabs.get_mut(self.selected_tab) else {
+            return;
+        };
+        if let Some(row) = result.row_index(grid.row()) {
+            grid.fit_text(grid.column(), &value.to_string());
+            edit.set_cell(row, grid.column(), value, &result.rows[row][grid.column()]);
+        }
+    }
+
+    /// Adds an empty row to insert in edit mode and selects it
+    pub fn add_edited_row(&mut self) {
+        let Some(ResultTab { result: Ok(result), grid, edit: Some(_), .. }) = self.result_tabs.get_mut(self.selected_tab) else {
+            return;
+        };
+        result.rows.push(vec![CellValue::Null; result.headers.len()]);
+        result.refresh_view();
+        for column in 0..result.headers.len() {
+            grid.fit_text(column, "DEFAULT");
+        }
+        match result.visible_index(result.rows.len() - 1) {
+            Some(index) => {
+                grid.move_row(index as isize - grid.row() as isize, result.visible_count());
+                grid.first_column();
+            }
+            None => self.set_status("Added a row, hidden by the filter".to_string()),
+        }
+    }
+
+    /// Marks the selected row for deletion in edit mode, or unmarks it
+    pub fn toggle_deleted_row(&mut self) {
+        let Some(ResultTab { result: Ok(result), grid, edit: Some(edit), .. }) = self.result_tabs.get_mut(self.selected_tab) else {
+            return;
+        };
+        if let Some(row) = result.row_index(grid.row()) {
+            edit.toggle_deleted(row);
+        }
+    }
+
+    /// Undoes the pending change of the selected cell, or else the deletion of its row
+    pub fn revert_edited_cell(&mut self) {
+        let Some(ResultTab { result: Ok(result), grid, edit: Some(edit), .. }) = self.result_tabs.get_mut(self.selected_tab) else {
+            return;
+        };
+        if let Some(row) = result.row_index(grid.row()) {
+            if !edit.revert(row, grid.column()) {
+                self.set_status("Nothing to undo in this cell".to_string());
+            }
+        }
+    }
+
+    /// Gets the statements applying the pending edits of the shown result
+    pub fn edit_statements(&self) -> Result<Vec<String>, String> {
+        match self.result_tabs.get(self.selected_tab) {
+            Some(ResultTab { result: Ok(result), edit: Some(edit), .. }) => edit.statements(result, self.driver()),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Opens the preview of the statements applying the pending edits
+    pub fn open_edit_preview(&mut self) {
+        match self.edit_statements() {
+            Ok(statements) if statements.is_empty() => self.set_status("No pending changes".to_string()),
+            Ok(_) => self.popup = Some(Popup::EditPreview { scroll: 0 }),
+            Err(e) => self.set_error(e),
+        }
+    }
+
+    /// Applies the pending edits in one transaction, then runs the query again to
+    /// show the rows as they are stored
+    pub async fn apply_edits(&mut self) {
+        let statements = match self.edit_statements() {
+            Ok(statements) => statements,
+            Err(e) => return self.set_error(e),
+        };
+        let Some(db) = &self.db_manager else {
+            self.set_error("Not connected to database".to_string());
+            return;
+        };
+        if self.has_open_transaction() {
+            self.set_error("Commit or roll back the open transaction before applying edits".to_string());
+            return;
+        }
+        if self.running_query.is_some() {
+            self.set_error("A query is already running".to_string());
+            return;
+        }
+
+        if let Err(e) = db.apply_row_changes(&statements).await {
+            let db_error: DatabaseError = e.into();
+            self.set_error(format!("Changes rolled back: {}", format_error(&db_error)));
+            return;
+        }
+        let tab = &mut self.result_tabs[self.selected_tab];
+        tab.edit = None;
+        let statement = tab.statement.clone();
+        self.run_query(statement).await;
+        if self.running_query.is_some() {
+            self.applied_edits = Some(statements.len());
+        }
+    }
+
+    /// Sorts the shown result by the selected column, cycling it through ascending,
+    /// descending and unsorted. With `add` the column becomes another sort key after
+    /// the existing ones, otherwise it replaces them.
+    pub fn sort_by_column(&mut self, add: bool) {
+        let Some(ResultTab { result: Ok(result), grid, .. }) = self.result_tabs.get_mut(self.selected_tab) else {
+            return;
+        };
+        if result.headers.is_empty() {
+            return;
+        }
+
+        let column = grid.column();
+        // Replacing the keys starts over unless the column is already the only one
+        if !add && result.sort.iter().any(|key| key.column != column) {
+            result.sort.clear();
+        }
+        match result.sort.iter().position(|key| key.column == column) {
+            Some(position) if result.sort[position].ascending => result.sort[position].ascending = false,
+            Some(position) => {
+                result.sort.remove(position);
+            }
+            None => result.sort.push(SortKey { column, ascending: true }),
+        }
+        result.refresh_view();
+        grid.first_row();
+        grid.clear_selection();
+
+        let keys: Vec<String> = result
+            .sort
+            .iter()
+            .map(|key| format!("{} {}", result.headers[key.column], if key.ascending { "ascending" } else { "descending" }))
+            .collect();
+        if keys.is_empty() {
+            self.set_status("Sorting cleared".to_string());
+        } else {
+            self.set_status(format!("Sorted by {}", keys.join(", then ")));
+        }
+    }
+
+    /// Filters the rows of the shown result by a pattern as it is typed, searching
+    /// one column or every column. An empty pattern shows all rows again.
+    pub fn filter_rows(&mut self, pattern: &str, column: Option<usize>) {
+        let Some(ResultTab { result: Ok(result), grid, .. }) = self.result_tabs.get_mut(self.selected_tab) else {
+            return;
+        };
+
+        let filter = match pattern {
+            "" | "/" => None,
+            pattern => match RowFilter::new(pattern, column) {
+                Ok(filter) => Some(filter),
+                Err(e) => {
+                    // Keep the last valid filter while the expression is incomplete. The
+                    // error's last line says what is wrong, without the pointer to it.
+                    let e = e.to_string();
+                    let reason = e.lines().last().unwrap_or_default().trim_start_matches("error: ");
+                    self.set_error(format!("Invalid regular expression: {}", reason));
+                    return;
+                }
+            },
+        };
+        result.filter = filter;
+        result.refresh_view();
+        grid.first_row();
+        grid.clear_selection();
+        self.last_error = None;
+    }
+
+    /// Cancels the background query from a separate connection
+    pub async fn cancel_query(&mut self) {
+        let Some(running) = &self.running_query else {
+            return;
+        };
+        if self.db_manager.as_ref().is_some_and(|db| !db.driver().can_cancel()) {
+            self.set_error("Queries cannot be cancelled with this driver".to_string());
+            return;
+        }
+        let (Some(db), Some(pid)) = (&self.db_manager, running.backend_pid()) else {
+            self.set_status("Query is still connecting, try again".to_string());
+            return;
+        };
+
+        match db.cancel_backend(pid).await {
+            Ok(true) => self.set_status("Cancelling query...".to_string()),
+            Ok(false) => self.set_error("Could not cancel the query".to_string()),
+            Err(e) => {
+                let db_error: DatabaseError = e.into();
+                self.set_error(format_error(&db_error));
+            }
+        }
+    }
+
+    /// Whether transaction mode has a transaction open, including a failed one
+    pub fn has_open_transaction(&self) -> bool {
+        self.session
+            .as_ref()
+            .is_some_and(|session| session.status() != TransactionStatus::Idle)
+    }
+
+    /// Fails if a transaction is open, so it is not silently dropped with its connection
+    fn ensure_no_open_transaction(&self) -> anyhow::Result<()> {
+        if self.has_open_transaction() {
+            anyhow::bail!("Commit or roll back the open transaction first");
+        }
+        Ok(())
+    }
+
+    /// Turns transaction mode on, pinning a connection, or off, releasing it
+    pub async fn toggle_transaction_mode(&mut self) {
+        if self.running_query.is_some() {
+            self.set_error("Wait for the running query to finish".to_string());
+            return;
+        }
+        if self.session.is_some() {
+            if let Err(e) = self.ensure_no_open_transaction() {
+                self.set_error(e.to_string());
+                return;
+            }
+            self.close_result_cursor().await;
+            self.session = None;
+            self.set_status("Transaction mode off, statements autocommit".to_string());
+            return;
+        }
+
+        let Some(db) = &self.db_manager else {
+            self.set_error("Not connected to database".to_string());
+            return;
+        };
+        match db.open_session().await {
+            Ok(session) => {
+                self.close_result_cursor().await;
+                self.session = Some(session);
+                self.set_status("Transaction mode on, statements run in a transaction until committed".to_string());
+            }
+            Err(e) => {
+                let db_error: DatabaseError = e.into();
+                self.set_error(format_error(&db_error));
+            }
+        }
+    }
+
+    /// Commits the open transaction of transaction mode
+    pub async fn commit_transaction(&mut self) {
+        let Some(session) = self.open_transaction_session() else {
+            return;
+        };
+        let failed = session.status() == TransactionStatus::Failed;
+
+        // Ending the transaction closes its cursor on the server
+        self.result_cursor = None;
+        match session.commit().await {
+            Ok(()) if failed => self.set_error("The transaction had failed and was rolled back".to_string()),
+            Ok(()) => self.set_status("Transaction committed".to_string()),
+            Err(e) => {
+                let db_error: DatabaseError = e.into();
+                self.set_error(format_error(&db_error));
+            }
+        }
+    }
+
+    /// Rolls back the open transaction of transaction mode
+    pub async fn rollback_transaction(&mut self) {
+        let Some(session) = self.open_transaction_session() else {
+            return;
+        };
+
+        self.result_cursor = None;
+        match session.rollback().await {
+            Ok(()) => self.set_status("Transaction rolled back".to_string()),
+            Err(e) => {
+                let db_error: DatabaseError = e.into();
+                self.set_error(format_error(&db_error));
+            }
+        }
+    }
+
+    /// Creates a savepoint in transaction mode, opening a transaction if none is open
+    pub async fn create_savepoint(&mut self) {
+        let Some(session) = self.idle_session() else {
+            return;
+        };
+
+        match session.savepoint().await {
+            Ok(name) => self.set_status(format!("Savepoint {} created", name)),
+            Err(e) => {
+                let db_error: DatabaseError = e.into();
+                self.set_error(format_error(&db_error));
+            }
+        }
+    }
+
+    /// Rolls back to the latest savepoint of the open transaction
+    pub async fn rollback_to_savepoint(&mut self) {
+        let Some(session) = self.open_transaction_session() else {
+            return;
+        };
+
+        self.close_result_cursor().await;
+        match session.rollback_to_savepoint().await {
+            Ok(Some(name)) => self.set_status(format!("Rolled back to savepoint {}", name)),
+            Ok(None) => self.set_error("No savepoint to roll back to".to_string()),
+            Err(e) => {
+                let db_error: DatabaseError = e.into();
+                self.set_error(format_error(&db_error));
+            }
+        }
+    }
+
+    /// Gets the session when transaction mode is on and no query is running
+    fn idle_session(&mut self) -> Option<Session> {
+        if self.running_query.is_some() {
+            self.set_error("Wait for the running query to finish".to_string());
+            return None;
+        }
+        if self.session.is_none() {
+            self.set_error("Transaction mode is off (press T to turn it on)".to_string());
+        }
+        self.session.clone()
+    }
+
+    /// Gets the session when it has a transaction open and no query is running
+    fn open_transaction_session(&mut self) -> Option<Session> {
+        let session = self.idle_session()?;
+        if session.status() == TransactionStatus::Idle {
+            self.set_error("No transaction is open".to_string());
+            return None;
+        }
+        Some(session)
+    }
+
+    /// Opens the completion popup for the word before the cursor, loading the
+    /// catalog first if needed. Opened `explicitly`, a single candidate is inserted
+    /// right away; otherwise the popup only opens when there are candidates.
+    pub async fn open_completion(&mut self, explicitly: bool) {
+        if self.catalog.is_none() {
+            if !explicitly {
+                return;
+            }
+            self.refresh_catalog().await;
+        }
+        let Some(catalog) = &self.catalog else {
+            return;
+        };
+
+        let (prefix_len, items) = complete(catalog, self.driver(), &self.input.buffer(), self.input.cursor_offset());
+        self.completion = match items.len() {
+            0 => {
+                if explicitly {
+                    self.set_status("No completions".to_string());
+                }
+                None
+            }
+            1 if explicitly => {
+                self.input.replace_before_cursor(prefix_len, &items[0].text);
+                None
+            }
+            _ => Some(CompletionState {
+                items,
+                selected: 0,
+                prefix_len,
+            }),
+        };
+    }
+
+    /// Updates the open completion popup after the word before the cursor changed,
+    /// closing it when nothing matches or the word was deleted
+    pub fn update_completion(&mut self) {
+        let (Some(catalog), Some(_)) = (&self.catalog, &self.completion) else {
+            return;
+        };
+
+        let buffer = self.input.buffer();
+        let cursor = self.input.cursor_offset();
+        let (prefix_len, items) = complete(catalog, self.driver(), &buffer, cursor);
+        let after_dot = buffer[..cursor - prefix_len].ends_with('.');
+        self.completion = if items.is_empty() || (prefix_len == 0 && !after_dot) {
+            None
+        } else {
+            Some(CompletionState {
+                items,
+                selected: 0,
+                prefix_len,
+            })
+        };
+    }
+
+    /// Replaces the word before the cursor with the highlighted completion
+    pub fn accept_completion(&mut self) {
+        if let Some(completion) = self.completion.take() {
+            if let Some(item) = completion.items.get(completion.selected) {
+                self.input.replace_before_cursor(completion.prefix_len, &item.text);
             }
         }
     }
-}
\ No newline at end of file
+
+    /// Whether the character before the cursor can start a completion
+    pub fn can_complete(&self) -> bool {
+        let cursor = self.input.cursor();
+        let line = &self.input.lines()[cursor.line];
+        line[..cursor.column]
+            .bytes()
+            .last()
+            .is_some_and(|byte| is_word_byte(byte) || byte == b'.')
+    }
+
+    /// Reloads the object names used for completion, e.g. after DDL
+    pub async fn refresh_catalog(&mut self) {
+        let Some(db) = &self.db_manager else {
+            self.set_error("Not connected to database".to_string());
+            return;
+        };
+
+        match db.load_catalog().await {
+            Ok(catalog) => {
+                self.set_status(format!(
+                    "Loaded {} schemas, {} tables and {} functions for completion",
+                    catalog.schemas.len(),
+                    catalog.tables.len(),
+                    catalog.functions.len()
+                ));
+                self.catalog = Some(catalog);
+            }
+            Err(e) => {
+                let db_error: DatabaseError = e.into();
+                self.set_error(format_error(&db_error));
+            }
+        }
+    }
+
+    /// Fetches the next page of the last result, if more rows are available
+    pub async fn load_more_rows(&mut self) {
+        let Some(cursor) = &mut self.result_cursor else {
+            return;
+        };
+
+        // Inserted rows come after the loaded ones, so no more are loaded while editing
+        if self.result_tabs.first().is_some_and(|tab| tab.edit.is_some()) {
+            return;
+        }
+
+        match cursor.fetch_page().await {
+            Ok(page) => {
+                let exhausted = page.row_count() < cursor.page_size();
+                // Only single statements are paged, so the cursor belongs to the only tab
+                if let Some(ResultTab { result: Ok(result), grid, .. }) = self.result_tabs.first_mut() {
+                    let first_new = result.rows.len();
+                    result.rows.extend(page.rows_as_values());
+                    result.refresh_view();
+                    grid.fit_columns(result, first_new);
+                }
+                if exhausted {
+                    self.close_result_cursor().await;
+                }
+            }
+            Err(e) => {
+                self.result_cursor = None;
+                let db_error: DatabaseError = e.into();
+                self.set_error(format_error(&db_error));
+            }
+        }
+    }
+
+    /// Closes the cursor of the last result, if it is still open
+    pub async fn close_result_cursor(&mut self) {
+        if let Some(cursor) = self.result_cursor.take() {
+            if let Err(e) = cursor.close().await {
+                warn!("Failed to close result cursor: {}", e);
+            }
+        }
+    }
+}
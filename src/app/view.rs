@@ -0,0 +1,159 @@
+use std::cmp::Ordering;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use regex::{Regex, RegexBuilder};
+
+use crate::database::CellValue;
+
+/// A column the loaded rows are sorted by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortKey {
+    /// Index of the column
+    pub column: usize,
+    /// Whether smaller values come first
+    pub ascending: bool,
+}
+
+/// A filter hiding the loaded rows that do not match a pattern
+#[derive(Debug, Clone)]
+pub struct RowFilter {
+    /// The pattern as typed; a leading `/` makes the rest a regular expression
+    pub pattern: String,
+    /// Column to search, or every column when `None`
+    pub column: Option<usize>,
+    /// The pattern compiled, with substrings escaped
+    matcher: Regex,
+}
+
+impl RowFilter {
+    /// Compiles a filter. Matching ignores case unless the pattern has an upper case letter.
+    pub fn new(pattern: &str, column: Option<usize>) -> Result<Self, regex::Error> {
+        let expression = match pattern.strip_prefix('/') {
+            Some(regex) => regex.to_string(),
+            None => regex::escape(pattern),
+        };
+        let matcher = RegexBuilder::new(&expression)
+            .case_insensitive(!pattern.chars().any(char::is_uppercase))
+            .build()?;
+        Ok(Self {
+            pattern: pattern.to_string(),
+            column,
+            matcher,
+        })
+    }
+
+    /// Whether a row has a matching cell, comparing cells as they are shown
+    pub fn matches(&self, row: &[CellValue]) -> bool {
+        let is_match = |cell: &CellValue| self.matcher.is_match(&cell.to_string());
+        match self.column {
+            Some(column) => row.get(column).is_some_and(is_match),
+            None => row.iter().any(is_match),
+        }
+    }
+}
+
+/// Orders the rows to show: those passing the filter, sorted by the sort keys
+/// with ties kept in their loaded order. `None` means all rows as loaded.
+pub fn visible_rows(
+    rows: &[Vec<CellValue>],
+    column_types: &[String],
+    sort: &[SortKey],
+    filter: Option<&RowFilter>,
+) -> Option<Vec<usize>> {
+    if sort.is_empty() && filter.is_none() {
+        return None;
+    }
+
+    let mut indices: Vec<usize> = (0..rows.len())
+        .filter(|index| filter.is_none_or(|filter| filter.matches(&rows[*index])))
+        .collect();
+    indices.sort_by(|a, b| {
+        sort.iter()
+            .map(|key| {
+                let column_type = column_types.get(key.column).map_or("", String::as_str);
+                let ordering = compare_cells(&rows[*a][key.column], &rows[*b][key.column], column_type);
+                if key.ascending { ordering } else { ordering.reverse() }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+    Some(indices)
+}
+
+/// Compares two cells of a column the way Postgres would order them: numbers by
+/// value, dates and timestamps in time order and NULL after everything else
+pub fn compare_cells(a: &CellValue, b: &CellValue, column_type: &str) -> Ordering {
+    let (a, b) = match (a, b) {
+        (CellValue::Null, CellValue::Null) => return Ordering::Equal,
+        (CellValue::Null, _) => return Ordering::Greater,
+        (_, CellValue::Null) => return Ordering::Less,
+        (CellValue::Value(a), CellValue::Value(b)) => (a, b),
+        // Undecodable cells only show their type, so they sort after the values
+        (CellValue::Undecodable(_), CellValue::Undecodable(_)) => return Ordering::Equal,
+        (CellValue::Undecodable(_), _) => return Ordering::Greater,
+        (_, CellValue::Undecodable(_)) => return Ordering::Less,
+    };
+
+    match column_type {
+        "INT2" | "INT4" | "INT8" | "FLOAT4" | "FLOAT8" | "NUMERIC" | "OID" | "" => {
+            compare_parsed(a, b, |text| text.parse::<f64>().ok(), f64::total_cmp)
+        }
+        "DATE" => compare_parsed(a, b, |text| NaiveDate::parse_from_str(text, "%Y-%m-%d").ok(), Ord::cmp),
+        "TIMESTAMP" | "TIMESTAMPTZ" => compare_parsed(
+            a,
+            b,
+            |text| NaiveDateTime::parse_from_str(text.trim_end_matches("+00"), "%Y-%m-%d %H:%M:%S%.f").ok(),
+            Ord::cmp,
+        ),
+        _ => a.cmp(b),
+    }
+}
+
+/// Compares two values by their parsed form. Values that do not parse, such as
+/// text in a column of unknown type, come after those that do and compare as text.
+fn compare_parsed<T>(
+    a: &str,
+    b: &str,
+    parse: impl Fn(&str) -> Option<T>,
+    compare: impl Fn(&T, &T) -> Ordering,
+) -> Ordering {
+    match (parse(a), parse(b)) {
+        (Some(a), Some(b)) => compare(&a, &b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visible_rows() {
+        let value = |text: &str| CellValue::Value(text.to_string());
+        let rows = vec![
+            vec![value("10"), value("b"), value("2024-01-02 00:00:00")],
+            vec![value("9"), value("a"), CellValue::Null],
+            vec![CellValue::Null, value("B"), value("2023-12-31 23:59:59.5")],
+            vec![value("10"), value("a"), value("2024-01-02 00:00:00")],
+        ];
+        let types: Vec<String> = ["INT4", "TEXT", "TIMESTAMP"].iter().map(|t| t.to_string()).collect();
+        let key = |column: usize, ascending: bool| SortKey { column, ascending };
+
+        assert_eq!(visible_rows(&rows, &types, &[], None), None);
+        // Numbers by value, NULL last, equal values in loaded order
+        assert_eq!(visible_rows(&rows, &types, &[key(0, true)], None), Some(vec![1, 0, 3, 2]));
+        assert_eq!(visible_rows(&rows, &types, &[key(0, false)], None), Some(vec![2, 0, 3, 1]));
+        assert_eq!(visible_rows(&rows, &types, &[key(0, false), key(1, true)], None), Some(vec![2, 3, 0, 1]));
+        assert_eq!(visible_rows(&rows, &types, &[key(2, true), key(0, true)], None), Some(vec![2, 0, 3, 1]));
+
+        // Lower case patterns ignore case, a leading slash makes a regular expression
+        let filter = |pattern: &str, column| RowFilter::new(pattern, column).unwrap();
+        assert_eq!(visible_rows(&rows, &types, &[], Some(&filter("b", None))), Some(vec![0, 2]));
+        assert_eq!(visible_rows(&rows, &types, &[], Some(&filter("B", None))), Some(vec![2]));
+        assert_eq!(visible_rows(&rows, &types, &[], Some(&filter("/^1.$", Some(0)))), Some(vec![0, 3]));
+        assert_eq!(visible_rows(&rows, &types, &[key(1, true)], Some(&filter("null", None))), Some(vec![2, 1]));
+        assert!(RowFilter::new("/(", None).is_err());
+    }
+}
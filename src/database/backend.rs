@@ -0,0 +1,369 @@
+use std::fmt;
+use std::sync::atomic::AtomicI64;
+use std::time::Instant;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::pool::PoolConnection;
+use sqlx::{Column, Database, Either, Executor, Pool, Row};
+use tokio::sync::mpsc;
+
+use super::cursor::{fetch_first_page, RowCursor};
+use super::schema::{ColumnInfo, ConstraintInfo};
+use super::session::{TransactionState, TransactionStatus};
+use super::statement::{classify, StatementKind};
+use super::{Catalog, CellValue, ObjectKind, QueryResult, Session, StatementResult, TableStructure};
+
+/// Database servers that can be connected to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Driver {
+    /// PostgreSQL
+    #[default]
+    Postgres,
+    /// MySQL or MariaDB
+    #[serde(alias = "mariadb")]
+    Mysql,
+    /// SQLite, the database being a file path or `:memory:`
+    Sqlite,
+}
+
+impl Driver {
+    /// Gets the name used for the driver in the configuration
+    pub fn name(self) -> &'static str {
+        match self {
+            Driver::Postgres => "postgres",
+            Driver::Mysql => "mysql",
+            Driver::Sqlite => "sqlite",
+        }
+    }
+
+    /// Gets the port the server listens on by default, 0 for file databases
+    pub fn default_port(self) -> u16 {
+        match self {
+            Driver::Postgres => 5432,
+            Driver::Mysql => 3306,
+            Driver::Sqlite => 0,
+        }
+    }
+
+    /// Gets the kinds of objects the schema browser lists
+    pub fn object_kinds(self) -> &'static [ObjectKind] {
+        match self {
+            Driver::Postgres => &ObjectKind::ALL,
+            Driver::Mysql => &[ObjectKind::Table, ObjectKind::View, ObjectKind::Function],
+            Driver::Sqlite => &[ObjectKind::Table, ObjectKind::View],
+        }
+    }
+
+    /// Whether a running query can be cancelled from another connection
+    pub fn can_cancel(self) -> bool {
+        self != Driver::Sqlite
+    }
+}
+
+impl fmt::Display for Driver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Runs queries and reads the catalog of one kind of database server. Tables
+/// are passed as written in a query, e.g. `public."Order"`; the other catalog
+/// methods take schema and object names as they are stored.
+#[async_trait]
+pub trait Backend: Send + Sync + fmt::Debug {
+    /// Gets the driver the backend connects with
+    fn driver(&self) -> Driver;
+
+    /// Executes a query and returns all of its rows, the app always pages results
+    #[cfg(test)]
+    async fn execute_query(&self, query: &str) -> Result<QueryResult>;
+
+    /// Executes a query, reading only the first `page_size` rows of a row-returning
+    /// statement. The cursor for the remaining rows is returned if there may be more.
+    /// `backend_pid` is set to the server connection running the query once it is
+    /// known, so the query can be cancelled with `cancel_backend`.
+    async fn open_query(
+        &self,
+        query: &str,
+        page_size: usize,
+        backend_pid: &AtomicI64,
+    ) -> Result<(QueryResult, Option<RowCursor>)>;
+
+    /// Opens a cursor over a query that cannot change data, so the rows can be
+    /// read again (e.g. to export them) without repeating any side effects
+    async fn open_read_only_cursor(&self, query: &str, page_size: usize) -> Result<RowCursor>;
+
+    /// Runs statements one after another on a single connection, stopping at the
+    /// first error unless `continue_on_error` is set. Only the first `page_size` rows
+    /// of a row-returning statement are read. A transaction the script leaves open
+    /// is rolled back, which is reported by the returned flag.
+    async fn run_script(
+        &self,
+        statements: Vec<String>,
+        page_size: usize,
+        continue_on_error: bool,
+        backend_pid: &AtomicI64,
+    ) -> Result<(Vec<StatementResult>, bool)>;
+
+    /// Runs statements that change one row each in a single transaction. A statement
+    /// that fails or does not affect exactly one row rolls back all of them.
+    async fn apply_row_changes(&self, statements: &[String]) -> Result<()>;
+
+    /// Asks the server to cancel the statement running on another connection,
+    /// returning whether the cancel request was delivered
+    async fn cancel_backend(&self, pid: i64) -> Result<bool>;
+
+    /// Pins a connection for transaction mode
+    async fn open_session(&self) -> Result<Session> {
+        anyhow::bail!("Transaction mode is not supported by the {} driver", self.driver())
+    }
+
+    /// Gets a list of all databases
+    async fn list_databases(&self) -> Result<Vec<String>>;
+
+    /// Gets a list of all schemas in the current database
+    async fn list_schemas(&self) -> Result<Vec<String>>;
+
+    /// Gets the objects of the given kind in a schema
+    async fn list_objects(&self, schema: &str, kind: ObjectKind) -> Result<Vec<String>>;
+
+    /// Gets the columns of a table or view
+    async fn list_columns(&self, schema: &str, table: &str) -> Result<Vec<ColumnInfo>>;
+
+    /// Gets the index names of a table
+    async fn list_indexes(&self, schema: &str, table: &str) -> Result<Vec<String>>;
+
+    /// Gets the constraints of a table
+    async fn list_constraints(&self, schema: &str, table: &str) -> Result<Vec<ConstraintInfo>>;
+
+    /// Gets the primary key columns of a table in key order, empty if it has none
+    async fn primary_key(&self, table: &str) -> Result<Vec<String>>;
+
+    /// Gets the server's estimate of the number of rows in a table, if it has one
+    async fn estimate_rows(&self, table: &str) -> Result<Option<i64>>;
+
+    /// Reads the definition of a table or view
+    async fn table_structure(&self, table: &str) -> Result<TableStructure>;
+
+    /// Loads the schemas, tables with their columns and functions of the current database
+    async fn load_catalog(&self) -> Result<Catalog>;
+}
+
+/// Turns the rows a sqlx driver reads into results
+pub(super) trait Decoder: Database {
+    /// Decodes the cell at `index`
+    fn decode_cell(row: &Self::Row, index: usize) -> CellValue;
+
+    /// Gets the name Postgres gives a type, e.g. `INT4` for a MySQL `INT`, so the
+    /// grid and exports treat the values of every driver alike
+    fn type_name(type_info: &Self::TypeInfo) -> String;
+
+    /// Gets the type of the column at `index`, given the first row
+    fn column_type(row: &Self::Row, index: usize) -> String {
+        Self::type_name(row.columns()[index].type_info())
+    }
+
+    /// Gets the number of rows a statement inserted, updated or deleted
+    fn rows_affected(done: &Self::QueryResult) -> u64;
+}
+
+// The helpers below run statements as plain text rather than as prepared statements,
+// which MySQL needs for statements it cannot prepare and which SQLite treats alike
+
+/// Executes a statement on a connection, collecting both its rows and how many
+/// rows it affected
+pub(super) async fn execute_text<DB: Decoder>(conn: &mut DB::Connection, query: &str) -> Result<QueryResult>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    let kind = classify(query);
+    let mut rows = Vec::new();
+    let mut rows_affected = 0;
+
+    let mut results = conn.fetch_many(query);
+    while let Some(item) = results.try_next().await? {
+        match item {
+            Either::Left(done) => rows_affected += DB::rows_affected(&done),
+            Either::Right(row) => rows.push(row),
+        }
+    }
+    drop(results);
+
+    let mut result = QueryResult::from_rows::<DB>(&rows);
+    match kind {
+        StatementKind::Dml => result.rows_affected = Some(rows_affected),
+        StatementKind::Other(tag) => result.command_tag = Some(tag),
+        StatementKind::Query => {}
+    }
+    Ok(result)
+}
+
+/// Reads at most `page_size` rows of a row-returning statement, leaving the rest unread
+pub(super) async fn fetch_text_page<DB: Decoder>(
+    conn: &mut DB::Connection,
+    query: &str,
+    page_size: usize,
+) -> Result<QueryResult>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    let mut rows = Vec::new();
+    let mut more_rows = false;
+
+    let mut stream = conn.fetch(query);
+    while let Some(row) = stream.try_next().await? {
+        if rows.len() == page_size {
+            more_rows = true;
+            break;
+        }
+        rows.push(row);
+    }
+    drop(stream);
+
+    let mut result = QueryResult::from_rows::<DB>(&rows);
+    result.more_rows = more_rows;
+    Ok(result)
+}
+
+/// Executes a query, reading a row-returning statement through `stream_rows`
+pub(super) async fn open_text_query<DB: Decoder>(
+    mut conn: PoolConnection<DB>,
+    query: &str,
+    page_size: usize,
+) -> Result<(QueryResult, Option<RowCursor>)>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    if classify(query) == StatementKind::Query {
+        return fetch_first_page(query, stream_rows(conn, query.to_string(), page_size, None)).await;
+    }
+    Ok((execute_text::<DB>(&mut conn, query).await?, None))
+}
+
+/// Reads the rows of a query on a background task holding the connection, a page
+/// at a time as the returned cursor asks for them. An empty first page carries
+/// the columns of the query. `finish` runs on the connection once the rows are
+/// read or the cursor is dropped, e.g. to end a transaction started for the query.
+pub(super) fn stream_rows<DB: Decoder>(
+    mut conn: PoolConnection<DB>,
+    query: String,
+    page_size: usize,
+    finish: Option<&'static str>,
+) -> RowCursor
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    // One page waits to be read while the next is fetched
+    let (sender, receiver) = mpsc::channel(1);
+
+    tokio::spawn(async move {
+        let mut sent = false;
+        let mut rows = Vec::new();
+        let mut stream = conn.fetch(query.as_str());
+        let last = loop {
+            match stream.try_next().await {
+                Ok(Some(row)) => {
+                    rows.push(row);
+                    if rows.len() == page_size {
+                        let page = QueryResult::from_rows::<DB>(&std::mem::take(&mut rows));
+                        if sender.send(Ok(page)).await.is_err() {
+                            break None;
+                        }
+                        sent = true;
+                    }
+                }
+                Ok(None) => break Some(Ok(QueryResult::from_rows::<DB>(&rows))),
+                Err(e) => break Some(Err(e.into())),
+            }
+        };
+        drop(stream);
+
+        if let Some(mut last) = last {
+            if let Ok(page) = &mut last {
+                if !sent && page.row_count() == 0 {
+                    // Without rows there is nothing to read the column names from
+                    if let Ok(described) = (&mut *conn).describe(query.as_str()).await {
+                        (page.columns, page.column_types) = described
+                            .columns()
+                            .iter()
+                            .map(|column| (column.name().to_string(), DB::type_name(column.type_info())))
+                            .unzip();
+                    }
+                }
+            }
+            let _ = sender.send(last).await;
+        }
+        if let Some(finish) = finish {
+            if let Err(e) = (&mut *conn).execute(finish).await {
+                tracing::warn!("Failed to finish streamed query: {}", e);
+            }
+        }
+    });
+
+    RowCursor::on_stream(receiver, page_size)
+}
+
+/// Runs statements one after another on a connection, like `Backend::run_script`.
+/// Whether the statements leave a transaction open is followed from the
+/// transaction statements among them.
+pub(super) async fn run_text_script<DB: Decoder>(
+    conn: &mut DB::Connection,
+    statements: Vec<String>,
+    page_size: usize,
+    continue_on_error: bool,
+) -> Result<(Vec<StatementResult>, bool)>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    let mut transaction = TransactionState {
+        status: TransactionStatus::Idle,
+        savepoints: Vec::new(),
+    };
+    let mut results = Vec::new();
+    for statement in statements {
+        let start_time = Instant::now();
+        let result = if classify(&statement) == StatementKind::Query {
+            fetch_text_page::<DB>(conn, &statement, page_size).await
+        } else {
+            execute_text::<DB>(conn, &statement).await
+        };
+        transaction.record(&statement, result.is_ok());
+
+        let failed = result.is_err();
+        results.push(StatementResult {
+            statement,
+            result,
+            execution_time: start_time.elapsed().as_millis(),
+        });
+        if failed && !continue_on_error {
+            break;
+        }
+    }
+
+    let in_transaction = transaction.status != TransactionStatus::Idle;
+    if in_transaction {
+        conn.execute("ROLLBACK").await?;
+    }
+    Ok((results, in_transaction))
+}
+
+/// Runs statements that change one row each in a single transaction, like
+/// `Backend::apply_row_changes`
+pub(super) async fn apply_text_changes<DB: Decoder>(pool: &Pool<DB>, statements: &[String]) -> Result<()>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+    let mut tx = pool.begin().await?;
+    for statement in statements {
+        let affected = DB::rows_affected(&(&mut *tx).execute(statement.as_str()).await?);
+        if affected != 1 {
+            anyhow::bail!("{} affected {} rows instead of 1", statement, affected);
+        }
+    }
+    tx.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use sqlx::Row;
+
+use super::postgres::PostgresBackend;
+
+/// Names of the objects in the current database, cached for completion
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    /// Schema names
+    pub schemas: Vec<String>,
+    /// Tables, views and materialized views outside the system schemas
+    pub tables: Vec<CatalogTable>,
+    /// Distinct names of callable functions
+    pub functions: Vec<String>,
+    /// Schema whose tables are written without qualification, e.g. `public`
+    pub default_schema: String,
+}
+
+/// A table, view or materialized view with its columns
+#[derive(Debug, Clone)]
+pub struct CatalogTable {
+    /// Schema name
+    pub schema: String,
+    /// Table name
+    pub name: String,
+    /// Column names in column order
+    pub columns: Vec<String>,
+}
+
+impl Catalog {
+    /// Finds a table by name, in the given schema or else preferring the default schema.
+    /// Names are compared as Postgres compares them after folding unquoted names.
+    pub fn table(&self, schema: Option<&str>, name: &str) -> Option<&CatalogTable> {
+        let matching = |table: &&CatalogTable| table.name == name && schema.is_none_or(|schema| table.schema == schema);
+        self.tables
+            .iter()
+            .filter(matching)
+            .find(|table| schema.is_some() || table.schema == self.default_schema)
+            .or_else(|| self.tables.iter().find(matching))
+    }
+}
+
+impl PostgresBackend {
+    /// Loads the schemas, tables with their columns and functions of the current database
+    pub async fn load_catalog(&self) -> Result<Catalog> {
+        let schemas = self.list_schemas().await?;
+
+        let rows = sqlx::query(
+            "SELECT n.nspname AS schema, c.relname AS name, \
+                    array_agg(a.attname::text ORDER BY a.attnum) FILTER (WHERE a.attnum IS NOT NULL) AS columns \
+             FROM pg_class c \
+             JOIN pg_namespace n ON n.oid = c.relnamespace \
+             LEFT JOIN pg_attribute a ON a.attrelid = c.oid AND a.attnum > 0 AND NOT a.attisdropped \
+             WHERE c.relkind IN ('r', 'p', 'f', 'v', 'm') \
+               AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+               AND n.nspname NOT LIKE 'pg_toast%' AND n.nspname NOT LIKE 'pg_temp_%' \
+             GROUP BY n.nspname, c.relname \
+             ORDER BY n.nspname, c.relname;",
+        )
+            .fetch_all(&self.pool)
+            .await?;
+        let tables = rows
+            .iter()
+            .map(|row| CatalogTable {
+                schema: row.get("schema"),
+                name: row.get("name"),
+                columns: row.get::<Option<Vec<String>>, _>("columns").unwrap_or_default(),
+            })
+            .collect();
+
+        // Functions taking or returning pseudo-types such as internal are not called from SQL
+        let functions = sqlx::query_scalar(
+            "SELECT DISTINCT p.proname::text \
+             FROM pg_proc p JOIN pg_namespace n ON n.oid = p.pronamespace \
+             WHERE n.nspname NOT IN ('information_schema') AND n.nspname NOT LIKE 'pg_toast%' \
+               AND p.prorettype NOT IN ('internal'::regtype, 'cstring'::regtype, 'trigger'::regtype) \
+               AND NOT p.proargtypes::oid[] && ARRAY['internal'::regtype, 'cstring'::regtype]::oid[] \
+             ORDER BY 1;",
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(Catalog {
+            schemas,
+            tables,
+            functions,
+            default_schema: "public".to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseConfig;
+
+    #[tokio::test]
+    async fn test_load_catalog() {
+        let db = PostgresBackend::connect(&DatabaseConfig::new()).await.unwrap();
+        sqlx::query("CREATE TABLE IF NOT EXISTS ferrum_test_catalog (id int, label text)")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let catalog = db.load_catalog().await.unwrap();
+        let table = catalog.table(None, "ferrum_test_catalog").expect("table is listed");
+        assert_eq!(table.columns, vec!["id", "label"]);
+        assert!(catalog.schemas.iter().any(|schema| schema == "public"));
+        assert!(catalog.functions.iter().any(|function| function == "now"));
+        assert!(!catalog.functions.iter().any(|function| function == "int4in"));
+
+        sqlx::query("DROP TABLE ferrum_test_catalog").execute(&db.pool).await.unwrap();
+    }
+}
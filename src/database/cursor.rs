@@ -0,0 +1,281 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use anyhow::Result;
+use sqlx::{Column, Executor, PgConnection, Postgres, Transaction, TypeInfo};
+use tokio::sync::mpsc;
+
+use super::postgres::{decode_rows, execute_on, PostgresBackend};
+use super::statement::{classify, StatementKind};
+use super::{QueryResult, Session};
+
+/// Name of the cursor declared for paged queries
+pub(super) const CURSOR_NAME: &str = "ferrum_cursor";
+
+/// A cursor over the remaining rows of a query
+#[derive(Debug)]
+pub struct RowCursor {
+    conn: CursorConnection,
+    page_size: usize,
+}
+
+/// Connection a cursor was declared on
+#[derive(Debug)]
+enum CursorConnection {
+    /// The cursor's own transaction. Dropping the cursor rolls it back and releases the connection.
+    Transaction(Box<Transaction<'static, Postgres>>),
+    /// The transaction of a session, which is left open when the cursor is closed
+    Session(Session),
+    /// Pages read by a background task that holds the connection, see `stream_rows`.
+    /// Dropping the cursor stops the task.
+    Stream(mpsc::Receiver<Result<QueryResult>>),
+}
+
+impl RowCursor {
+    /// Creates a cursor over the cursor declared in the open transaction of a session
+    pub(super) fn on_session(session: Session, page_size: usize) -> Self {
+        Self {
+            conn: CursorConnection::Session(session),
+            page_size,
+        }
+    }
+
+    /// Creates a cursor over the pages a background task reads
+    pub(super) fn on_stream(receiver: mpsc::Receiver<Result<QueryResult>>, page_size: usize) -> Self {
+        Self {
+            conn: CursorConnection::Stream(receiver),
+            page_size,
+        }
+    }
+
+    /// Fetches the next page of rows
+    pub async fn fetch_page(&mut self) -> Result<QueryResult> {
+        // Not prepared: a cached FETCH would keep the row description of the first
+        // cursor it read and misdecode the rows of later ones on the same connection
+        let fetch = format!("FETCH FORWARD {} FROM {}", self.page_size, CURSOR_NAME);
+        match &mut self.conn {
+            CursorConnection::Transaction(tx) => {
+                let rows = sqlx::query(&fetch).persistent(false).fetch_all(&mut ***tx).await?;
+                decode_rows(tx, &rows).await
+            }
+            CursorConnection::Session(session) => {
+                let rows = session.fetch_all(&fetch).await?;
+                decode_rows(&mut **session.lock().await, &rows).await
+            }
+            // Once the task has sent the last page, the pages are empty
+            CursorConnection::Stream(receiver) => {
+                receiver.recv().await.unwrap_or_else(|| Ok(QueryResult::default()))
+            }
+        }
+    }
+
+    /// Gets the number of rows fetched per page
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Closes the cursor. Its own transaction is committed, keeping any side effects of the query.
+    pub async fn close(self) -> Result<()> {
+        let close = format!("CLOSE {}", CURSOR_NAME);
+        match self.conn {
+            CursorConnection::Transaction(mut tx) => {
+                sqlx::query(&close).execute(&mut **tx).await?;
+                (*tx).commit().await?;
+            }
+            CursorConnection::Session(session) => session.execute(&close).await?,
+            CursorConnection::Stream(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Gets the column names and type names of a query from the cursor's connection
+    async fn describe(&mut self, query: &str) -> Result<(Vec<String>, Vec<String>)> {
+        let described = match &mut self.conn {
+            CursorConnection::Transaction(tx) => (&mut ***tx).describe(query).await?,
+            CursorConnection::Session(session) => (&mut **session.lock().await).describe(query).await?,
+            // The task describes the query itself when it has no rows
+            CursorConnection::Stream(_) => return Ok((Vec::new(), Vec::new())),
+        };
+        Ok(described
+            .columns()
+            .iter()
+            .map(|c| (c.name().to_string(), c.type_info().name().to_string()))
+            .unzip())
+    }
+}
+
+impl PostgresBackend {
+    /// Executes a query, fetching only the first `page_size` rows of a row-returning
+    /// statement from a server-side cursor. The cursor for the remaining rows is
+    /// returned if there may be more. `backend_pid` is set to the server process
+    /// running the query once it is known, so the query can be cancelled.
+    pub async fn open_query(
+        &self,
+        query: &str,
+        page_size: usize,
+        backend_pid: &AtomicI64,
+    ) -> Result<(QueryResult, Option<RowCursor>)> {
+        if classify(query) == StatementKind::Query {
+            let mut tx = self.pool.begin().await?;
+            record_backend_pid(&mut tx, backend_pid).await?;
+
+            match declare_cursor(&mut tx, query).await {
+                Ok(()) => {
+                    let cursor = RowCursor {
+                        conn: CursorConnection::Transaction(Box::new(tx)),
+                        page_size,
+                    };
+                    return fetch_first_page(query, cursor).await;
+                }
+                Err(e) => {
+                    // Not every statement can back a cursor (e.g. data-modifying CTEs). The
+                    // query has not run yet, so run it directly and let it report its own errors.
+                    tracing::debug!("Falling back to a plain query, cannot declare cursor: {}", e);
+                    tx.rollback().await?;
+                }
+            }
+        }
+
+        let mut conn = self.pool.acquire().await?;
+        record_backend_pid(&mut conn, backend_pid).await?;
+        Ok((execute_on(&mut conn, query).await?, None))
+    }
+
+    /// Declares a cursor over a query in a read-only transaction, so the rows can be
+    /// read again (e.g. to export them) without repeating any side effects
+    pub async fn open_read_only_cursor(&self, query: &str, page_size: usize) -> Result<RowCursor> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SET TRANSACTION READ ONLY").execute(&mut *tx).await?;
+        declare_cursor(&mut tx, query).await?;
+        Ok(RowCursor {
+            conn: CursorConnection::Transaction(Box::new(tx)),
+            page_size,
+        })
+    }
+}
+
+/// Declares the cursor for a query in the transaction open on a connection
+pub(super) async fn declare_cursor(conn: &mut PgConnection, query: &str) -> Result<()> {
+    let declare = format!(
+        "DECLARE {} NO SCROLL CURSOR FOR {}",
+        CURSOR_NAME,
+        query.trim().trim_end_matches(';')
+    );
+    sqlx::query(&declare).persistent(false).execute(conn).await?;
+    Ok(())
+}
+
+/// Fetches the first page from a freshly opened cursor, closing it right away
+/// when the whole result fits in the page
+pub(super) async fn fetch_first_page(query: &str, mut cursor: RowCursor) -> Result<(QueryResult, Option<RowCursor>)> {
+    let mut page = cursor.fetch_page().await?;
+
+    if page.row_count() < cursor.page_size {
+        if page.columns.is_empty() {
+            // Without rows there is nothing to read the column names from
+            (page.columns, page.column_types) = cursor.describe(query).await?;
+        }
+        cursor.close().await?;
+        return Ok((page, None));
+    }
+
+    Ok((page, Some(cursor)))
+}
+
+/// Stores the backend process ID of a connection
+pub(super) async fn record_backend_pid(conn: &mut PgConnection, backend_pid: &AtomicI64) -> Result<()> {
+    let pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()")
+        .fetch_one(conn)
+        .await?;
+    backend_pid.store(pid.into(), Ordering::Relaxed);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseConfig;
+
+    #[tokio::test]
+    async fn test_open_query_pages() {
+        let db = PostgresBackend::connect(&DatabaseConfig::new()).await.unwrap();
+        let pid = AtomicI64::new(0);
+
+        let (page, cursor) = db
+            .open_query("SELECT g FROM generate_series(1, 25) g;", 10, &pid)
+            .await
+            .unwrap();
+        assert_eq!(page.row_count(), 10);
+        assert_ne!(pid.load(Ordering::Relaxed), 0);
+        let mut cursor = cursor.expect("more rows available");
+        assert_eq!(cursor.fetch_page().await.unwrap().rows_as_strings()[0], vec!["11"]);
+        assert_eq!(cursor.fetch_page().await.unwrap().row_count(), 5);
+        cursor.close().await.unwrap();
+
+        let (page, cursor) = db
+            .open_query("SELECT 1 AS a, 2 AS b WHERE false", 10, &pid)
+            .await
+            .unwrap();
+        assert!(cursor.is_none());
+        assert_eq!(page.columns(), vec!["a", "b"]);
+        assert_eq!(page.column_types(), vec!["INT4", "INT4"]);
+
+        let mut cursor = db.open_read_only_cursor("SELECT g FROM generate_series(1, 3) g", 2).await.unwrap();
+        assert_eq!(cursor.fetch_page().await.unwrap().row_count(), 2);
+        assert_eq!(cursor.fetch_page().await.unwrap().row_count(), 1);
+        cursor.close().await.unwrap();
+        assert!(db.open_read_only_cursor("DELETE FROM pg_class RETURNING 1", 2).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_backend() {
+        let db = PostgresBackend::connect(&DatabaseConfig::new()).await.unwrap();
+        let pid = AtomicI64::new(0);
+
+        let cancel = async {
+            // Wait until the query is actually sleeping, a cancel before that is a no-op
+            loop {
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                let sleeping: Option<bool> =
+                    sqlx::query_scalar("SELECT wait_event = 'PgSleep' FROM pg_stat_activity WHERE pid = $1::integer")
+                        .bind(pid.load(Ordering::Relaxed))
+                        .fetch_optional(&db.pool)
+                        .await
+                        .unwrap();
+                if sleeping == Some(true) {
+                    break;
+                }
+            }
+            db.cancel_backend(pid.load(Ordering::Relaxed)).await.unwrap()
+        };
+        let (result, cancelled) = tokio::join!(db.open_query("SELECT pg_sleep(30)", 10, &pid), cancel);
+
+        assert!(cancelled);
+        let error = result.unwrap_err();
+        let code = error
+            .downcast_ref::<sqlx::Error>()
+            .and_then(|e| e.as_database_error())
+            .and_then(|e| e.code());
+        assert_eq!(code.as_deref(), Some("57014"));
+    }
+
+    #[tokio::test]
+    async fn test_open_query_reports_affected_rows() {
+        let db = PostgresBackend::connect(&DatabaseConfig::new()).await.unwrap();
+        let pid = AtomicI64::new(0);
+        let run = |query| db.open_query(query, 10, &pid);
+
+        let (result, _) = run("CREATE TABLE IF NOT EXISTS ferrum_test_affected (id int)").await.unwrap();
+        assert_eq!(result.command_tag(), Some("CREATE TABLE"));
+
+        let (result, _) = run("INSERT INTO ferrum_test_affected SELECT generate_series(1, 3) RETURNING id")
+            .await
+            .unwrap();
+        assert_eq!((result.rows_affected(), result.row_count()), (Some(3), 3));
+
+        let (result, _) = run("DELETE FROM ferrum_test_affected").await.unwrap();
+        assert_eq!((result.rows_affected(), result.row_count()), (Some(3), 0));
+
+        let (result, _) = run("DROP TABLE ferrum_test_affected").await.unwrap();
+        assert_eq!(result.command_tag(), Some("DROP TABLE"));
+    }
+}
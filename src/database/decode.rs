@@ -0,0 +1,451 @@
+use std::fmt;
+
+use chrono::{Offset, TimeZone};
+use chrono_tz::Tz;
+
+use sqlx::postgres::types::{Oid, PgInterval, PgTimeTz};
+use sqlx::postgres::{PgRow, PgTypeKind, PgValueFormat};
+use sqlx::types::chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use sqlx::types::ipnetwork::IpNetwork;
+use sqlx::types::mac_address::MacAddress;
+use sqlx::types::{JsonValue, Uuid};
+use sqlx::{Column, Row, TypeInfo, ValueRef};
+
+/// A single decoded cell of a result row
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue {
+    /// A genuine SQL NULL
+    Null,
+    /// A value rendered as text
+    Value(String),
+    /// A non-NULL value whose type could not be decoded
+    Undecodable(String),
+}
+
+impl fmt::Display for CellValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellValue::Null => write!(f, "NULL"),
+            CellValue::Value(value) => write!(f, "{}", value),
+            CellValue::Undecodable(type_name) => write!(f, "<{}>", type_name),
+        }
+    }
+}
+
+/// Time zone `timestamptz` values are shown in, the session's `TimeZone` setting
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionTimeZone {
+    /// A zone from the time zone database, e.g. `Europe/Berlin`
+    Named(Tz),
+    /// A fixed offset from UTC, for POSIX-style settings such as `<+03>-03`
+    Fixed(FixedOffset),
+}
+
+impl Default for SessionTimeZone {
+    fn default() -> Self {
+        SessionTimeZone::Fixed(FixedOffset::east_opt(0).unwrap())
+    }
+}
+
+impl SessionTimeZone {
+    /// Parses a `TimeZone` setting. Zones the time zone database does not know
+    /// use `utc_offset`, the current offset from UTC in seconds.
+    pub fn new(name: &str, utc_offset: i32) -> Self {
+        match name.parse::<Tz>() {
+            Ok(zone) => SessionTimeZone::Named(zone),
+            Err(_) => FixedOffset::east_opt(utc_offset).map_or_else(Self::default, SessionTimeZone::Fixed),
+        }
+    }
+
+    /// Gets the offset from UTC at an instant
+    fn offset_at(&self, value: &DateTime<Utc>) -> FixedOffset {
+        match self {
+            SessionTimeZone::Named(zone) => zone.offset_from_utc_datetime(&value.naive_utc()).fix(),
+            SessionTimeZone::Fixed(offset) => *offset,
+        }
+    }
+}
+
+/// Whether a row has `timestamptz` columns, whose values depend on the time zone
+pub fn has_timestamptz(row: &PgRow) -> bool {
+    row.columns()
+        .iter()
+        .any(|column| matches!(column.type_info().name(), "TIMESTAMPTZ" | "TIMESTAMPTZ[]"))
+}
+
+/// Decodes the cell at `index` based on the column's Postgres type, showing
+/// `timestamptz` values in `time_zone`
+pub fn decode_cell(row: &PgRow, index: usize, time_zone: &SessionTimeZone) -> CellValue {
+    let raw = match row.try_get_raw(index) {
+        Ok(raw) => raw,
+        Err(_) => return CellValue::Undecodable("?".to_string()),
+    };
+
+    if raw.is_null() {
+        return CellValue::Null;
+    }
+
+    let type_info = raw.type_info().into_owned();
+    let type_name = type_info.name().to_string();
+
+    // Values sent in text format (e.g. simple query protocol) are already rendered by the server
+    if raw.format() == PgValueFormat::Text {
+        return match raw.as_str() {
+            Ok(text) => CellValue::Value(text.to_string()),
+            Err(_) => CellValue::Undecodable(type_name),
+        };
+    }
+
+    // Enum labels are sent as plain UTF-8 text in binary format too
+    if let PgTypeKind::Enum(_) = type_info.kind() {
+        return match raw.as_str() {
+            Ok(text) => CellValue::Value(text.to_string()),
+            Err(_) => CellValue::Undecodable(type_name),
+        };
+    }
+
+    match decode_value(row, index, &type_name, time_zone) {
+        Some(value) => CellValue::Value(value),
+        None => CellValue::Undecodable(type_name),
+    }
+}
+
+/// Decodes a non-NULL scalar or array value, returning `None` if the type is unsupported
+fn decode_value(row: &PgRow, index: usize, type_name: &str, time_zone: &SessionTimeZone) -> Option<String> {
+    if let Some(element) = type_name.strip_suffix("[]") {
+        return decode_array(row, index, element, time_zone);
+    }
+
+    let value = match type_name {
+        "BOOL" => format_bool(row.try_get(index).ok()?),
+        "INT2" => row.try_get::<i16, _>(index).ok()?.to_string(),
+        "INT4" => row.try_get::<i32, _>(index).ok()?.to_string(),
+        "INT8" => row.try_get::<i64, _>(index).ok()?.to_string(),
+        "OID" => row.try_get::<Oid, _>(index).ok()?.0.to_string(),
+        "FLOAT4" => format_float(row.try_get::<f32, _>(index).ok()?),
+        "FLOAT8" => format_float(row.try_get::<f64, _>(index).ok()?),
+        "NUMERIC" => format_numeric(row.try_get_raw(index).ok()?.as_bytes().ok()?)?,
+        "TEXT" | "VARCHAR" | "CHAR" | "NAME" | "UNKNOWN" | "citext" => row.try_get(index).ok()?,
+        "\"CHAR\"" => char::from(row.try_get::<i8, _>(index).ok()? as u8).to_string(),
+        "UUID" => row.try_get::<Uuid, _>(index).ok()?.to_string(),
+        "JSON" | "JSONB" => row.try_get::<JsonValue, _>(index).ok()?.to_string(),
+        "BYTEA" => format_bytea(&row.try_get::<Vec<u8>, _>(index).ok()?),
+        "DATE" => format_date(row.try_get(index).ok()?),
+        "TIME" => format_time(row.try_get(index).ok()?),
+        "TIMETZ" => format_timetz(row.try_get(index).ok()?),
+        "TIMESTAMP" => format_timestamp(row.try_get(index).ok()?),
+        "TIMESTAMPTZ" => format_timestamptz(row.try_get(index).ok()?, time_zone),
+        "INTERVAL" => format_interval(&row.try_get(index).ok()?),
+        "INET" => format_inet(row.try_get(index).ok()?),
+        "CIDR" => row.try_get::<IpNetwork, _>(index).ok()?.to_string(),
+        "MACADDR" => format_macaddr(row.try_get(index).ok()?),
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+/// Decodes a one-dimensional array into Postgres' `{a,b,NULL}` notation
+fn decode_array(row: &PgRow, index: usize, element: &str, time_zone: &SessionTimeZone) -> Option<String> {
+    let elements = match element {
+        "BOOL" => format_elements(row.try_get(index).ok()?, format_bool),
+        "INT2" => format_elements(row.try_get::<Vec<Option<i16>>, _>(index).ok()?, |v| v.to_string()),
+        "INT4" => format_elements(row.try_get::<Vec<Option<i32>>, _>(index).ok()?, |v| v.to_string()),
+        "INT8" => format_elements(row.try_get::<Vec<Option<i64>>, _>(index).ok()?, |v| v.to_string()),
+        "OID" => format_elements(row.try_get::<Vec<Option<Oid>>, _>(index).ok()?, |v| v.0.to_string()),
+        "FLOAT4" => format_elements(row.try_get::<Vec<Option<f32>>, _>(index).ok()?, format_float),
+        "FLOAT8" => format_elements(row.try_get::<Vec<Option<f64>>, _>(index).ok()?, format_float),
+        "NUMERIC" => {
+            let elements = split_array(row.try_get_raw(index).ok()?.as_bytes().ok()?)?;
+            let values = elements
+                .into_iter()
+                .map(|element| element.map(format_numeric).unwrap_or(Some("NULL".to_string())))
+                .collect::<Option<Vec<_>>>()?;
+            values.join(",")
+        }
+        "TEXT" | "VARCHAR" | "CHAR" | "NAME" => {
+            format_elements(row.try_get::<Vec<Option<String>>, _>(index).ok()?, |v| quote_element(&v))
+        }
+        "UUID" => format_elements(row.try_get::<Vec<Option<Uuid>>, _>(index).ok()?, |v| v.to_string()),
+        "JSON" | "JSONB" => format_elements(row.try_get::<Vec<Option<JsonValue>>, _>(index).ok()?, |v| {
+            quote_element(&v.to_string())
+        }),
+        "BYTEA" => format_elements(row.try_get::<Vec<Option<Vec<u8>>>, _>(index).ok()?, |v| {
+            quote_element(&format_bytea(&v))
+        }),
+        "DATE" => format_elements(row.try_get(index).ok()?, format_date),
+        "TIME" => format_elements(row.try_get(index).ok()?, format_time),
+        "TIMESTAMP" => format_elements(row.try_get(index).ok()?, |v| quote_element(&format_timestamp(v))),
+        "TIMESTAMPTZ" => format_elements(row.try_get(index).ok()?, |v| {
+            quote_element(&format_timestamptz(v, time_zone))
+        }),
+        "INTERVAL" => format_elements(row.try_get::<Vec<Option<PgInterval>>, _>(index).ok()?, |v| {
+            quote_element(&format_interval(&v))
+        }),
+        "INET" => format_elements(row.try_get(index).ok()?, format_inet),
+        "CIDR" => format_elements(row.try_get::<Vec<Option<IpNetwork>>, _>(index).ok()?, |v| v.to_string()),
+        "MACADDR" => format_elements(row.try_get(index).ok()?, format_macaddr),
+        _ => return None,
+    };
+
+    Some(format!("{{{}}}", elements))
+}
+
+/// Splits a one-dimensional binary array into its raw elements, `None` marking NULL elements
+fn split_array(bytes: &[u8]) -> Option<Vec<Option<&[u8]>>> {
+    let ndim = read_i32(bytes, 0)?;
+    if ndim == 0 {
+        return Some(Vec::new());
+    }
+    if ndim != 1 {
+        return None;
+    }
+
+    // Header: ndim, has_null flag, element oid, then (length, lower bound) per dimension
+    let len = usize::try_from(read_i32(bytes, 12)?).ok()?;
+    let mut offset = 20;
+    let mut elements = Vec::with_capacity(len);
+
+    for _ in 0..len {
+        let size = read_i32(bytes, offset)?;
+        offset += 4;
+        if size < 0 {
+            elements.push(None);
+        } else {
+            let end = offset + size as usize;
+            elements.push(Some(bytes.get(offset..end)?));
+            offset = end;
+        }
+    }
+
+    Some(elements)
+}
+
+fn read_i16(bytes: &[u8], offset: usize) -> Option<i16> {
+    Some(i16::from_be_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn read_i32(bytes: &[u8], offset: usize) -> Option<i32> {
+    Some(i32::from_be_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Joins array elements, rendering missing elements as NULL
+fn format_elements<T>(values: Vec<Option<T>>, format: impl Fn(T) -> String) -> String {
+    values
+        .into_iter()
+        .map(|value| value.map(&format).unwrap_or_else(|| "NULL".to_string()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Quotes an array element the way Postgres does when it contains special characters
+fn quote_element(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value.eq_ignore_ascii_case("NULL")
+        || value
+            .chars()
+            .any(|c| matches!(c, '{' | '}' | ',' | '"' | '\\') || c.is_whitespace());
+
+    if needs_quotes {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn format_bool(value: bool) -> String {
+    if value { "true" } else { "false" }.to_string()
+}
+
+/// Formats a REAL or DOUBLE PRECISION value in its own precision, so `0.1::real` stays `0.1`
+fn format_float<T: Copy + Into<f64> + ToString>(value: T) -> String {
+    let wide: f64 = value.into();
+    if wide.is_nan() {
+        "NaN".to_string()
+    } else if wide.is_infinite() {
+        if wide > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Formats a binary NUMERIC value, honouring its display scale and special values
+fn format_numeric(bytes: &[u8]) -> Option<String> {
+    let ndigits = read_i16(bytes, 0)?;
+    let weight = read_i16(bytes, 2)?;
+    let sign = read_i16(bytes, 4)? as u16;
+    let dscale = read_i16(bytes, 6)?;
+
+    match sign {
+        0xC000 => return Some("NaN".to_string()),
+        0xD000 => return Some("Infinity".to_string()),
+        0xF000 => return Some("-Infinity".to_string()),
+        _ => {}
+    }
+
+    // Digits are stored in base 10000, weight being the exponent of the first one
+    let digits = (0..ndigits as usize)
+        .map(|i| read_i16(bytes, 8 + i * 2))
+        .collect::<Option<Vec<_>>>()?;
+    let digit = |i: i32| -> i16 {
+        usize::try_from(i)
+            .ok()
+            .and_then(|i| digits.get(i).copied())
+            .unwrap_or(0)
+    };
+
+    let mut result = String::new();
+    if sign == 0x4000 {
+        result.push('-');
+    }
+
+    if weight < 0 {
+        result.push('0');
+    } else {
+        result.push_str(&digit(0).to_string());
+        for i in 1..=weight as i32 {
+            result.push_str(&format!("{:04}", digit(i)));
+        }
+    }
+
+    if dscale > 0 {
+        let mut fraction = String::new();
+        let mut i = weight as i32 + 1;
+        while fraction.len() < dscale as usize {
+            fraction.push_str(&format!("{:04}", digit(i)));
+            i += 1;
+        }
+        fraction.truncate(dscale as usize);
+        result.push('.');
+        result.push_str(&fraction);
+    }
+
+    Some(result)
+}
+
+pub(super) fn format_bytea(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(2 + bytes.len() * 2);
+    hex.push_str("\\x");
+    for byte in bytes {
+        hex.push_str(&format!("{:02x}", byte));
+    }
+    hex
+}
+
+fn format_date(value: NaiveDate) -> String {
+    value.format("%Y-%m-%d").to_string()
+}
+
+fn format_time(value: NaiveTime) -> String {
+    value.format("%H:%M:%S%.f").to_string()
+}
+
+fn format_timetz(value: PgTimeTz<NaiveTime, FixedOffset>) -> String {
+    format!("{}{}", format_time(value.time), value.offset)
+}
+
+fn format_timestamp(value: NaiveDateTime) -> String {
+    value.format("%Y-%m-%d %H:%M:%S%.f").to_string()
+}
+
+fn format_timestamptz(value: DateTime<Utc>, time_zone: &SessionTimeZone) -> String {
+    let offset = time_zone.offset_at(&value);
+    format!("{}{}", format_timestamp(value.with_timezone(&offset).naive_local()), format_offset(offset))
+}
+
+/// Formats an offset from UTC the way Postgres does, e.g. `+02`, `-03:30` or `+00:19:32`
+fn format_offset(offset: FixedOffset) -> String {
+    let seconds = offset.local_minus_utc();
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.unsigned_abs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if seconds != 0 {
+        format!("{}{:02}:{:02}:{:02}", sign, hours, minutes, seconds)
+    } else if minutes != 0 {
+        format!("{}{:02}:{:02}", sign, hours, minutes)
+    } else {
+        format!("{}{:02}", sign, hours)
+    }
+}
+
+/// Formats an interval using Postgres' default `postgres` interval style
+fn format_interval(interval: &PgInterval) -> String {
+    let mut parts = Vec::new();
+
+    push_unit(&mut parts, interval.months / 12, "year");
+    push_unit(&mut parts, interval.months % 12, "mon");
+    push_unit(&mut parts, interval.days, "day");
+
+    if interval.microseconds != 0 || parts.is_empty() {
+        let sign = if interval.microseconds < 0 { "-" } else { "" };
+        let micros = interval.microseconds.unsigned_abs();
+        let seconds = micros / 1_000_000;
+        let fraction = micros % 1_000_000;
+
+        let mut time = format!(
+            "{}{:02}:{:02}:{:02}",
+            sign,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+        if fraction != 0 {
+            time.push_str(format!(".{:06}", fraction).trim_end_matches('0'));
+        }
+        parts.push(time);
+    }
+
+    parts.join(" ")
+}
+
+fn push_unit(parts: &mut Vec<String>, value: i32, unit: &str) {
+    if value != 0 {
+        let plural = if value.abs() == 1 { "" } else { "s" };
+        parts.push(format!("{} {}{}", value, unit, plural));
+    }
+}
+
+/// Formats an inet value, omitting the prefix length for single hosts like Postgres does
+fn format_inet(value: IpNetwork) -> String {
+    let host_prefix = if value.is_ipv4() { 32 } else { 128 };
+    if value.prefix() == host_prefix {
+        value.ip().to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn format_macaddr(value: MacAddress) -> String {
+    value.to_string().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_interval() {
+        let interval = PgInterval {
+            months: 14,
+            days: 3,
+            microseconds: 14_706_500_000,
+        };
+        assert_eq!(format_interval(&interval), "1 year 2 mons 3 days 04:05:06.5");
+
+        let zero = PgInterval {
+            months: 0,
+            days: 0,
+            microseconds: 0,
+        };
+        assert_eq!(format_interval(&zero), "00:00:00");
+    }
+
+    #[test]
+    fn test_quote_element() {
+        assert_eq!(quote_element("plain"), "plain");
+        assert_eq!(quote_element("with space"), "\"with space\"");
+        assert_eq!(quote_element("null"), "\"null\"");
+        assert_eq!(quote_element("a\"b"), "\"a\\\"b\"");
+    }
+}
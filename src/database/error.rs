@@ -11,10 +11,6 @@ pub enum DatabaseError {
     #[error("Query execution failed: {0}")]
     QueryError(String),
 
-    /// Error occurred while parsing query results
-    #[error("Failed to parse query results: {0}")]
-    ResultError(String),
-
     /// Error occurred while managing database configuration
     #[error("Database configuration error: {0}")]
     ConfigError(String),
@@ -37,6 +33,9 @@ impl From<sqlx::Error> for DatabaseError {
 
 impl From<anyhow::Error> for DatabaseError {
     fn from(err: anyhow::Error) -> Self {
-        DatabaseError::Unknown(err.to_string())
+        match err.downcast::<sqlx::Error>() {
+            Ok(err) => err.into(),
+            Err(err) => DatabaseError::Unknown(err.to_string()),
+        }
     }
 }
\ No newline at end of file
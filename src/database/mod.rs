@@ -1,110 +1,205 @@
 use anyhow::Result;
-use sqlx::{postgres::PgPoolOptions, Pool, Postgres, Row, Column};
-use std::time::Duration;
+use std::collections::BTreeMap;
+use std::ops::Deref;
+use std::sync::Arc;
 
+mod backend;
+mod catalog;
+mod connection;
+mod cursor;
+mod decode;
 mod error;
+mod mysql;
+mod postgres;
+mod running;
+mod schema;
+mod script;
+mod session;
+mod splitter;
+mod sqlite;
+mod statement;
+mod structure;
+mod tokenizer;
+pub use backend::Driver;
+pub use catalog::{Catalog, CatalogTable};
+pub use connection::redact_password;
+pub use cursor::RowCursor;
+pub use decode::CellValue;
 pub use error::DatabaseError;
+pub use running::{QueryOutput, RunningQuery};
+pub use schema::ObjectKind;
+pub use script::StatementResult;
+pub use session::{Session, TransactionStatus};
+pub use splitter::split_statements;
+pub use structure::TableStructure;
+pub use tokenizer::{quote_identifier, split_qualified_name, tokenize, TokenKind, KEYWORDS};
+pub use statement::{destructive, modifies_data, source_table, transaction_control, DestructiveStatement, TransactionControl};
+use backend::{Backend, Decoder};
+use mysql::MySqlBackend;
+use postgres::PostgresBackend;
+use sqlite::SqliteBackend;
+use sqlx::{Column, Row};
 
 /// Configuration for database connection
 #[derive(Debug, Clone, Default)]
 pub struct DatabaseConfig {
-    /// Database host
+    /// Database server the connection is made to
+    pub driver: Driver,
+    /// Connection URL, e.g. `postgres://user@host/db`, filling in the fields left empty
+    pub url: Option<String>,
+    /// Database host, or the directory of a Unix-domain socket when it starts with `/`
     pub host: String,
-    /// Database port
+    /// Database port, 0 for the driver's default port
     pub port: u16,
     /// Database username
     pub username: String,
     /// Database password
     pub password: String,
-    /// Database name
+    /// Database name, or the database file for SQLite
     pub database: String,
+    /// Whether sessions only allow reading, e.g. with `default_transaction_read_only = on`
+    pub read_only: bool,
+    /// Further connection parameters, e.g. `sslmode`, or server settings such as `search_path`
+    pub params: BTreeMap<String, String>,
 }
 
 impl DatabaseConfig {
     /// Creates a new database configuration with default values
+    #[cfg(test)]
     pub fn new() -> Self {
         Self {
+            driver: Driver::Postgres,
             host: "localhost".to_string(),
             port: 5432,
             username: "postgres".to_string(),
             password: "postgres".to_string(),
             database: "postgres".to_string(),
+            read_only: false,
+            ..Self::default()
+        }
+    }
+
+    /// Gets the port to connect to, falling back to the driver's default port
+    pub fn server_port(&self) -> u16 {
+        if self.port == 0 {
+            self.driver.default_port()
+        } else {
+            self.port
         }
     }
 }
 
-/// Database connection manager
-#[derive(Debug)]
+/// Database connection manager. It derefs to the backend of the configured
+/// driver, which runs the queries and reads the catalog.
+#[derive(Debug, Clone)]
 pub struct DatabaseManager {
-    pool: Pool<Postgres>,
+    backend: Arc<dyn Backend>,
     config: DatabaseConfig,
 }
 
 impl DatabaseManager {
-    /// Creates a new database connection pool
+    /// Connects to the database with the driver named in the configuration, see
+    /// `DatabaseConfig::resolve` for where settings left out are taken from
     pub async fn new(config: DatabaseConfig) -> Result<Self> {
-        let connection_string = format!(
-            "postgres://{}:{}@{}:{}/{}",
-            config.username, config.password, config.host, config.port, config.database
-        );
-
-        let pool = PgPoolOptions::new()
-            .max_connections(5)
-            .acquire_timeout(Duration::from_secs(3))
-            .connect(&connection_string)
-            .await?;
+        let config = config.resolve()?;
+        let backend: Arc<dyn Backend> = match config.driver {
+            Driver::Postgres => Arc::new(PostgresBackend::connect(&config).await?),
+            Driver::Mysql => Arc::new(MySqlBackend::connect(&config).await?),
+            Driver::Sqlite => Arc::new(SqliteBackend::connect(&config).await?),
+        };
 
-        Ok(Self { pool, config })
+        Ok(Self { backend, config })
     }
 
-    /// Executes a query and returns the results
-    pub async fn execute_query(&self, query: &str) -> Result<QueryResult> {
-        let rows = sqlx::query(query)
-            .fetch_all(&self.pool)
-            .await?;
-
-        Ok(QueryResult::new(rows))
+    /// Gets the current database name
+    pub fn current_database(&self) -> &str {
+        &self.config.database
     }
 
-    /// Gets a list of all databases
-    pub async fn list_databases(&self) -> Result<Vec<String>> {
-        let rows = sqlx::query("SELECT datname FROM pg_database WHERE datistemplate = false;")
-            .fetch_all(&self.pool)
-            .await?;
-
-        Ok(rows
-            .iter()
-            .map(|row| row.get("datname"))
-            .collect())
+    /// Gets the schema unqualified names refer to when none is chosen: `public`,
+    /// SQLite's `main` or the MySQL database
+    pub fn default_schema(&self) -> &str {
+        match self.config.driver {
+            Driver::Postgres => "public",
+            Driver::Mysql => &self.config.database,
+            Driver::Sqlite => "main",
+        }
     }
 
-    /// Gets a list of all schemas in the current database
-    pub async fn list_schemas(&self) -> Result<Vec<String>> {
-        let rows = sqlx::query("SELECT schema_name FROM information_schema.schemata;")
-            .fetch_all(&self.pool)
-            .await?;
-
-        Ok(rows
-            .iter()
-            .map(|row| row.get("schema_name"))
-            .collect())
+    /// Gets the connection configuration
+    pub fn config(&self) -> &DatabaseConfig {
+        &self.config
     }
+}
 
-    /// Gets the current database name
-    pub fn current_database(&self) -> &str {
-        &self.config.database
+impl Deref for DatabaseManager {
+    type Target = dyn Backend;
+
+    fn deref(&self) -> &Self::Target {
+        self.backend.as_ref()
     }
 }
 
 /// Represents the result of a database query
+#[derive(Debug, Default)]
 pub struct QueryResult {
-    rows: Vec<sqlx::postgres::PgRow>,
+    /// Column names
+    columns: Vec<String>,
+    /// Column type names, see `column_types`
+    column_types: Vec<String>,
+    /// Decoded rows
+    rows: Vec<Vec<CellValue>>,
+    /// Number of rows inserted, updated or deleted by a DML statement
+    rows_affected: Option<u64>,
+    /// Command tag of a statement that does not return rows, e.g. `CREATE TABLE`
+    command_tag: Option<String>,
+    /// Whether the statement returned more rows than were read
+    more_rows: bool,
 }
 
 impl QueryResult {
-    /// Creates a new QueryResult
-    pub fn new(rows: Vec<sqlx::postgres::PgRow>) -> Self {
-        Self { rows }
+    /// Decodes the rows read by a driver, taking the columns from the first row
+    fn from_rows<DB: Decoder>(rows: &[DB::Row]) -> Self {
+        Self::decode_rows::<DB>(rows, DB::decode_cell)
+    }
+
+    /// Decodes the rows read by a driver with `decode_cell`, taking the columns from the first row
+    fn decode_rows<DB: Decoder>(rows: &[DB::Row], decode_cell: impl Fn(&DB::Row, usize) -> CellValue) -> Self {
+        let (columns, column_types) = match rows.first() {
+            Some(row) => row
+                .columns()
+                .iter()
+                .enumerate()
+                .map(|(index, column)| (column.name().to_string(), DB::column_type(row, index)))
+                .unzip(),
+            None => (Vec::new(), Vec::new()),
+        };
+        let rows = rows
+            .iter()
+            .map(|row| (0..row.columns().len()).map(|index| decode_cell(row, index)).collect())
+            .collect();
+
+        Self {
+            columns,
+            column_types,
+            rows,
+            ..Self::default()
+        }
+    }
+
+    /// Gets the number of rows affected by a DML statement
+    pub fn rows_affected(&self) -> Option<u64> {
+        self.rows_affected
+    }
+
+    /// Gets the command tag of a statement that does not return rows
+    pub fn command_tag(&self) -> Option<&str> {
+        self.command_tag.as_deref()
+    }
+
+    /// Whether the statement returned more rows than were read, see `Backend::run_script`
+    pub fn has_more_rows(&self) -> bool {
+        self.more_rows
     }
 
     /// Gets the number of rows in the result
@@ -114,28 +209,26 @@ impl QueryResult {
 
     /// Gets the column names of the result
     pub fn columns(&self) -> Vec<String> {
-        if let Some(row) = self.rows.first() {
-            row.columns()
-                .iter()
-                .map(|col| col.name().to_string())
-                .collect()
-        } else {
-            Vec::new()
-        }
+        self.columns.clone()
+    }
+
+    /// Gets the type names of the columns as Postgres names them, e.g. `INT4` or
+    /// `TEXT`, whichever driver read them
+    pub fn column_types(&self) -> Vec<String> {
+        self.column_types.clone()
+    }
+
+    /// Gets the rows as vectors of decoded cell values
+    pub fn rows_as_values(&self) -> Vec<Vec<CellValue>> {
+        self.rows.clone()
     }
 
     /// Gets the rows as vectors of strings
+    #[cfg(test)]
     pub fn rows_as_strings(&self) -> Vec<Vec<String>> {
         self.rows
             .iter()
-            .map(|row| {
-                (0..row.columns().len())
-                    .map(|i| {
-                        row.try_get::<String, _>(i)
-                            .unwrap_or_else(|_| "NULL".to_string())
-                    })
-                    .collect()
-            })
+            .map(|row| row.iter().map(CellValue::to_string).collect())
             .collect()
     }
 }
@@ -150,4 +243,60 @@ mod tests {
         let result = DatabaseManager::new(config).await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_rows_as_strings_decodes_types() {
+        let db = DatabaseManager::new(DatabaseConfig::new()).await.unwrap();
+        let result = db
+            .execute_query(
+                "SELECT 42::int4, 1.50::numeric, true, NULL::text, '2024-01-02'::date, \
+                 '1 day 02:00:00'::interval, '00000000-0000-0000-0000-000000000001'::uuid, \
+                 '{\"a\": 1}'::jsonb, '\\xdead'::bytea, '10.0.0.1'::inet, \
+                 ARRAY[1, NULL, 3]::int4[], ARRAY['a b', 'c']::text[], 0.1::real, \
+                 ARRAY[0.1, NULL]::real[], 0.1::float8",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            result.rows_as_strings()[0],
+            vec![
+                "42",
+                "1.50",
+                "true",
+                "NULL",
+                "2024-01-02",
+                "1 day 02:00:00",
+                "00000000-0000-0000-0000-000000000001",
+                "{\"a\":1}",
+                "\\xdead",
+                "10.0.0.1",
+                "{1,NULL,3}",
+                "{\"a b\",c}",
+                "0.1",
+                "{0.1,NULL}",
+                "0.1",
+            ]
+        );
+        assert_eq!(result.rows_as_values()[0][3], CellValue::Null);
+    }
+
+    #[tokio::test]
+    async fn test_timestamptz_in_session_time_zone() {
+        let query = "SELECT '2024-01-15 12:00:00+00'::timestamptz, '2024-07-15 12:00:00+00'::timestamptz, \
+                     ARRAY['2024-01-15 12:00:00+00']::timestamptz[]";
+        let mut config = DatabaseConfig::new();
+        config.params.insert("TimeZone".to_string(), "America/New_York".to_string());
+        let db = DatabaseManager::new(config).await.unwrap();
+        assert_eq!(
+            db.execute_query(query).await.unwrap().rows_as_strings()[0],
+            vec!["2024-01-15 07:00:00-05", "2024-07-15 08:00:00-04", "{\"2024-01-15 07:00:00-05\"}"]
+        );
+
+        // A POSIX-style zone is not in the time zone database
+        let mut config = DatabaseConfig::new();
+        config.params.insert("TimeZone".to_string(), "<+0530>-05:30".to_string());
+        let db = DatabaseManager::new(config).await.unwrap();
+        assert_eq!(db.execute_query(query).await.unwrap().rows_as_strings()[0][0], "2024-01-15 17:30:00+05:30");
+    }
 }
\ No newline at end of file
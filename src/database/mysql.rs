@@ -0,0 +1,588 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use sqlx::mysql::{MySql, MySqlConnectOptions, MySqlPoolOptions, MySqlQueryResult, MySqlRow, MySqlTypeInfo};
+use sqlx::{Executor, Pool, Row, TypeInfo, ValueRef};
+
+use super::backend::{apply_text_changes, open_text_query, run_text_script, stream_rows, Backend, Decoder, Driver};
+use super::decode::format_bytea;
+use super::schema::{ColumnInfo, ConstraintInfo};
+use super::structure::{ColumnDetails, ConstraintDetails, IndexDetails, TriggerDetails};
+use super::{
+    quote_identifier, split_qualified_name, Catalog, CatalogTable, CellValue, DatabaseConfig, ObjectKind, QueryResult,
+    RowCursor, StatementResult, TableStructure,
+};
+
+/// Schemas that belong to the server rather than to users
+const SYSTEM_SCHEMAS: &str = "('information_schema', 'mysql', 'performance_schema', 'sys')";
+
+/// Backend for MySQL and MariaDB. Databases are listed as schemas. The session's
+/// `sql_mode` is left as the server sets it, so generated queries quote names
+/// with backticks and escape backslashes in string literals.
+#[derive(Debug, Clone)]
+pub struct MySqlBackend {
+    pool: Pool<MySql>,
+}
+
+impl MySqlBackend {
+    /// Creates a new database connection pool
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self> {
+        let mut options = MySqlConnectOptions::new()
+            .port(config.server_port())
+            .username(&config.username)
+            .password(&config.password);
+        if config.uses_socket() {
+            options = options.socket(&config.host);
+        } else if !config.host.is_empty() {
+            options = options.host(&config.host);
+        }
+        if !config.database.is_empty() {
+            options = options.database(&config.database);
+        }
+
+        let mut timeout = Duration::from_secs(3);
+        for (key, value) in &config.params {
+            match key.as_str() {
+                "ssl-mode" | "sslmode" => options = options.ssl_mode(value.parse()?),
+                "ssl-ca" | "sslrootcert" => options = options.ssl_ca(value),
+                "ssl-cert" | "sslcert" => options = options.ssl_client_cert(value),
+                "ssl-key" | "sslkey" => options = options.ssl_client_key(value),
+                "charset" => options = options.charset(value),
+                "collation" => options = options.collation(value),
+                "connect_timeout" => {
+                    let seconds = value.parse().with_context(|| format!("Invalid connect_timeout: {}", value))?;
+                    timeout = Duration::from_secs(seconds);
+                }
+                _ => bail!("Unsupported MySQL connection parameter: {}", key),
+            }
+        }
+
+        let read_only = config.read_only;
+        let pool = MySqlPoolOptions::new()
+            .max_connections(5)
+            .acquire_timeout(timeout)
+            .after_connect(move |conn, _| {
+                Box::pin(async move {
+                    if read_only {
+                        conn.execute("SET SESSION TRANSACTION READ ONLY").await?;
+                    }
+                    Ok(())
+                })
+            })
+            .connect_with(options)
+            .await?;
+
+        Ok(Self { pool })
+    }
+
+    /// Gets the id of a connection, which `KILL QUERY` takes
+    async fn connection_id(conn: &mut sqlx::MySqlConnection) -> Result<i64> {
+        let id: u64 = sqlx::query_scalar("SELECT CONNECTION_ID()").fetch_one(conn).await?;
+        i64::try_from(id).with_context(|| format!("Connection id {} is out of range", id))
+    }
+}
+
+impl Decoder for MySql {
+    fn decode_cell(row: &MySqlRow, index: usize) -> CellValue {
+        match row.try_get_raw(index) {
+            Ok(raw) if raw.is_null() => return CellValue::Null,
+            Ok(_) => {}
+            Err(_) => return CellValue::Undecodable("?".to_string()),
+        }
+
+        // Statements run as text, so every value arrives as its text form
+        let type_name = Self::column_type(row, index);
+        let bytes = match row.try_get_unchecked::<&[u8], _>(index) {
+            Ok(bytes) => bytes,
+            Err(_) => return CellValue::Undecodable(type_name),
+        };
+        match (type_name.as_str(), std::str::from_utf8(bytes)) {
+            ("BOOL", Ok("1")) => CellValue::Value("true".to_string()),
+            ("BOOL", Ok("0")) => CellValue::Value("false".to_string()),
+            ("BYTEA", _) | (_, Err(_)) => CellValue::Value(format_bytea(bytes)),
+            (_, Ok(text)) => CellValue::Value(text.to_string()),
+        }
+    }
+
+    fn type_name(type_info: &MySqlTypeInfo) -> String {
+        postgres_type_name(type_info.name()).to_string()
+    }
+
+    fn rows_affected(done: &MySqlQueryResult) -> u64 {
+        done.rows_affected()
+    }
+}
+
+#[async_trait]
+impl Backend for MySqlBackend {
+    fn driver(&self) -> Driver {
+        Driver::Mysql
+    }
+
+    #[cfg(test)]
+    async fn execute_query(&self, query: &str) -> Result<QueryResult> {
+        let mut conn = self.pool.acquire().await?;
+        super::backend::execute_text::<MySql>(&mut conn, query).await
+    }
+
+    async fn open_query(
+        &self,
+        query: &str,
+        page_size: usize,
+        backend_pid: &AtomicI64,
+    ) -> Result<(QueryResult, Option<RowCursor>)> {
+        let mut conn = self.pool.acquire().await?;
+        backend_pid.store(Self::connection_id(&mut conn).await?, Ordering::SeqCst);
+        open_text_query(conn, query, page_size).await
+    }
+
+    async fn open_read_only_cursor(&self, query: &str, page_size: usize) -> Result<RowCursor> {
+        let mut conn = self.pool.acquire().await?;
+        conn.execute("START TRANSACTION READ ONLY").await?;
+        Ok(stream_rows(conn, query.to_string(), page_size, Some("ROLLBACK")))
+    }
+
+    async fn run_script(
+        &self,
+        statements: Vec<String>,
+        page_size: usize,
+        continue_on_error: bool,
+        backend_pid: &AtomicI64,
+    ) -> Result<(Vec<StatementResult>, bool)> {
+        let mut conn = self.pool.acquire().await?;
+        backend_pid.store(Self::connection_id(&mut conn).await?, Ordering::SeqCst);
+        run_text_script::<MySql>(&mut conn, statements, page_size, continue_on_error).await
+    }
+
+    async fn apply_row_changes(&self, statements: &[String]) -> Result<()> {
+        apply_text_changes(&self.pool, statements).await
+    }
+
+    async fn cancel_backend(&self, pid: i64) -> Result<bool> {
+        // KILL cannot be prepared, the id is a number so it is safe to format in
+        self.pool.execute(format!("KILL QUERY {}", pid).as_str()).await?;
+        Ok(true)
+    }
+
+    async fn list_databases(&self) -> Result<Vec<String>> {
+        let names = sqlx::query_scalar(
+            "SELECT CAST(schema_name AS CHAR) FROM information_schema.schemata ORDER BY schema_name;",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(names)
+    }
+
+    async fn list_schemas(&self) -> Result<Vec<String>> {
+        self.list_databases().await
+    }
+
+    async fn list_objects(&self, schema: &str, kind: ObjectKind) -> Result<Vec<String>> {
+        let query = match kind {
+            ObjectKind::Table => {
+                "SELECT CAST(table_name AS CHAR) FROM information_schema.tables \
+                 WHERE table_schema = ? AND table_type = 'BASE TABLE' ORDER BY table_name;"
+            }
+            ObjectKind::View => {
+                "SELECT CAST(table_name AS CHAR) FROM information_schema.tables \
+                 WHERE table_schema = ? AND table_type = 'VIEW' ORDER BY table_name;"
+            }
+            ObjectKind::Function => {
+                "SELECT CAST(routine_name AS CHAR) FROM information_schema.routines \
+                 WHERE routine_schema = ? ORDER BY routine_name;"
+            }
+            ObjectKind::MaterializedView | ObjectKind::Sequence => return Ok(Vec::new()),
+        };
+        let names = sqlx::query_scalar(query).bind(schema).fetch_all(&self.pool).await?;
+        Ok(names)
+    }
+
+    async fn list_columns(&self, schema: &str, table: &str) -> Result<Vec<ColumnInfo>> {
+        let rows = sqlx::query(
+            "SELECT CAST(column_name AS CHAR) AS name, CAST(column_type AS CHAR) AS data_type, \
+                    is_nullable = 'YES' AS nullable \
+             FROM information_schema.columns \
+             WHERE table_schema = ? AND table_name = ? ORDER BY ordinal_position;",
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ColumnInfo {
+                name: row.get("name"),
+                data_type: row.get("data_type"),
+                nullable: row.get::<i64, _>("nullable") != 0,
+            })
+            .collect())
+    }
+
+    async fn list_indexes(&self, schema: &str, table: &str) -> Result<Vec<String>> {
+        let names = sqlx::query_scalar(
+            "SELECT DISTINCT CAST(index_name AS CHAR) FROM information_schema.statistics \
+             WHERE table_schema = ? AND table_name = ? ORDER BY 1;",
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(names)
+    }
+
+    async fn list_constraints(&self, schema: &str, table: &str) -> Result<Vec<ConstraintInfo>> {
+        let rows = sqlx::query(
+            "SELECT CAST(constraint_name AS CHAR) AS name, CAST(constraint_type AS CHAR) AS kind \
+             FROM information_schema.table_constraints \
+             WHERE table_schema = ? AND table_name = ? ORDER BY constraint_name;",
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ConstraintInfo {
+                name: row.get("name"),
+                kind: row.get("kind"),
+            })
+            .collect())
+    }
+
+    async fn primary_key(&self, table: &str) -> Result<Vec<String>> {
+        let (schema, name) = split_qualified_name(table);
+        let columns = sqlx::query_scalar(
+            "SELECT CAST(column_name AS CHAR) FROM information_schema.key_column_usage \
+             WHERE table_schema = COALESCE(?, DATABASE()) AND table_name = ? AND constraint_name = 'PRIMARY' \
+             ORDER BY ordinal_position;",
+        )
+        .bind(schema)
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(columns)
+    }
+
+    async fn estimate_rows(&self, table: &str) -> Result<Option<i64>> {
+        let (schema, name) = split_qualified_name(table);
+        let estimate: Option<Option<u64>> = sqlx::query_scalar(
+            "SELECT table_rows FROM information_schema.tables \
+             WHERE table_schema = COALESCE(?, DATABASE()) AND table_name = ?;",
+        )
+        .bind(schema)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(estimate.flatten().map(|rows| rows as i64))
+    }
+
+    async fn table_structure(&self, table: &str) -> Result<TableStructure> {
+        let (schema, name) = split_qualified_name(table);
+        let row = sqlx::query(
+            "SELECT CAST(table_schema AS CHAR) AS table_schema, CAST(table_type AS CHAR) AS table_type, \
+                    CAST(table_comment AS CHAR) AS table_comment \
+             FROM information_schema.tables \
+             WHERE table_schema = COALESCE(?, DATABASE()) AND table_name = ?;",
+        )
+        .bind(&schema)
+        .bind(&name)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| anyhow!("Table {} not found", table))?;
+        let schema: String = row.get("table_schema");
+        let is_view = row.get::<String, _>("table_type") == "VIEW";
+        let comment: String = row.get("table_comment");
+
+        let mut structure = TableStructure {
+            driver: Driver::Mysql,
+            schema: schema.clone(),
+            name: name.clone(),
+            kind: if is_view { "view" } else { "table" }.to_string(),
+            comment: Some(comment).filter(|comment| !comment.is_empty() && !is_view),
+            ..TableStructure::default()
+        };
+
+        let columns = sqlx::query(
+            "SELECT CAST(column_name AS CHAR) AS name, CAST(column_type AS CHAR) AS data_type, \
+                    is_nullable = 'YES' AS nullable, CAST(column_default AS CHAR) AS column_default, \
+                    CAST(generation_expression AS CHAR) AS generation_expression, CAST(extra AS CHAR) AS extra, \
+                    CAST(collation_name AS CHAR) AS collation_name, CAST(column_comment AS CHAR) AS column_comment \
+             FROM information_schema.columns \
+             WHERE table_schema = ? AND table_name = ? ORDER BY ordinal_position;",
+        )
+        .bind(&schema)
+        .bind(&name)
+        .fetch_all(&self.pool)
+        .await?;
+        structure.columns = columns
+            .iter()
+            .map(|row| {
+                let expression: Option<String> = row.get("generation_expression");
+                let extra: Option<String> = row.get("extra");
+                let generated = expression.filter(|expression| !expression.is_empty()).map(|expression| {
+                    let stored = extra.as_deref().is_some_and(|extra| extra.contains("STORED"));
+                    format!("GENERATED ALWAYS AS ({}) {}", expression, if stored { "STORED" } else { "VIRTUAL" })
+                });
+                ColumnDetails {
+                    name: row.get("name"),
+                    data_type: row.get("data_type"),
+                    nullable: row.get::<i64, _>("nullable") != 0,
+                    default: if generated.is_some() { None } else { row.get("column_default") },
+                    generated,
+                    collation: row.get("collation_name"),
+                    comment: row.get::<Option<String>, _>("column_comment").filter(|comment| !comment.is_empty()),
+                }
+            })
+            .collect();
+
+        if is_view {
+            structure.view_definition = sqlx::query_scalar(
+                "SELECT CAST(view_definition AS CHAR) FROM information_schema.views \
+                 WHERE table_schema = ? AND table_name = ?;",
+            )
+            .bind(&schema)
+            .bind(&name)
+            .fetch_optional(&self.pool)
+            .await?;
+        } else {
+            let constraints = sqlx::query(
+                "SELECT CAST(c.constraint_name AS CHAR) AS name, CAST(c.constraint_type AS CHAR) AS kind, \
+                        CAST(GROUP_CONCAT(k.column_name ORDER BY k.ordinal_position SEPARATOR ',') AS CHAR) AS columns, \
+                        CAST(MAX(k.referenced_table_name) AS CHAR) AS parent, \
+                        CAST(GROUP_CONCAT(k.referenced_column_name ORDER BY k.ordinal_position SEPARATOR ',') AS CHAR) AS parent_columns \
+                 FROM information_schema.table_constraints c \
+                 JOIN information_schema.key_column_usage k \
+                   ON k.constraint_schema = c.constraint_schema AND k.constraint_name = c.constraint_name \
+                  AND k.table_name = c.table_name \
+                 WHERE c.table_schema = ? AND c.table_name = ? \
+                 GROUP BY c.constraint_name, c.constraint_type \
+                 ORDER BY c.constraint_type = 'PRIMARY KEY' DESC, c.constraint_name;",
+            )
+            .bind(&schema)
+            .bind(&name)
+            .fetch_all(&self.pool)
+            .await?;
+            structure.constraints = constraints
+                .iter()
+                .map(|row| {
+                    let kind: String = row.get("kind");
+                    let columns = column_list(row.get("columns"));
+                    let definition = match row.get::<Option<String>, _>("parent") {
+                        Some(parent) if kind == "FOREIGN KEY" => format!(
+                            "FOREIGN KEY ({}) REFERENCES {} ({})",
+                            columns,
+                            quote_identifier(&parent, Driver::Mysql),
+                            column_list(row.get::<Option<String>, _>("parent_columns").unwrap_or_default())
+                        ),
+                        _ => format!("{} ({})", kind, columns),
+                    };
+                    ConstraintDetails {
+                        name: row.get("name"),
+                        definition,
+                        inherited: false,
+                    }
+                })
+                .collect();
+
+            let indexes = sqlx::query(
+                "SELECT CAST(index_name AS CHAR) AS name, MIN(non_unique) AS non_unique, \
+                        CAST(MAX(index_type) AS CHAR) AS method, \
+                        CAST(GROUP_CONCAT(COALESCE(column_name, '<expression>') ORDER BY seq_in_index SEPARATOR ',') AS CHAR) AS columns \
+                 FROM information_schema.statistics \
+                 WHERE table_schema = ? AND table_name = ? \
+                 GROUP BY index_name ORDER BY index_name;",
+            )
+            .bind(&schema)
+            .bind(&name)
+            .fetch_all(&self.pool)
+            .await?;
+            let constraint_names: Vec<&str> = structure.constraints.iter().map(|c| c.name.as_str()).collect();
+            structure.indexes = indexes
+                .iter()
+                .map(|row| {
+                    let index_name: String = row.get("name");
+                    let unique = if row.get::<i64, _>("non_unique") == 0 { "UNIQUE " } else { "" };
+                    IndexDetails {
+                        definition: format!(
+                            "CREATE {}INDEX {} ON {} USING {} ({})",
+                            unique,
+                            quote_identifier(&index_name, Driver::Mysql),
+                            quote_identifier(&name, Driver::Mysql),
+                            row.get::<String, _>("method"),
+                            column_list(row.get("columns"))
+                        ),
+                        constraint: constraint_names.contains(&index_name.as_str()),
+                        name: index_name,
+                        inherited: false,
+                        valid: true,
+                    }
+                })
+                .collect();
+
+            let triggers = sqlx::query(
+                "SELECT CAST(trigger_name AS CHAR) AS name, CAST(action_timing AS CHAR) AS timing, \
+                        CAST(event_manipulation AS CHAR) AS event, CAST(action_statement AS CHAR) AS body \
+                 FROM information_schema.triggers \
+                 WHERE event_object_schema = ? AND event_object_table = ? ORDER BY trigger_name;",
+            )
+            .bind(&schema)
+            .bind(&name)
+            .fetch_all(&self.pool)
+            .await?;
+            structure.triggers = triggers
+                .iter()
+                .map(|row| {
+                    let trigger_name: String = row.get("name");
+                    TriggerDetails {
+                        definition: format!(
+                            "CREATE TRIGGER {} {} {} ON {} FOR EACH ROW {}",
+                            quote_identifier(&trigger_name, Driver::Mysql),
+                            row.get::<String, _>("timing"),
+                            row.get::<String, _>("event"),
+                            quote_identifier(&name, Driver::Mysql),
+                            row.get::<String, _>("body")
+                        ),
+                        name: trigger_name,
+                        enabled: true,
+                        inherited: false,
+                    }
+                })
+                .collect();
+        }
+
+        // The server's own DDL keeps details the catalog does not show, e.g. the engine
+        let object = if is_view { "VIEW" } else { "TABLE" };
+        let show = format!("SHOW CREATE {} {}", object, structure.qualified_name());
+        let mut conn = self.pool.acquire().await?;
+        if let Some(row) = conn.fetch_optional(show.as_str()).await? {
+            let ddl: String = String::from_utf8_lossy(row.try_get::<&[u8], _>(1)?).into_owned();
+            let triggers = structure.triggers.iter().map(|trigger| format!("{};", trigger.definition));
+            structure.reported_ddl = Some(
+                std::iter::once(format!("{};", ddl))
+                    .chain(triggers)
+                    .collect::<Vec<_>>()
+                    .join("\n\n"),
+            );
+        }
+
+        Ok(structure)
+    }
+
+    async fn load_catalog(&self) -> Result<Catalog> {
+        let schemas = self.list_schemas().await?;
+
+        let rows = sqlx::query(&format!(
+            "SELECT CAST(t.table_schema AS CHAR) AS table_schema, CAST(t.table_name AS CHAR) AS table_name, \
+                    CAST(c.column_name AS CHAR) AS column_name \
+             FROM information_schema.tables t \
+             LEFT JOIN information_schema.columns c \
+               ON c.table_schema = t.table_schema AND c.table_name = t.table_name \
+             WHERE t.table_schema NOT IN {} \
+             ORDER BY t.table_schema, t.table_name, c.ordinal_position;",
+            SYSTEM_SCHEMAS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut tables: Vec<CatalogTable> = Vec::new();
+        for row in &rows {
+            let schema: String = row.get("table_schema");
+            let name: String = row.get("table_name");
+            if tables.last().is_none_or(|table| table.schema != schema || table.name != name) {
+                tables.push(CatalogTable {
+                    schema,
+                    name,
+                    columns: Vec::new(),
+                });
+            }
+            let table = tables.last_mut().unwrap();
+            table.columns.extend(row.get::<Option<String>, _>("column_name"));
+        }
+
+        let functions = sqlx::query_scalar(&format!(
+            "SELECT DISTINCT CAST(routine_name AS CHAR) FROM information_schema.routines \
+             WHERE routine_schema NOT IN {} ORDER BY 1;",
+            SYSTEM_SCHEMAS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let default_schema: Option<String> = sqlx::query_scalar("SELECT CAST(DATABASE() AS CHAR)")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(Catalog {
+            schemas,
+            tables,
+            functions,
+            default_schema: default_schema.unwrap_or_default(),
+        })
+    }
+}
+
+/// Maps the name of a MySQL type to the name Postgres gives it
+fn postgres_type_name(name: &str) -> &str {
+    match name {
+        "BOOLEAN" => "BOOL",
+        "TINYINT" | "TINYINT UNSIGNED" | "SMALLINT" | "SMALLINT UNSIGNED" | "YEAR" => "INT2",
+        "MEDIUMINT" | "MEDIUMINT UNSIGNED" | "INT" => "INT4",
+        "INT UNSIGNED" | "BIGINT" => "INT8",
+        "BIGINT UNSIGNED" | "DECIMAL" => "NUMERIC",
+        "FLOAT" => "FLOAT4",
+        "DOUBLE" => "FLOAT8",
+        "DATETIME" | "TIMESTAMP" => "TIMESTAMP",
+        "BLOB" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BINARY" | "VARBINARY" => "BYTEA",
+        "VARCHAR" | "CHAR" | "TEXT" | "TINYTEXT" | "MEDIUMTEXT" | "LONGTEXT" | "ENUM" | "SET" => "TEXT",
+        "NULL" => "",
+        name => name,
+    }
+}
+
+/// Quotes and joins the comma-separated column names `GROUP_CONCAT` gives
+fn column_list(columns: String) -> String {
+    columns
+        .split(',')
+        .filter(|column| !column.is_empty())
+        .map(|column| quote_identifier(column, Driver::Mysql))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_postgres_type_name() {
+        assert_eq!(postgres_type_name("BOOLEAN"), "BOOL");
+        assert_eq!(postgres_type_name("INT"), "INT4");
+        assert_eq!(postgres_type_name("INT UNSIGNED"), "INT8");
+        assert_eq!(postgres_type_name("BIGINT UNSIGNED"), "NUMERIC");
+        assert_eq!(postgres_type_name("VARBINARY"), "BYTEA");
+        assert_eq!(postgres_type_name("ENUM"), "TEXT");
+        assert_eq!(postgres_type_name("JSON"), "JSON");
+        assert_eq!(column_list("id,Name,".to_string()), "id, `Name`");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MySQL server on localhost"]
+    async fn test_mysql_sql_mode() {
+        let config = DatabaseConfig {
+            driver: Driver::Mysql,
+            host: "localhost".to_string(),
+            username: "root".to_string(),
+            password: "root".to_string(),
+            database: "mysql".to_string(),
+            ..DatabaseConfig::default()
+        };
+        let backend = MySqlBackend::connect(&config).await.unwrap();
+
+        // Without ANSI_QUOTES double quotes delimit strings, and backslashes escape in them
+        let result = backend.execute_query("SELECT \"a\", 'C:\\\\temp', 1 AS `order`").await.unwrap();
+        assert_eq!(result.rows_as_strings(), vec![vec!["a", "C:\\temp", "1"]]);
+    }
+}
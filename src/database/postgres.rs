@@ -0,0 +1,282 @@
+use std::sync::atomic::AtomicI64;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgQueryResult, PgRow, PgTypeInfo};
+use sqlx::{Either, Executor, PgConnection, Pool, Postgres, Row, TypeInfo};
+
+use super::backend::{Backend, Decoder, Driver};
+use super::connection::{escape_server_setting, parse_server_options};
+use super::schema::{ColumnInfo, ConstraintInfo};
+use super::statement::{classify, StatementKind};
+use super::decode::SessionTimeZone;
+use super::{decode, Catalog, CellValue, DatabaseConfig, ObjectKind, QueryResult, RowCursor, Session, StatementResult, TableStructure};
+
+/// Backend for PostgreSQL. Results are paged through server-side cursors and
+/// definitions are read from `pg_catalog`.
+#[derive(Debug, Clone)]
+pub struct PostgresBackend {
+    pub(super) pool: Pool<Postgres>,
+}
+
+impl PostgresBackend {
+    /// Creates a new database connection pool from resolved settings. The TLS
+    /// settings and `options` not given in the profile come from the `PGSSLMODE`,
+    /// `PGAPPNAME`, `PGOPTIONS` and other environment variables libpq reads.
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self> {
+        let mut options = PgConnectOptions::new_without_pgpass().port(config.server_port());
+        if !config.host.is_empty() {
+            // A host starting with `/` is taken as a socket directory
+            options = options.host(&config.host);
+        }
+        if !config.username.is_empty() {
+            options = options.username(&config.username);
+        }
+        if !config.password.is_empty() {
+            options = options.password(&config.password);
+        }
+        if !config.database.is_empty() {
+            options = options.database(&config.database);
+        }
+
+        let mut timeout = Duration::from_secs(3);
+        let mut settings = Vec::new();
+        for (key, value) in &config.params {
+            match key.as_str() {
+                "sslmode" => options = options.ssl_mode(value.parse()?),
+                "sslrootcert" => options = options.ssl_root_cert(value),
+                "sslcert" => options = options.ssl_client_cert(value),
+                "sslkey" => options = options.ssl_client_key(value),
+                "application_name" => options = options.application_name(value),
+                "connect_timeout" => {
+                    let seconds = value.parse().with_context(|| format!("Invalid connect_timeout: {}", value))?;
+                    timeout = Duration::from_secs(seconds);
+                }
+                "options" => settings.extend(parse_server_options(value)?),
+                // Anything else is a server setting, e.g. `search_path` or `statement_timeout`
+                _ => settings.push((key.clone(), value.clone())),
+            }
+        }
+        if config.read_only {
+            settings.push(("default_transaction_read_only".to_string(), "on".to_string()));
+        }
+        // sqlx asks for `TimeZone=UTC` when connecting, which wins over `options`, so the
+        // zone is set once connected instead
+        let time_zone = settings
+            .iter()
+            .rev()
+            .find(|(name, _)| name.eq_ignore_ascii_case("TimeZone"))
+            .map(|(_, value)| value.clone());
+        settings.retain(|(name, _)| !name.eq_ignore_ascii_case("TimeZone"));
+        for (name, value) in &settings {
+            // sqlx writes the settings into the `options` parameter without escaping them
+            options = options.options([(name, escape_server_setting(name, value)?)]);
+        }
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .acquire_timeout(timeout)
+            .after_connect(move |conn, _| {
+                let time_zone = time_zone.clone();
+                Box::pin(async move {
+                    if let Some(time_zone) = time_zone {
+                        sqlx::query("SELECT set_config('TimeZone', $1, false)")
+                            .bind(time_zone)
+                            .execute(conn)
+                            .await?;
+                    }
+                    Ok(())
+                })
+            })
+            .connect_with(options)
+            .await?;
+
+        Ok(Self { pool })
+    }
+
+    /// Gets a list of all databases
+    pub async fn list_databases(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query("SELECT datname FROM pg_database WHERE datistemplate = false ORDER BY datname;")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| row.get("datname"))
+            .collect())
+    }
+
+    /// Gets a list of all schemas in the current database
+    pub async fn list_schemas(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT schema_name FROM information_schema.schemata \
+             WHERE schema_name NOT LIKE 'pg_toast%' AND schema_name NOT LIKE 'pg_temp_%' \
+             ORDER BY schema_name;",
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| row.get("schema_name"))
+            .collect())
+    }
+
+    /// Asks the server to cancel the statement running on another connection,
+    /// returning whether the cancel request was delivered
+    pub async fn cancel_backend(&self, pid: i64) -> Result<bool> {
+        let cancelled: bool = sqlx::query_scalar("SELECT pg_cancel_backend($1::integer)")
+            .bind(pid)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(cancelled)
+    }
+}
+
+/// Executes a statement on a connection, collecting both its rows (e.g. from
+/// RETURNING) and how many rows it affected
+pub(super) async fn execute_on(conn: &mut PgConnection, query: &str) -> Result<QueryResult> {
+    let kind = classify(query);
+    let mut rows = Vec::new();
+    let mut rows_affected = 0;
+
+    let mut results = conn.fetch_many(sqlx::query(query));
+    while let Some(item) = results.try_next().await? {
+        match item {
+            Either::Left(done) => rows_affected += done.rows_affected(),
+            Either::Right(row) => rows.push(row),
+        }
+    }
+    drop(results);
+
+    let mut result = decode_rows(conn, &rows).await?;
+    match kind {
+        StatementKind::Dml => result.rows_affected = Some(rows_affected),
+        StatementKind::Other(tag) => result.command_tag = Some(tag),
+        StatementKind::Query => {}
+    }
+    Ok(result)
+}
+
+/// Decodes rows read on a connection, showing `timestamptz` values in the session's
+/// `TimeZone` as psql does. The setting is only read when there are such values.
+pub(super) async fn decode_rows(conn: &mut PgConnection, rows: &[PgRow]) -> Result<QueryResult> {
+    let time_zone = match rows.first() {
+        Some(row) if decode::has_timestamptz(row) => {
+            let (name, utc_offset): (String, i32) =
+                sqlx::query_as("SELECT current_setting('TimeZone'), EXTRACT(timezone FROM now())::int4")
+                    .fetch_one(conn)
+                    .await?;
+            SessionTimeZone::new(&name, utc_offset)
+        }
+        _ => SessionTimeZone::default(),
+    };
+    Ok(QueryResult::decode_rows::<Postgres>(rows, |row, index| {
+        decode::decode_cell(row, index, &time_zone)
+    }))
+}
+
+impl Decoder for Postgres {
+    /// Shows `timestamptz` values in UTC, rows read on a connection go through `decode_rows`
+    fn decode_cell(row: &PgRow, index: usize) -> CellValue {
+        decode::decode_cell(row, index, &SessionTimeZone::default())
+    }
+
+    fn type_name(type_info: &PgTypeInfo) -> String {
+        type_info.name().to_string()
+    }
+
+    fn rows_affected(done: &PgQueryResult) -> u64 {
+        done.rows_affected()
+    }
+}
+
+#[async_trait]
+impl Backend for PostgresBackend {
+    fn driver(&self) -> Driver {
+        Driver::Postgres
+    }
+
+    #[cfg(test)]
+    async fn execute_query(&self, query: &str) -> Result<QueryResult> {
+        let mut conn = self.pool.acquire().await?;
+        execute_on(&mut conn, query).await
+    }
+
+    async fn open_query(
+        &self,
+        query: &str,
+        page_size: usize,
+        backend_pid: &AtomicI64,
+    ) -> Result<(QueryResult, Option<RowCursor>)> {
+        PostgresBackend::open_query(self, query, page_size, backend_pid).await
+    }
+
+    async fn open_read_only_cursor(&self, query: &str, page_size: usize) -> Result<RowCursor> {
+        PostgresBackend::open_read_only_cursor(self, query, page_size).await
+    }
+
+    async fn run_script(
+        &self,
+        statements: Vec<String>,
+        page_size: usize,
+        continue_on_error: bool,
+        backend_pid: &AtomicI64,
+    ) -> Result<(Vec<StatementResult>, bool)> {
+        PostgresBackend::run_script(self, statements, page_size, continue_on_error, backend_pid).await
+    }
+
+    async fn apply_row_changes(&self, statements: &[String]) -> Result<()> {
+        PostgresBackend::apply_row_changes(self, statements).await
+    }
+
+    async fn cancel_backend(&self, pid: i64) -> Result<bool> {
+        PostgresBackend::cancel_backend(self, pid).await
+    }
+
+    async fn open_session(&self) -> Result<Session> {
+        PostgresBackend::open_session(self).await
+    }
+
+    async fn list_databases(&self) -> Result<Vec<String>> {
+        PostgresBackend::list_databases(self).await
+    }
+
+    async fn list_schemas(&self) -> Result<Vec<String>> {
+        PostgresBackend::list_schemas(self).await
+    }
+
+    async fn list_objects(&self, schema: &str, kind: ObjectKind) -> Result<Vec<String>> {
+        PostgresBackend::list_objects(self, schema, kind).await
+    }
+
+    async fn list_columns(&self, schema: &str, table: &str) -> Result<Vec<ColumnInfo>> {
+        PostgresBackend::list_columns(self, schema, table).await
+    }
+
+    async fn list_indexes(&self, schema: &str, table: &str) -> Result<Vec<String>> {
+        PostgresBackend::list_indexes(self, schema, table).await
+    }
+
+    async fn list_constraints(&self, schema: &str, table: &str) -> Result<Vec<ConstraintInfo>> {
+        PostgresBackend::list_constraints(self, schema, table).await
+    }
+
+    async fn primary_key(&self, table: &str) -> Result<Vec<String>> {
+        PostgresBackend::primary_key(self, table).await
+    }
+
+    async fn estimate_rows(&self, table: &str) -> Result<Option<i64>> {
+        PostgresBackend::estimate_rows(self, table).await
+    }
+
+    async fn table_structure(&self, table: &str) -> Result<TableStructure> {
+        PostgresBackend::table_structure(self, table).await
+    }
+
+    async fn load_catalog(&self) -> Result<Catalog> {
+        PostgresBackend::load_catalog(self).await
+    }
+}
@@ -0,0 +1,108 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use tokio::task::JoinHandle;
+
+use super::{split_statements, DatabaseManager, QueryResult, RowCursor, Session, StatementResult};
+
+/// Output of a completed background execution
+pub enum QueryOutput {
+    /// A single statement with its first page of rows, and the cursor for the rest
+    Single(QueryResult, Option<RowCursor>),
+    /// A script with the results of the statements that ran, and whether a
+    /// transaction it left open was rolled back
+    Script(Vec<StatementResult>, bool),
+}
+
+/// A query executing on a background task
+pub struct RunningQuery {
+    /// The query text
+    query: String,
+    /// When the query was started
+    started: Instant,
+    /// Backend process ID of the connection running the query, 0 until known
+    backend_pid: Arc<AtomicI64>,
+    handle: JoinHandle<Result<QueryOutput>>,
+}
+
+impl RunningQuery {
+    /// Gets the query text
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Gets the time since the query was started
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Gets the backend process ID running the query, once it has connected
+    pub fn backend_pid(&self) -> Option<i64> {
+        match self.backend_pid.load(Ordering::Relaxed) {
+            0 => None,
+            pid => Some(pid),
+        }
+    }
+
+    /// Whether the query has completed, successfully or not
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Waits for the query to complete and returns its output
+    pub async fn finish(self) -> Result<QueryOutput> {
+        self.handle.await?
+    }
+}
+
+impl std::fmt::Debug for RunningQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RunningQuery")
+            .field("query", &self.query)
+            .field("backend_pid", &self.backend_pid())
+            .finish()
+    }
+}
+
+impl DatabaseManager {
+    /// Starts executing a query on a background task. A single statement is paged,
+    /// see `open_query`; several statements are run as a script, see `run_script`.
+    /// With a session, the query runs in its transaction instead, see `Session::run`.
+    pub fn spawn_query(
+        &self,
+        query: String,
+        page_size: usize,
+        continue_on_error: bool,
+        session: Option<Session>,
+    ) -> RunningQuery {
+        let db = self.clone();
+        let backend_pid = Arc::new(AtomicI64::new(0));
+        let task_pid = backend_pid.clone();
+        let task_query = query.clone();
+        let driver = self.driver();
+
+        let handle = tokio::spawn(async move {
+            let statements = split_statements(&task_query, driver);
+            if let Some(session) = session {
+                let statements = statements.into_iter().map(String::from).collect();
+                session.run(statements, page_size, continue_on_error, &task_pid).await
+            } else if statements.len() > 1 {
+                let statements = statements.into_iter().map(String::from).collect();
+                let (results, rolled_back) = db.run_script(statements, page_size, continue_on_error, &task_pid).await?;
+                Ok(QueryOutput::Script(results, rolled_back))
+            } else {
+                let (result, cursor) = db.open_query(&task_query, page_size, &task_pid).await?;
+                Ok(QueryOutput::Single(result, cursor))
+            }
+        });
+
+        RunningQuery {
+            query,
+            started: Instant::now(),
+            backend_pid,
+            handle,
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use anyhow::Result;
+use sqlx::Row;
+
+use super::postgres::PostgresBackend;
+
+/// Kinds of schema objects shown in the schema browser
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectKind {
+    Table,
+    View,
+    MaterializedView,
+    Function,
+    Sequence,
+}
+
+impl ObjectKind {
+    /// All object kinds in display order
+    pub const ALL: [ObjectKind; 5] = [
+        ObjectKind::Table,
+        ObjectKind::View,
+        ObjectKind::MaterializedView,
+        ObjectKind::Function,
+        ObjectKind::Sequence,
+    ];
+
+    /// Gets the plural label used for the object group
+    pub fn label(&self) -> &'static str {
+        match self {
+            ObjectKind::Table => "Tables",
+            ObjectKind::View => "Views",
+            ObjectKind::MaterializedView => "Materialized Views",
+            ObjectKind::Function => "Functions",
+            ObjectKind::Sequence => "Sequences",
+        }
+    }
+
+    /// Whether objects of this kind have columns, indexes and constraints
+    pub fn has_columns(&self) -> bool {
+        matches!(
+            self,
+            ObjectKind::Table | ObjectKind::View | ObjectKind::MaterializedView
+        )
+    }
+}
+
+/// A column of a table, view or materialized view
+#[derive(Debug, Clone)]
+pub struct ColumnInfo {
+    /// Column name
+    pub name: String,
+    /// Formatted column type
+    pub data_type: String,
+    /// Whether the column accepts NULL
+    pub nullable: bool,
+}
+
+/// A constraint defined on a table
+#[derive(Debug, Clone)]
+pub struct ConstraintInfo {
+    /// Constraint name
+    pub name: String,
+    /// Constraint type (PRIMARY KEY, FOREIGN KEY, UNIQUE, CHECK, EXCLUDE)
+    pub kind: String,
+}
+
+impl PostgresBackend {
+    /// Gets the planner's estimate of the number of rows in a table, if it has one
+    pub async fn estimate_rows(&self, table: &str) -> Result<Option<i64>> {
+        let estimate: Option<i64> = sqlx::query_scalar(
+            "SELECT reltuples::bigint FROM pg_class WHERE oid = to_regclass($1) AND reltuples >= 0",
+        )
+            .bind(table)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(estimate)
+    }
+
+    /// Gets the primary key columns of a table in key order, empty if it has none
+    pub async fn primary_key(&self, table: &str) -> Result<Vec<String>> {
+        let columns = sqlx::query_scalar(
+            "SELECT a.attname::text \
+             FROM pg_index i \
+             JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey) \
+             WHERE i.indrelid = to_regclass($1) AND i.indisprimary \
+             ORDER BY array_position(i.indkey::int2[], a.attnum);",
+        )
+            .bind(table)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(columns)
+    }
+
+    /// Gets the objects of the given kind in a schema
+    pub async fn list_objects(&self, schema: &str, kind: ObjectKind) -> Result<Vec<String>> {
+        let relkinds = match kind {
+            ObjectKind::Table => vec!["r", "p", "f"],
+            ObjectKind::View => vec!["v"],
+            ObjectKind::MaterializedView => vec!["m"],
+            ObjectKind::Sequence => vec!["S"],
+            ObjectKind::Function => return self.list_functions(schema).await,
+        };
+
+        let rows = sqlx::query(
+            "SELECT c.relname AS name \
+             FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+             WHERE n.nspname = $1 AND c.relkind::text = ANY($2) \
+             ORDER BY c.relname;",
+        )
+        .bind(schema)
+        .bind(relkinds)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| row.get("name"))
+            .collect())
+    }
+
+    /// Gets the functions in a schema, including their argument signatures
+    async fn list_functions(&self, schema: &str) -> Result<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT p.proname || '(' || pg_get_function_identity_arguments(p.oid) || ')' AS name \
+             FROM pg_proc p JOIN pg_namespace n ON n.oid = p.pronamespace \
+             WHERE n.nspname = $1 \
+             ORDER BY 1;",
+        )
+        .bind(schema)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| row.get("name"))
+            .collect())
+    }
+
+    /// Gets the columns of a table, view or materialized view
+    pub async fn list_columns(&self, schema: &str, table: &str) -> Result<Vec<ColumnInfo>> {
+        let rows = sqlx::query(
+            "SELECT a.attname AS name, format_type(a.atttypid, a.atttypmod) AS data_type, \
+             NOT a.attnotnull AS nullable \
+             FROM pg_attribute a \
+             JOIN pg_class c ON c.oid = a.attrelid \
+             JOIN pg_namespace n ON n.oid = c.relnamespace \
+             WHERE n.nspname = $1 AND c.relname = $2 AND a.attnum > 0 AND NOT a.attisdropped \
+             ORDER BY a.attnum;",
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ColumnInfo {
+                name: row.get("name"),
+                data_type: row.get("data_type"),
+                nullable: row.get("nullable"),
+            })
+            .collect())
+    }
+
+    /// Gets the index names of a table or materialized view
+    pub async fn list_indexes(&self, schema: &str, table: &str) -> Result<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT indexname FROM pg_indexes \
+             WHERE schemaname = $1 AND tablename = $2 ORDER BY indexname;",
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| row.get("indexname"))
+            .collect())
+    }
+
+    /// Gets the constraints of a table
+    pub async fn list_constraints(&self, schema: &str, table: &str) -> Result<Vec<ConstraintInfo>> {
+        let rows = sqlx::query(
+            "SELECT con.conname AS name, \
+             CASE con.contype \
+                 WHEN 'p' THEN 'PRIMARY KEY' WHEN 'f' THEN 'FOREIGN KEY' \
+                 WHEN 'u' THEN 'UNIQUE' WHEN 'c' THEN 'CHECK' \
+                 WHEN 'x' THEN 'EXCLUDE' ELSE 'OTHER' END AS kind \
+             FROM pg_constraint con \
+             JOIN pg_class c ON c.oid = con.conrelid \
+             JOIN pg_namespace n ON n.oid = c.relnamespace \
+             WHERE n.nspname = $1 AND c.relname = $2 \
+             ORDER BY con.contype, con.conname;",
+        )
+        .bind(schema)
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ConstraintInfo {
+                name: row.get("name"),
+                kind: row.get("kind"),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseConfig;
+
+    #[tokio::test]
+    async fn test_catalog_queries() {
+        let db = PostgresBackend::connect(&DatabaseConfig::new()).await.unwrap();
+
+        let views = db.list_objects("pg_catalog", ObjectKind::View).await.unwrap();
+        assert!(views.contains(&"pg_tables".to_string()));
+
+        let columns = db.list_columns("pg_catalog", "pg_class").await.unwrap();
+        assert_eq!(columns[0].name, "oid");
+        assert!(!columns[0].nullable);
+
+        let indexes = db.list_indexes("pg_catalog", "pg_class").await.unwrap();
+        assert!(indexes.contains(&"pg_class_oid_index".to_string()));
+
+        sqlx::query("CREATE TABLE IF NOT EXISTS ferrum_test_keys (a int, b int, c text, PRIMARY KEY (b, a))")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(db.primary_key("ferrum_test_keys").await.unwrap(), vec!["b", "a"]);
+        sqlx::query("DROP TABLE ferrum_test_keys").execute(&db.pool).await.unwrap();
+    }
+}
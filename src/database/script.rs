@@ -0,0 +1,247 @@
+use std::sync::atomic::AtomicI64;
+use std::time::Instant;
+
+use anyhow::Result;
+use sqlx::PgConnection;
+
+use super::cursor::{declare_cursor, record_backend_pid, CURSOR_NAME};
+use super::postgres::{decode_rows, execute_on, PostgresBackend};
+use super::statement::{classify, StatementKind};
+use super::{QueryResult, Session};
+
+/// Outcome of one statement of a script
+pub struct StatementResult {
+    /// The statement text
+    pub statement: String,
+    /// Its result, or the error it failed with
+    pub result: Result<QueryResult>,
+    /// Execution time in milliseconds
+    pub execution_time: u128,
+}
+
+impl PostgresBackend {
+    /// Runs statements one after another on a single connection, so transaction
+    /// statements in the script apply to the statements after them. Stops at the
+    /// first error unless `continue_on_error` is set. Only the first `page_size` rows
+    /// of a row-returning statement are fetched. A transaction the script leaves
+    /// open is rolled back, which is reported by the returned flag.
+    pub async fn run_script(
+        &self,
+        statements: Vec<String>,
+        page_size: usize,
+        continue_on_error: bool,
+        backend_pid: &AtomicI64,
+    ) -> Result<(Vec<StatementResult>, bool)> {
+        let mut conn = self.pool.acquire().await?;
+        record_backend_pid(&mut conn, backend_pid).await?;
+
+        let results = run_statements(&mut conn, statements, page_size, continue_on_error, None).await;
+
+        // A savepoint can only be created inside a transaction block, so this tells
+        // whether the script left one open (or aborted) before the connection is reused
+        let in_transaction = match sqlx::query("SAVEPOINT ferrum_script_check").execute(&mut *conn).await {
+            Ok(_) => true,
+            Err(sqlx::Error::Database(e)) => e.code().as_deref() != Some("25P01"),
+            Err(e) => return Err(e.into()),
+        };
+        if in_transaction {
+            sqlx::query("ROLLBACK").execute(&mut *conn).await?;
+        }
+
+        Ok((results, in_transaction))
+    }
+
+    /// Runs statements that change one row each in a single transaction. A statement
+    /// that fails or does not affect exactly one row rolls back all of them.
+    pub async fn apply_row_changes(&self, statements: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for statement in statements {
+            let affected = sqlx::query(statement).execute(&mut *tx).await?.rows_affected();
+            if affected != 1 {
+                anyhow::bail!("{} affected {} rows instead of 1", statement, affected);
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Runs statements one after another on a connection, stopping at the first error
+/// unless `continue_on_error` is set. Row-returning statements are read through a
+/// cursor, fetching at most `page_size` rows. In a session, a transaction is opened
+/// before the statements that need one and the session's transaction state is kept
+/// up to date.
+pub(super) async fn run_statements(
+    conn: &mut PgConnection,
+    statements: Vec<String>,
+    page_size: usize,
+    continue_on_error: bool,
+    session: Option<&Session>,
+) -> Vec<StatementResult> {
+    let mut results = Vec::new();
+    for statement in statements {
+        let start_time = Instant::now();
+        let result = match session {
+            Some(session) => match session.begin_if_needed(conn, &statement).await {
+                Ok(()) => execute_paged(conn, &statement, page_size).await,
+                Err(e) => Err(e),
+            },
+            None => execute_paged(conn, &statement, page_size).await,
+        };
+        if let Some(session) = session {
+            session.record(&statement, result.is_ok());
+        }
+
+        let failed = result.is_err();
+        results.push(StatementResult {
+            statement,
+            result,
+            execution_time: start_time.elapsed().as_millis(),
+        });
+        if failed && !continue_on_error {
+            break;
+        }
+    }
+    results
+}
+
+/// Executes a statement, fetching at most `page_size` rows of a row-returning one from
+/// a cursor. Outside a transaction block the cursor gets a transaction of its own.
+async fn execute_paged(conn: &mut PgConnection, statement: &str, page_size: usize) -> Result<QueryResult> {
+    if classify(statement) != StatementKind::Query {
+        return execute_on(conn, statement).await;
+    }
+
+    // A savepoint can only be created inside a transaction block, and it keeps a
+    // failed DECLARE from aborting the script's transaction
+    let in_transaction = match sqlx::query("SAVEPOINT ferrum_declare").execute(&mut *conn).await {
+        Ok(_) => true,
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("25P01") => false,
+        // An aborted transaction rejects the statement, let it report that itself
+        Err(_) => return execute_on(conn, statement).await,
+    };
+    if !in_transaction {
+        sqlx::query("BEGIN").execute(&mut *conn).await?;
+    }
+
+    let declared = declare_cursor(conn, statement).await;
+    if in_transaction {
+        if declared.is_err() {
+            sqlx::query("ROLLBACK TO SAVEPOINT ferrum_declare").execute(&mut *conn).await?;
+        }
+        sqlx::query("RELEASE SAVEPOINT ferrum_declare").execute(&mut *conn).await?;
+    } else if declared.is_err() {
+        sqlx::query("ROLLBACK").execute(&mut *conn).await?;
+    }
+    if declared.is_err() {
+        // Not every statement can back a cursor (e.g. data-modifying CTEs), so run it directly
+        return execute_on(conn, statement).await;
+    }
+
+    // One row more than the page tells whether there are more. The statement is not
+    // prepared, as its row description depends on the cursor.
+    let fetch = format!("FETCH FORWARD {} FROM {}", page_size + 1, CURSOR_NAME);
+    let fetched = match sqlx::query(&fetch).persistent(false).fetch_all(&mut *conn).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            if !in_transaction {
+                sqlx::query("ROLLBACK").execute(&mut *conn).await?;
+            }
+            return Err(e.into());
+        }
+    };
+    let mut result = decode_rows(conn, &fetched[..fetched.len().min(page_size)]).await?;
+    result.more_rows = fetched.len() > page_size;
+
+    sqlx::query(&format!("CLOSE {}", CURSOR_NAME)).execute(&mut *conn).await?;
+    if !in_transaction {
+        sqlx::query("COMMIT").execute(&mut *conn).await?;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::backend::Backend;
+    use crate::database::{split_statements, DatabaseConfig, Driver};
+
+    #[tokio::test]
+    async fn test_run_script() {
+        let db = PostgresBackend::connect(&DatabaseConfig::new()).await.unwrap();
+        let pid = AtomicI64::new(0);
+        let statements = |script| split_statements(script, Driver::Postgres).into_iter().map(String::from).collect();
+
+        let (results, rolled_back) = db
+            .run_script(statements("BEGIN; SELECT 1 AS a; SELECT nope; SELECT 2"), 10, false, &pid)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].result.as_ref().unwrap().command_tag(), Some("BEGIN"));
+        assert_eq!(results[1].result.as_ref().unwrap().rows_as_strings(), vec![vec!["1"]]);
+        assert!(results[2].result.is_err());
+        assert!(rolled_back);
+
+        let (results, rolled_back) = db
+            .run_script(statements("SELECT nope; SELECT 2"), 10, true, &pid)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[1].result.is_ok());
+        assert!(!rolled_back);
+
+        // Only the first page of a row-returning statement is fetched, in or outside a transaction
+        let (results, rolled_back) = db
+            .run_script(
+                statements(
+                    "SELECT g FROM generate_series(1, 25) g; SELECT 1; BEGIN; \
+                     SELECT g FROM generate_series(1, 25) g; \
+                     CREATE TEMP TABLE ferrum_test_script (id int) ON COMMIT DROP; \
+                     WITH i AS (INSERT INTO ferrum_test_script SELECT generate_series(1, 12) RETURNING id) \
+                     SELECT id FROM i; \
+                     SELECT count(*) FROM ferrum_test_script",
+                ),
+                10,
+                false,
+                &pid,
+            )
+            .await
+            .unwrap();
+        let results: Vec<QueryResult> = results.into_iter().map(|result| result.result.unwrap()).collect();
+        assert_eq!(results.len(), 7);
+        assert_eq!(results[0].row_count(), 10);
+        assert!(results[0].has_more_rows());
+        assert_eq!(results[1].rows_as_strings(), vec![vec!["1"]]);
+        assert!(!results[1].has_more_rows());
+        assert_eq!(results[3].row_count(), 10);
+        assert!(results[3].has_more_rows());
+        // A data-modifying CTE cannot back a cursor, so it runs as it is
+        assert_eq!(results[5].row_count(), 12);
+        assert_eq!(results[6].rows_as_strings(), vec![vec!["12"]]);
+        assert!(rolled_back);
+    }
+
+    #[tokio::test]
+    async fn test_apply_row_changes() {
+        let db = PostgresBackend::connect(&DatabaseConfig::new()).await.unwrap();
+        let statements = |statements: &[&str]| statements.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        db.execute_query("CREATE TABLE IF NOT EXISTS ferrum_test_edits (id int PRIMARY KEY, v text)").await.unwrap();
+        db.execute_query("TRUNCATE ferrum_test_edits").await.unwrap();
+
+        db.apply_row_changes(&statements(&["INSERT INTO ferrum_test_edits VALUES (1, 'a');"]))
+            .await
+            .unwrap();
+        // The second statement matches no row, so the first is rolled back too
+        let error = db
+            .apply_row_changes(&statements(&[
+                "UPDATE ferrum_test_edits SET v = 'b' WHERE id = 1;",
+                "DELETE FROM ferrum_test_edits WHERE id = 2;",
+            ]))
+            .await;
+        assert!(error.is_err());
+        let rows = db.execute_query("SELECT v FROM ferrum_test_edits").await.unwrap();
+        assert_eq!(rows.rows_as_strings(), vec![vec!["a"]]);
+
+        db.execute_query("DROP TABLE ferrum_test_edits").await.unwrap();
+    }
+}
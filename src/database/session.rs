@@ -0,0 +1,264 @@
+use std::sync::atomic::AtomicI64;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use sqlx::pool::PoolConnection;
+use sqlx::postgres::PgRow;
+use sqlx::{PgConnection, Postgres};
+
+use super::cursor::{declare_cursor, fetch_first_page, record_backend_pid};
+use super::script::run_statements;
+use super::statement::{classify, runs_in_transaction, transaction_control, StatementKind, TransactionControl};
+use super::postgres::{execute_on, PostgresBackend};
+use super::{QueryOutput, RowCursor};
+
+/// Transaction status of a session connection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransactionStatus {
+    /// No transaction is open; the next statement starts one
+    Idle,
+    /// A transaction is open
+    Active,
+    /// The open transaction hit an error and can only be rolled back
+    Failed,
+}
+
+/// Transaction status and open savepoints of a session
+#[derive(Debug, Clone)]
+pub struct TransactionState {
+    /// Status of the transaction
+    pub status: TransactionStatus,
+    /// Names of the open savepoints, oldest first
+    pub savepoints: Vec<String>,
+}
+
+impl TransactionState {
+    /// Updates the state after a statement ran in the session
+    pub(super) fn record(&mut self, statement: &str, succeeded: bool) {
+        if !succeeded {
+            if self.status == TransactionStatus::Active {
+                self.status = TransactionStatus::Failed;
+            }
+            return;
+        }
+
+        match transaction_control(statement) {
+            Some(TransactionControl::Begin) => self.status = TransactionStatus::Active,
+            Some(TransactionControl::End) => {
+                self.status = TransactionStatus::Idle;
+                self.savepoints.clear();
+            }
+            Some(TransactionControl::Savepoint(name)) => self.savepoints.push(name),
+            Some(TransactionControl::Release(name)) => {
+                if let Some(index) = self.savepoints.iter().rposition(|savepoint| *savepoint == name) {
+                    self.savepoints.truncate(index);
+                }
+            }
+            Some(TransactionControl::RollbackTo(name)) => {
+                if let Some(index) = self.savepoints.iter().rposition(|savepoint| *savepoint == name) {
+                    self.savepoints.truncate(index + 1);
+                }
+                self.status = TransactionStatus::Active;
+            }
+            None => {}
+        }
+    }
+}
+
+/// A connection held out of the pool for transaction mode. Every statement runs on
+/// it, and a transaction is opened before the first one and kept open until it is
+/// committed or rolled back.
+#[derive(Debug, Clone)]
+pub struct Session {
+    conn: Arc<tokio::sync::Mutex<PoolConnection<Postgres>>>,
+    state: Arc<Mutex<TransactionState>>,
+}
+
+impl PostgresBackend {
+    /// Pins a connection for transaction mode
+    pub async fn open_session(&self) -> Result<Session> {
+        let conn = self.pool.acquire().await?;
+        Ok(Session {
+            conn: Arc::new(tokio::sync::Mutex::new(conn)),
+            state: Arc::new(Mutex::new(TransactionState {
+                status: TransactionStatus::Idle,
+                savepoints: Vec::new(),
+            })),
+        })
+    }
+}
+
+impl Session {
+    /// Gets the transaction status and open savepoints
+    pub fn state(&self) -> TransactionState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Gets the transaction status
+    pub fn status(&self) -> TransactionStatus {
+        self.state.lock().unwrap().status
+    }
+
+    /// Commits the open transaction. A failed transaction is rolled back instead.
+    pub async fn commit(&self) -> Result<()> {
+        self.execute("COMMIT").await
+    }
+
+    /// Rolls back the open transaction
+    pub async fn rollback(&self) -> Result<()> {
+        self.execute("ROLLBACK").await
+    }
+
+    /// Creates a savepoint, opening a transaction first if none is open, and returns its name
+    pub async fn savepoint(&self) -> Result<String> {
+        if self.status() == TransactionStatus::Idle {
+            self.execute("BEGIN").await?;
+        }
+        let name = format!("ferrum_sp_{}", self.state().savepoints.len() + 1);
+        self.execute(&format!("SAVEPOINT {}", name)).await?;
+        Ok(name)
+    }
+
+    /// Rolls back to the latest savepoint, which also recovers a failed transaction,
+    /// and returns its name. Returns `None` when there is no savepoint.
+    pub async fn rollback_to_savepoint(&self) -> Result<Option<String>> {
+        let Some(name) = self.state().savepoints.last().cloned() else {
+            return Ok(None);
+        };
+        self.execute(&format!("ROLLBACK TO SAVEPOINT {}", name)).await?;
+        Ok(Some(name))
+    }
+
+    /// Runs a statement in the session without reading its rows
+    pub(super) async fn execute(&self, statement: &str) -> Result<()> {
+        self.fetch_all(statement).await?;
+        Ok(())
+    }
+
+    /// Runs a statement in the session and reads all of its rows. The statement is not
+    /// prepared, since a FETCH returns different columns for each declared cursor.
+    pub(super) async fn fetch_all(&self, statement: &str) -> Result<Vec<PgRow>> {
+        let mut conn = self.conn.lock().await;
+        let result = sqlx::query(statement).persistent(false).fetch_all(&mut **conn).await;
+        self.record(statement, result.is_ok());
+        Ok(result?)
+    }
+
+    /// Locks the pinned connection
+    pub(super) async fn lock(&self) -> tokio::sync::MutexGuard<'_, PoolConnection<Postgres>> {
+        self.conn.lock().await
+    }
+
+    /// Updates the transaction state after a statement ran
+    pub(super) fn record(&self, statement: &str, succeeded: bool) {
+        self.state.lock().unwrap().record(statement, succeeded);
+    }
+
+    /// Opens a transaction before a statement that should run in one, if none is open
+    pub(super) async fn begin_if_needed(&self, conn: &mut PgConnection, statement: &str) -> Result<()> {
+        if self.status() == TransactionStatus::Idle && runs_in_transaction(statement) {
+            sqlx::query("BEGIN").execute(conn).await?;
+            self.record("BEGIN", true);
+        }
+        Ok(())
+    }
+
+    /// Runs the statements of a query in the session. A single row-returning
+    /// statement is paged through a cursor declared in the open transaction.
+    pub(super) async fn run(
+        &self,
+        statements: Vec<String>,
+        page_size: usize,
+        continue_on_error: bool,
+        backend_pid: &AtomicI64,
+    ) -> Result<QueryOutput> {
+        let mut conn = self.conn.lock().await;
+        record_backend_pid(&mut conn, backend_pid).await?;
+
+        if statements.len() > 1 {
+            let results = run_statements(&mut conn, statements, page_size, continue_on_error, Some(self)).await;
+            return Ok(QueryOutput::Script(results, false));
+        }
+
+        let query = statements.into_iter().next().unwrap_or_default();
+        self.begin_if_needed(&mut conn, &query).await?;
+
+        if classify(&query) == StatementKind::Query && self.status() == TransactionStatus::Active {
+            // A failed DECLARE would abort the whole transaction, so it is tried under a savepoint
+            sqlx::query("SAVEPOINT ferrum_declare").execute(&mut **conn).await?;
+            let declared = declare_cursor(&mut conn, &query).await;
+            if declared.is_err() {
+                sqlx::query("ROLLBACK TO SAVEPOINT ferrum_declare").execute(&mut **conn).await?;
+            }
+            sqlx::query("RELEASE SAVEPOINT ferrum_declare").execute(&mut **conn).await?;
+
+            if declared.is_ok() {
+                drop(conn);
+                let output = fetch_first_page(&query, RowCursor::on_session(self.clone(), page_size)).await;
+                self.record(&query, output.is_ok());
+                let (result, cursor) = output?;
+                return Ok(QueryOutput::Single(result, cursor));
+            }
+        }
+
+        let result = execute_on(&mut conn, &query).await;
+        self.record(&query, result.is_ok());
+        Ok(QueryOutput::Single(result?, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseConfig;
+
+    #[tokio::test]
+    async fn test_session_transaction() {
+        let db = PostgresBackend::connect(&DatabaseConfig::new()).await.unwrap();
+        let session = db.open_session().await.unwrap();
+        let pid = AtomicI64::new(0);
+        let run = |query: &str| session.run(vec![query.to_string()], 10, false, &pid);
+
+        run("CREATE TEMP TABLE ferrum_test_session (id int)").await.unwrap();
+        assert_eq!(session.status(), TransactionStatus::Active);
+        session.commit().await.unwrap();
+        assert_eq!(session.status(), TransactionStatus::Idle);
+
+        run("INSERT INTO ferrum_test_session VALUES (1)").await.unwrap();
+        assert_eq!(session.savepoint().await.unwrap(), "ferrum_sp_1");
+        run("INSERT INTO ferrum_test_session VALUES (2)").await.unwrap();
+        assert!(run("SELECT nope").await.is_err());
+        assert_eq!(session.status(), TransactionStatus::Failed);
+
+        session.rollback_to_savepoint().await.unwrap();
+        assert_eq!(session.status(), TransactionStatus::Active);
+        match run("SELECT count(*) FROM ferrum_test_session").await.unwrap() {
+            QueryOutput::Single(result, _) => assert_eq!(result.rows_as_strings(), vec![vec!["1"]]),
+            QueryOutput::Script(..) => panic!("expected a single result"),
+        }
+
+        session.rollback().await.unwrap();
+        assert_eq!(session.state().savepoints, Vec::<String>::new());
+        match run("SELECT count(*) FROM ferrum_test_session").await.unwrap() {
+            QueryOutput::Single(result, _) => assert_eq!(result.rows_as_strings(), vec![vec!["0"]]),
+            QueryOutput::Script(..) => panic!("expected a single result"),
+        }
+        session.rollback().await.unwrap();
+
+        // Cursors declared one after another on the pinned connection keep their own columns
+        run("CREATE TEMP TABLE ferrum_test_session_pages (id int)").await.unwrap();
+        for (query, second_page) in [
+            ("SELECT g FROM generate_series(1, 25) g", "11"),
+            ("SELECT 'row ' || g FROM generate_series(1, 25) g", "row 11"),
+        ] {
+            match run(query).await.unwrap() {
+                QueryOutput::Single(_, Some(mut cursor)) => {
+                    assert_eq!(cursor.fetch_page().await.unwrap().rows_as_strings()[0], vec![second_page]);
+                    cursor.close().await.unwrap();
+                }
+                _ => panic!("expected a cursor"),
+            }
+        }
+        session.rollback().await.unwrap();
+    }
+}
@@ -0,0 +1,242 @@
+use super::backend::Driver;
+use super::statement::skip_comments;
+
+/// Splits a script into statements at semicolons, ignoring semicolons inside
+/// string literals, quoted identifiers, dollar-quoted bodies and comments as
+/// `driver` writes them. Statements are trimmed, and empty ones (only whitespace
+/// or comments) are dropped.
+pub fn split_statements(script: &str, driver: Driver) -> Vec<&str> {
+    let bytes = script.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if let Some(end) = skip_literal(bytes, i, driver) {
+            i = end;
+            continue;
+        }
+        if bytes[i] == b';' {
+            statements.push(&script[start..i]);
+            start = i + 1;
+        }
+        i += 1;
+    }
+    statements.push(&script[start..]);
+
+    statements
+        .into_iter()
+        .map(str::trim)
+        .filter(|statement| !skip_comments(statement, driver).is_empty())
+        .collect()
+}
+
+/// Gets the tokens of a statement outside parentheses, string literals and comments:
+/// words, quoted identifiers (with their quotes), `.` and `,`
+pub(super) fn top_level_tokens(statement: &str, driver: Driver) -> Vec<&str> {
+    let bytes = statement.as_bytes();
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start = i;
+        i = match (skip_literal(bytes, i, driver), bytes[i]) {
+            (Some(end), _) => end,
+            (None, b'(') => {
+                depth += 1;
+                i + 1
+            }
+            (None, b')') => {
+                depth = depth.saturating_sub(1);
+                i + 1
+            }
+            (None, byte) if is_identifier_byte(byte) => {
+                bytes[i..].iter().position(|b| !is_identifier_byte(*b)).map_or(bytes.len(), |end| i + end)
+            }
+            _ => i + 1,
+        };
+
+        if depth == 0 {
+            let token = &statement[start..i];
+            let keep = match bytes[start] {
+                b'.' | b',' => true,
+                quote @ (b'"' | b'`') => quote == identifier_quote(driver),
+                byte => is_identifier_byte(byte),
+            };
+            if keep {
+                tokens.push(token);
+            }
+        }
+    }
+    tokens
+}
+
+/// Gets the quote character of quoted identifiers: MySQL uses backticks, as
+/// double quotes delimit strings unless the ANSI_QUOTES mode is set
+pub(super) fn identifier_quote(driver: Driver) -> u8 {
+    match driver {
+        Driver::Mysql => b'`',
+        Driver::Postgres | Driver::Sqlite => b'"',
+    }
+}
+
+/// Skips the string literal, quoted identifier, dollar-quoted body or comment
+/// starting at `i`, returning the index after it, or `None` if none starts there.
+/// MySQL strings take backslash escapes in either quote, identifiers are quoted
+/// with backticks, `#` starts a comment, block comments do not nest and there
+/// is no dollar quoting.
+pub(super) fn skip_literal(bytes: &[u8], i: usize, driver: Driver) -> Option<usize> {
+    if let Some(end) = skip_comment(bytes, i, driver) {
+        return Some(end);
+    }
+    let mysql = driver == Driver::Mysql;
+    let end = match *bytes.get(i)? {
+        b'\'' => skip_quoted(bytes, i, b'\'', mysql || is_escape_string(bytes, i)),
+        b'"' => skip_quoted(bytes, i, b'"', mysql),
+        b'`' if mysql => skip_quoted(bytes, i, b'`', false),
+        b'$' if !mysql && (i == 0 || !is_identifier_byte(bytes[i - 1])) => {
+            let tag = dollar_tag(bytes, i)?;
+            let body = i + tag.len();
+            find(&bytes[body..], tag).map_or(bytes.len(), |end| body + end + tag.len())
+        }
+        _ => return None,
+    };
+    Some(end)
+}
+
+/// Skips the comment starting at `i`, returning the index after it, or `None`
+/// if none starts there. A MySQL `--` comment needs a space after the dashes.
+pub(super) fn skip_comment(bytes: &[u8], i: usize, driver: Driver) -> Option<usize> {
+    let mysql = driver == Driver::Mysql;
+    let line_comment = match (*bytes.get(i)?, bytes.get(i + 1)) {
+        (b'-', Some(b'-')) => !mysql || bytes.get(i + 2).is_none_or(|b| b.is_ascii_whitespace()),
+        (b'#', _) => mysql,
+        (b'/', Some(b'*')) if mysql => {
+            return Some(find(&bytes[i + 2..], b"*/").map_or(bytes.len(), |end| i + end + 4));
+        }
+        (b'/', Some(b'*')) => return Some(skip_block_comment(bytes, i)),
+        _ => false,
+    };
+    line_comment.then(|| bytes[i..].iter().position(|b| *b == b'\n').map_or(bytes.len(), |end| i + end + 1))
+}
+
+/// Whether the string literal starting at `quote` is an E'...' string, which allows backslash escapes
+pub(super) fn is_escape_string(bytes: &[u8], quote: usize) -> bool {
+    quote > 0
+        && bytes[quote - 1].eq_ignore_ascii_case(&b'e')
+        && (quote < 2 || !is_identifier_byte(bytes[quote - 2]))
+}
+
+pub(super) fn is_identifier_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte >= 0x80
+}
+
+/// Skips a quoted string or identifier starting at `start`, where a doubled quote
+/// stands for the quote itself, returning the index after the closing quote
+pub(super) fn skip_quoted(bytes: &[u8], start: usize, quote: u8, escapes: bool) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if escapes && bytes[i] == b'\\' {
+            i += 2;
+        } else if bytes[i] == quote {
+            if bytes.get(i + 1) == Some(&quote) {
+                i += 2;
+            } else {
+                return i + 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// Skips a possibly nested block comment starting at `start`
+pub(super) fn skip_block_comment(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Gets the dollar-quote tag (`$$` or `$name$`) starting at `start`, if any
+pub(super) fn dollar_tag(bytes: &[u8], start: usize) -> Option<&[u8]> {
+    let rest = &bytes[start + 1..];
+    let length = rest.iter().position(|b| *b == b'$')?;
+    let name = &rest[..length];
+    let valid = name.first().is_none_or(|b| !b.is_ascii_digit())
+        && name.iter().all(|b| is_identifier_byte(*b));
+    valid.then_some(&bytes[start..start + length + 2])
+}
+
+pub(super) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements() {
+        assert_eq!(
+            split_statements("BEGIN; UPDATE t SET a = 'x;y'; SELECT \"a;b\" FROM t;\nCOMMIT;", Driver::Postgres),
+            vec!["BEGIN", "UPDATE t SET a = 'x;y'", "SELECT \"a;b\" FROM t", "COMMIT"]
+        );
+        assert_eq!(
+            split_statements("SELECT 'it''s;' ; SELECT E'a\\';b'; -- trailing; comment\n", Driver::Postgres),
+            vec!["SELECT 'it''s;'", "SELECT E'a\\';b'"]
+        );
+        assert_eq!(
+            split_statements(
+                "CREATE FUNCTION f() RETURNS int AS $body$ BEGIN RETURN 1; END; $body$ LANGUAGE plpgsql;\
+                 /* a; /* nested; */ b; */ SELECT $1, $$;$$",
+                Driver::Postgres
+            ),
+            vec![
+                "CREATE FUNCTION f() RETURNS int AS $body$ BEGIN RETURN 1; END; $body$ LANGUAGE plpgsql",
+                "/* a; /* nested; */ b; */ SELECT $1, $$;$$",
+            ]
+        );
+        assert_eq!(split_statements("  ;; -- nothing\n", Driver::Postgres), Vec::<&str>::new());
+
+        // MySQL quotes identifiers with backticks, takes backslash escapes in
+        // strings, starts comments with `#` and does not nest block comments
+        assert_eq!(
+            split_statements("SELECT `a;b` FROM t; SELECT 'it\\'s;', \"x\\\";y\" # a; comment\n;", Driver::Mysql),
+            vec!["SELECT `a;b` FROM t", "SELECT 'it\\'s;', \"x\\\";y\" # a; comment"]
+        );
+        assert_eq!(
+            split_statements("SELECT 1--1; /* a /* b; */ SELECT $$; -- c; d\n", Driver::Mysql),
+            vec!["SELECT 1--1", "/* a /* b; */ SELECT $$"]
+        );
+    }
+
+    #[test]
+    fn test_top_level_tokens() {
+        assert_eq!(
+            top_level_tokens("UPDATE public.\"My Table\" SET a = (SELECT 1 WHERE true) -- WHERE\n, b = 'WHERE'", Driver::Postgres),
+            vec!["UPDATE", "public", ".", "\"My Table\"", "SET", "a", ",", "b"]
+        );
+        assert_eq!(
+            top_level_tokens("UPDATE `My Table` SET a = \"WHERE\" # WHERE\n, b = 'it\\'s WHERE'", Driver::Mysql),
+            vec!["UPDATE", "`My Table`", "SET", "a", ",", "b"]
+        );
+    }
+}
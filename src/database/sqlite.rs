@@ -0,0 +1,606 @@
+use std::str::FromStr;
+use std::sync::atomic::AtomicI64;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteQueryResult, SqliteRow, SqliteTypeInfo};
+use sqlx::{Column, Pool, Row, Sqlite, TypeInfo, ValueRef};
+
+use super::backend::{apply_text_changes, open_text_query, run_text_script, stream_rows, Backend, Decoder, Driver};
+use super::connection::check_pragma;
+use super::decode::format_bytea;
+use super::schema::{ColumnInfo, ConstraintInfo};
+use super::structure::{ColumnDetails, ConstraintDetails, IndexDetails, TriggerDetails};
+use super::{
+    quote_identifier, split_qualified_name, tokenize, Catalog, CatalogTable, CellValue, DatabaseConfig, ObjectKind,
+    QueryResult, RowCursor, StatementResult, TableStructure, TokenKind,
+};
+
+/// Backend for SQLite database files. Schemas are the attached databases, e.g. `main`.
+#[derive(Debug, Clone)]
+pub struct SqliteBackend {
+    pool: Pool<Sqlite>,
+    /// Path of the database file
+    database: String,
+}
+
+impl SqliteBackend {
+    /// Opens the database file, creating it unless the connection is read-only.
+    /// `:memory:` opens an in-memory database shared by the pooled connections.
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self> {
+        let in_memory = config.database == ":memory:";
+        let mut options = if in_memory {
+            SqliteConnectOptions::from_str("sqlite::memory:")?
+        } else {
+            SqliteConnectOptions::new()
+                .filename(&config.database)
+                .create_if_missing(!config.read_only)
+        };
+        // Parameters are pragmas, e.g. `journal_mode = "wal"`
+        for (key, value) in &config.params {
+            check_pragma(key, value)?;
+            options = options.pragma(key.clone(), value.clone());
+        }
+
+        let mut pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .acquire_timeout(Duration::from_secs(3));
+        if in_memory {
+            // The database lives only as long as one of its connections
+            pool = pool.min_connections(1).idle_timeout(None).max_lifetime(None);
+        }
+        let pool = pool.connect_with(options.read_only(config.read_only)).await?;
+
+        Ok(Self {
+            pool,
+            database: config.database.clone(),
+        })
+    }
+
+    /// Gets the columns of a table with their declared types and key positions
+    async fn table_info(&self, schema: &str, table: &str) -> Result<Vec<SqliteRow>> {
+        let rows = sqlx::query(
+            "SELECT name, type, \"notnull\", dflt_value, pk, hidden \
+             FROM pragma_table_xinfo(?1, ?2) ORDER BY cid;",
+        )
+        .bind(table)
+        .bind(schema)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Gets the primary key, unique and foreign key constraints of a table. SQLite
+    /// keeps no names for them, so they are named after their columns.
+    async fn constraints(&self, schema: &str, table: &str) -> Result<Vec<(ConstraintDetails, &'static str)>> {
+        let mut constraints = Vec::new();
+
+        let mut key: Vec<(i64, String)> = self
+            .table_info(schema, table)
+            .await?
+            .iter()
+            .filter(|row| row.get::<i64, _>("pk") > 0)
+            .map(|row| (row.get("pk"), row.get("name")))
+            .collect();
+        key.sort();
+        if !key.is_empty() {
+            let columns = column_list(key.into_iter().map(|(_, name)| name));
+            constraints.push((constraint(&columns, format!("PRIMARY KEY ({})", columns)), "PRIMARY KEY"));
+        }
+
+        let unique: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM pragma_index_list(?1, ?2) WHERE origin = 'u' ORDER BY seq;",
+        )
+        .bind(table)
+        .bind(schema)
+        .fetch_all(&self.pool)
+        .await?;
+        for index in unique {
+            let columns = column_list(self.index_columns(schema, &index).await?);
+            constraints.push((constraint(&columns, format!("UNIQUE ({})", columns)), "UNIQUE"));
+        }
+
+        let rows = sqlx::query(
+            "SELECT id, \"table\", \"from\", \"to\", on_update, on_delete \
+             FROM pragma_foreign_key_list(?1, ?2) ORDER BY id, seq;",
+        )
+        .bind(table)
+        .bind(schema)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut keys: Vec<ForeignKey> = Vec::new();
+        for row in &rows {
+            let id: i64 = row.get("id");
+            if keys.last().is_none_or(|key| key.id != id) {
+                keys.push(ForeignKey {
+                    id,
+                    parent: row.get("table"),
+                    columns: Vec::new(),
+                    parent_columns: Vec::new(),
+                    on_update: row.get("on_update"),
+                    on_delete: row.get("on_delete"),
+                });
+            }
+            let key = keys.last_mut().unwrap();
+            key.columns.push(row.get("from"));
+            // The referenced columns are left out when the key refers to the primary key
+            key.parent_columns.extend(row.get::<Option<String>, _>("to"));
+        }
+        for key in keys {
+            let columns = column_list(key.columns);
+            let mut definition = format!("FOREIGN KEY ({}) REFERENCES {}", columns, quote_identifier(&key.parent, Driver::Sqlite));
+            if !key.parent_columns.is_empty() {
+                definition.push_str(&format!(" ({})", column_list(key.parent_columns)));
+            }
+            for (action, event) in [(key.on_update, "UPDATE"), (key.on_delete, "DELETE")] {
+                if action != "NO ACTION" {
+                    definition.push_str(&format!(" ON {} {}", event, action));
+                }
+            }
+            constraints.push((constraint(&columns, definition), "FOREIGN KEY"));
+        }
+        Ok(constraints)
+    }
+
+    /// Gets the columns of an index in index order
+    async fn index_columns(&self, schema: &str, index: &str) -> Result<Vec<String>> {
+        let columns = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT name FROM pragma_index_info(?1, ?2) ORDER BY seqno;",
+        )
+        .bind(index)
+        .bind(schema)
+        .fetch_all(&self.pool)
+        .await?;
+        // Expressions have no column name
+        Ok(columns.into_iter().map(|name| name.unwrap_or_else(|| "<expression>".to_string())).collect())
+    }
+}
+
+/// A foreign key as `pragma_foreign_key_list` lists it, a row per column
+struct ForeignKey {
+    id: i64,
+    parent: String,
+    columns: Vec<String>,
+    parent_columns: Vec<String>,
+    on_update: String,
+    on_delete: String,
+}
+
+/// A constraint named after its columns
+fn constraint(columns: &str, definition: String) -> ConstraintDetails {
+    ConstraintDetails {
+        name: columns.to_string(),
+        definition,
+        inherited: false,
+    }
+}
+
+/// Quotes and joins column names
+fn column_list(columns: impl IntoIterator<Item = String>) -> String {
+    columns.into_iter().map(|column| quote_identifier(&column, Driver::Sqlite)).collect::<Vec<_>>().join(", ")
+}
+
+impl Decoder for Sqlite {
+    fn decode_cell(row: &SqliteRow, index: usize) -> CellValue {
+        let raw = match row.try_get_raw(index) {
+            Ok(raw) => raw,
+            Err(_) => return CellValue::Undecodable("?".to_string()),
+        };
+        if raw.is_null() {
+            return CellValue::Null;
+        }
+
+        // Values are decoded by how they are stored, which need not match the declared type
+        let declared = Self::type_name(row.columns()[index].type_info());
+        let storage = raw.type_info().name().to_string();
+        let value = match storage.as_str() {
+            "INTEGER" if declared == "BOOL" => row.try_get_unchecked::<bool, _>(index).map(|value| value.to_string()),
+            "INTEGER" => row.try_get_unchecked::<i64, _>(index).map(|value| value.to_string()),
+            "REAL" => row.try_get_unchecked::<f64, _>(index).map(|value| value.to_string()),
+            "BLOB" => row.try_get_unchecked::<Vec<u8>, _>(index).map(|value| format_bytea(&value)),
+            _ => row.try_get_unchecked::<String, _>(index),
+        };
+        match value {
+            Ok(value) => CellValue::Value(value),
+            Err(_) => CellValue::Undecodable(storage),
+        }
+    }
+
+    fn type_name(type_info: &SqliteTypeInfo) -> String {
+        match type_info.name() {
+            "INTEGER" => "INT8",
+            "REAL" => "FLOAT8",
+            "BLOB" => "BYTEA",
+            "BOOLEAN" => "BOOL",
+            "DATETIME" => "TIMESTAMP",
+            // Expressions have no declared type
+            "NULL" => "",
+            name => name,
+        }
+        .to_string()
+    }
+
+    fn column_type(row: &SqliteRow, index: usize) -> String {
+        let declared = Self::type_name(row.columns()[index].type_info());
+        if !declared.is_empty() {
+            return declared;
+        }
+        match row.try_get_raw(index) {
+            Ok(raw) if !raw.is_null() => Self::type_name(&raw.type_info()),
+            _ => declared,
+        }
+    }
+
+    fn rows_affected(done: &SqliteQueryResult) -> u64 {
+        done.rows_affected()
+    }
+}
+
+#[async_trait]
+impl Backend for SqliteBackend {
+    fn driver(&self) -> Driver {
+        Driver::Sqlite
+    }
+
+    #[cfg(test)]
+    async fn execute_query(&self, query: &str) -> Result<QueryResult> {
+        let mut conn = self.pool.acquire().await?;
+        super::backend::execute_text::<Sqlite>(&mut conn, query).await
+    }
+
+    async fn open_query(
+        &self,
+        query: &str,
+        page_size: usize,
+        _backend_pid: &AtomicI64,
+    ) -> Result<(QueryResult, Option<RowCursor>)> {
+        open_text_query(self.pool.acquire().await?, query, page_size).await
+    }
+
+    async fn open_read_only_cursor(&self, query: &str, page_size: usize) -> Result<RowCursor> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("PRAGMA query_only = ON").execute(&mut *conn).await?;
+        Ok(stream_rows(conn, query.to_string(), page_size, Some("PRAGMA query_only = OFF")))
+    }
+
+    async fn run_script(
+        &self,
+        statements: Vec<String>,
+        page_size: usize,
+        continue_on_error: bool,
+        _backend_pid: &AtomicI64,
+    ) -> Result<(Vec<StatementResult>, bool)> {
+        let mut conn = self.pool.acquire().await?;
+        run_text_script::<Sqlite>(&mut conn, statements, page_size, continue_on_error).await
+    }
+
+    async fn apply_row_changes(&self, statements: &[String]) -> Result<()> {
+        apply_text_changes(&self.pool, statements).await
+    }
+
+    async fn cancel_backend(&self, _pid: i64) -> Result<bool> {
+        Ok(false)
+    }
+
+    async fn list_databases(&self) -> Result<Vec<String>> {
+        Ok(vec![self.database.clone()])
+    }
+
+    async fn list_schemas(&self) -> Result<Vec<String>> {
+        let schemas = sqlx::query_scalar("SELECT name FROM pragma_database_list ORDER BY seq;")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(schemas)
+    }
+
+    async fn list_objects(&self, schema: &str, kind: ObjectKind) -> Result<Vec<String>> {
+        let object_type = match kind {
+            ObjectKind::Table => "table",
+            ObjectKind::View => "view",
+            _ => return Ok(Vec::new()),
+        };
+        let names = sqlx::query_scalar(&format!(
+            "SELECT name FROM {}.sqlite_master WHERE type = ?1 AND name NOT LIKE 'sqlite_%' ORDER BY name;",
+            quote_identifier(schema, Driver::Sqlite)
+        ))
+        .bind(object_type)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(names)
+    }
+
+    async fn list_columns(&self, schema: &str, table: &str) -> Result<Vec<ColumnInfo>> {
+        Ok(self
+            .table_info(schema, table)
+            .await?
+            .iter()
+            .map(|row| ColumnInfo {
+                name: row.get("name"),
+                data_type: row.get("type"),
+                nullable: !row.get::<bool, _>("notnull"),
+            })
+            .collect())
+    }
+
+    async fn list_indexes(&self, schema: &str, table: &str) -> Result<Vec<String>> {
+        let names = sqlx::query_scalar("SELECT name FROM pragma_index_list(?1, ?2) ORDER BY name;")
+            .bind(table)
+            .bind(schema)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(names)
+    }
+
+    async fn list_constraints(&self, schema: &str, table: &str) -> Result<Vec<ConstraintInfo>> {
+        Ok(self
+            .constraints(schema, table)
+            .await?
+            .into_iter()
+            .map(|(constraint, kind)| ConstraintInfo {
+                name: constraint.name,
+                kind: kind.to_string(),
+            })
+            .collect())
+    }
+
+    async fn primary_key(&self, table: &str) -> Result<Vec<String>> {
+        let (schema, table) = split_qualified_name(table);
+        let mut key: Vec<(i64, String)> = self
+            .table_info(schema.as_deref().unwrap_or("main"), &table)
+            .await?
+            .iter()
+            .filter(|row| row.get::<i64, _>("pk") > 0)
+            .map(|row| (row.get("pk"), row.get("name")))
+            .collect();
+        key.sort();
+        Ok(key.into_iter().map(|(_, name)| name).collect())
+    }
+
+    async fn estimate_rows(&self, _table: &str) -> Result<Option<i64>> {
+        // SQLite keeps no row estimates unless ANALYZE has been run
+        Ok(None)
+    }
+
+    async fn table_structure(&self, table: &str) -> Result<TableStructure> {
+        let (schema, name) = split_qualified_name(table);
+        let schema = schema.unwrap_or_else(|| "main".to_string());
+        let master = format!("{}.sqlite_master", quote_identifier(&schema, Driver::Sqlite));
+
+        let row = sqlx::query(&format!("SELECT type, sql FROM {} WHERE name = ?1 AND type IN ('table', 'view');", master))
+            .bind(&name)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| anyhow!("Table {} not found", table))?;
+        let kind: String = row.get("type");
+        let sql: String = row.get("sql");
+
+        let mut structure = TableStructure {
+            driver: Driver::Sqlite,
+            schema: schema.clone(),
+            name: name.clone(),
+            kind: kind.clone(),
+            ..TableStructure::default()
+        };
+        structure.columns = self
+            .table_info(&schema, &name)
+            .await?
+            .iter()
+            .map(|row| {
+                // Generated columns are hidden 2 (virtual) or 3 (stored)
+                let generated = match row.get::<i64, _>("hidden") {
+                    2 => Some("GENERATED ALWAYS AS (...) VIRTUAL".to_string()),
+                    3 => Some("GENERATED ALWAYS AS (...) STORED".to_string()),
+                    _ => None,
+                };
+                ColumnDetails {
+                    name: row.get("name"),
+                    data_type: row.get("type"),
+                    nullable: !row.get::<bool, _>("notnull"),
+                    default: row.get("dflt_value"),
+                    generated,
+                    ..ColumnDetails::default()
+                }
+            })
+            .collect();
+
+        if kind == "view" {
+            // The query follows the first AS of `CREATE VIEW name AS ...`
+            structure.view_definition = tokenize(&sql)
+                .into_iter()
+                .find(|token| token.kind == TokenKind::Keyword && sql[token.range.clone()].eq_ignore_ascii_case("AS"))
+                .map(|token| sql[token.range.end..].trim().to_string());
+        } else {
+            structure.constraints = self.constraints(&schema, &name).await?.into_iter().map(|(c, _)| c).collect();
+        }
+
+        let mut ddl = vec![format!("{};", sql)];
+        let indexes = sqlx::query(&format!(
+            "SELECT i.name, i.\"unique\", i.origin, m.sql \
+             FROM pragma_index_list(?1, ?2) i LEFT JOIN {} m ON m.type = 'index' AND m.name = i.name \
+             ORDER BY i.name;",
+            master
+        ))
+        .bind(&name)
+        .bind(&schema)
+        .fetch_all(&self.pool)
+        .await?;
+        for index in &indexes {
+            let index_name: String = index.get("name");
+            let origin: String = index.get("origin");
+            let definition = match index.get::<Option<String>, _>("sql") {
+                Some(sql) => {
+                    ddl.push(format!("{};", sql));
+                    sql
+                }
+                // Indexes made for constraints have no statement of their own
+                None => format!(
+                    "CREATE {}INDEX {} ON {} ({})",
+                    if index.get::<bool, _>("unique") { "UNIQUE " } else { "" },
+                    quote_identifier(&index_name, Driver::Sqlite),
+                    quote_identifier(&name, Driver::Sqlite),
+                    column_list(self.index_columns(&schema, &index_name).await?)
+                ),
+            };
+            structure.indexes.push(IndexDetails {
+                name: index_name,
+                definition,
+                constraint: origin != "c",
+                inherited: false,
+                valid: true,
+            });
+        }
+
+        let triggers = sqlx::query(&format!(
+            "SELECT name, sql FROM {} WHERE type = 'trigger' AND tbl_name = ?1 ORDER BY name;",
+            master
+        ))
+        .bind(&name)
+        .fetch_all(&self.pool)
+        .await?;
+        for trigger in &triggers {
+            let definition: String = trigger.get("sql");
+            ddl.push(format!("{};", definition));
+            structure.triggers.push(TriggerDetails {
+                name: trigger.get("name"),
+                definition,
+                enabled: true,
+                inherited: false,
+            });
+        }
+
+        structure.reported_ddl = Some(ddl.join("\n\n"));
+        Ok(structure)
+    }
+
+    async fn load_catalog(&self) -> Result<Catalog> {
+        let schemas = self.list_schemas().await?;
+
+        let mut tables = Vec::new();
+        for schema in &schemas {
+            let rows = sqlx::query(&format!(
+                "SELECT m.name AS name, p.name AS column_name \
+                 FROM {}.sqlite_master m LEFT JOIN pragma_table_info(m.name, ?1) p \
+                 WHERE m.type IN ('table', 'view') AND m.name NOT LIKE 'sqlite_%' \
+                 ORDER BY m.name, p.cid;",
+                quote_identifier(schema, Driver::Sqlite)
+            ))
+            .bind(schema)
+            .fetch_all(&self.pool)
+            .await?;
+
+            for row in &rows {
+                let name: String = row.get("name");
+                if tables.last().is_none_or(|table: &CatalogTable| table.schema != *schema || table.name != name) {
+                    tables.push(CatalogTable {
+                        schema: schema.clone(),
+                        name,
+                        columns: Vec::new(),
+                    });
+                }
+                let table = tables.last_mut().unwrap();
+                table.columns.extend(row.get::<Option<String>, _>("column_name"));
+            }
+        }
+
+        // Built-in and loaded functions; older SQLite versions cannot list them
+        let functions = sqlx::query_scalar("SELECT DISTINCT name FROM pragma_function_list ORDER BY name;")
+            .fetch_all(&self.pool)
+            .await
+            .unwrap_or_default();
+
+        Ok(Catalog {
+            schemas,
+            tables,
+            functions,
+            default_schema: "main".to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{DatabaseManager, QueryOutput};
+
+    #[tokio::test]
+    async fn test_sqlite_backend() {
+        let config = DatabaseConfig {
+            driver: Driver::Sqlite,
+            database: ":memory:".to_string(),
+            ..DatabaseConfig::default()
+        };
+        let db = DatabaseManager::new(config).await.unwrap();
+        let pid = AtomicI64::new(0);
+
+        let script = "CREATE TABLE parent (id INTEGER PRIMARY KEY, code TEXT UNIQUE NOT NULL); \
+                      CREATE TABLE child (id INTEGER PRIMARY KEY, parent_id INTEGER REFERENCES parent (id) ON DELETE CASCADE, \
+                                          active BOOLEAN DEFAULT 1, score REAL, data BLOB); \
+                      CREATE INDEX child_parent ON child (parent_id); \
+                      INSERT INTO parent (code) VALUES ('a'), ('b'), ('c'); \
+                      INSERT INTO child (parent_id, active, score, data) VALUES (1, 1, 1.5, x'dead'), (2, 0, NULL, NULL)";
+        match db.spawn_query(script.to_string(), 10, false, None).finish().await.unwrap() {
+            QueryOutput::Script(results, rolled_back) => {
+                assert!(results.iter().all(|result| result.result.is_ok()));
+                assert_eq!(results[3].result.as_ref().unwrap().rows_affected(), Some(3));
+                assert!(!rolled_back);
+            }
+            QueryOutput::Single(..) => panic!("expected a script"),
+        }
+
+        let (page, cursor) = db.open_query("SELECT * FROM child ORDER BY id", 10, &pid).await.unwrap();
+        assert!(cursor.is_none());
+        assert_eq!(page.columns(), vec!["id", "parent_id", "active", "score", "data"]);
+        assert_eq!(page.column_types(), vec!["INT8", "INT8", "BOOL", "FLOAT8", "BYTEA"]);
+        assert_eq!(page.rows_as_strings(), vec![vec!["1", "1", "true", "1.5", "\\xdead"], vec!["2", "2", "false", "NULL", "NULL"]]);
+
+        let (page, cursor) = db.open_query("SELECT code, 1 + 1 AS two FROM parent", 2, &pid).await.unwrap();
+        assert_eq!(page.column_types(), vec!["TEXT", "INT8"]);
+        let mut cursor = cursor.expect("more rows available");
+        assert_eq!(cursor.fetch_page().await.unwrap().rows_as_strings(), vec![vec!["c", "2"]]);
+        cursor.close().await.unwrap();
+
+        let (page, _) = db.open_query("SELECT code FROM parent WHERE 0", 10, &pid).await.unwrap();
+        assert_eq!((page.row_count(), page.columns()), (0, vec!["code".to_string()]));
+
+        assert_eq!(db.list_schemas().await.unwrap(), vec!["main"]);
+        assert_eq!(db.list_objects("main", ObjectKind::Table).await.unwrap(), vec!["child", "parent"]);
+        assert_eq!(db.primary_key("main.\"child\"").await.unwrap(), vec!["id"]);
+        let constraints: Vec<(String, String)> = db
+            .list_constraints("main", "child")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|constraint| (constraint.name, constraint.kind))
+            .collect();
+        assert_eq!(
+            constraints,
+            vec![("id".to_string(), "PRIMARY KEY".to_string()), ("parent_id".to_string(), "FOREIGN KEY".to_string())]
+        );
+
+        let structure = db.table_structure("child").await.unwrap();
+        assert_eq!(structure.columns[2].default.as_deref(), Some("1"));
+        assert_eq!(
+            structure.constraints[1].definition,
+            "FOREIGN KEY (parent_id) REFERENCES parent (id) ON DELETE CASCADE"
+        );
+        assert!(structure.ddl().ends_with("CREATE INDEX child_parent ON child (parent_id);"));
+
+        let catalog = db.load_catalog().await.unwrap();
+        assert_eq!(catalog.table(None, "parent").unwrap().columns, vec!["id", "code"]);
+
+        db.apply_row_changes(&["UPDATE parent SET code = 'z' WHERE id = 3;".to_string()]).await.unwrap();
+        assert!(db.apply_row_changes(&["DELETE FROM parent WHERE id = 4;".to_string()]).await.is_err());
+
+        let (results, rolled_back) = db
+            .run_script(vec!["BEGIN".to_string(), "DELETE FROM child".to_string()], 10, false, &pid)
+            .await
+            .unwrap();
+        assert_eq!(results[1].result.as_ref().unwrap().rows_affected(), Some(2));
+        assert!(rolled_back);
+        assert_eq!(db.execute_query("SELECT count(*) FROM child").await.unwrap().rows_as_strings(), vec![vec!["2"]]);
+
+        let mut cursor = db.open_read_only_cursor("DELETE FROM child RETURNING id", 10).await.unwrap();
+        assert!(cursor.fetch_page().await.is_err());
+    }
+}
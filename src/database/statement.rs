@@ -0,0 +1,438 @@
+use super::backend::Driver;
+use super::splitter::{skip_comment, top_level_tokens};
+use super::tokenizer::{tokenize, TokenKind};
+
+/// How the outcome of a statement is reported
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementKind {
+    /// Returns rows and can be read through a cursor (SELECT, WITH, VALUES, TABLE)
+    Query,
+    /// Modifies rows and reports how many were affected (INSERT, UPDATE, DELETE, MERGE, COPY)
+    Dml,
+    /// Anything else, reported by its command tag (CREATE TABLE, GRANT, ...)
+    Other(String),
+}
+
+/// Effect of a transaction control statement on the open transaction
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransactionControl {
+    /// BEGIN or START TRANSACTION
+    Begin,
+    /// COMMIT, ROLLBACK, END, ABORT or PREPARE TRANSACTION
+    End,
+    /// SAVEPOINT with the savepoint name
+    Savepoint(String),
+    /// RELEASE SAVEPOINT with the savepoint name
+    Release(String),
+    /// ROLLBACK TO SAVEPOINT with the savepoint name
+    RollbackTo(String),
+}
+
+/// A statement that can destroy data or schema objects
+#[derive(Debug, Clone, PartialEq)]
+pub struct DestructiveStatement {
+    /// What the statement does, e.g. `DELETE without WHERE` or `DROP TABLE`
+    pub action: String,
+    /// Names of the objects it affects, as written in the statement
+    pub targets: Vec<String>,
+    /// Whether the targets are tables whose rows are removed or rewritten
+    pub affects_rows: bool,
+}
+
+/// Keywords between CREATE/ALTER/DROP and the object type that are not part of the command tag
+const CREATE_MODIFIERS: &[&str] = &[
+    "OR", "REPLACE", "TEMP", "TEMPORARY", "UNLOGGED", "UNIQUE", "GLOBAL", "LOCAL",
+    "RECURSIVE", "TRUSTED", "PROCEDURAL", "DEFAULT", "CONSTRAINT", "IF", "NOT", "EXISTS",
+];
+
+/// Classifies a statement by its leading keywords
+pub fn classify(query: &str) -> StatementKind {
+    let words = leading_words(query, 6);
+    let Some(first) = words.first() else {
+        return StatementKind::Other(String::new());
+    };
+
+    match first.as_str() {
+        "SELECT" | "WITH" | "VALUES" | "TABLE" => StatementKind::Query,
+        "INSERT" | "UPDATE" | "DELETE" | "MERGE" | "COPY" => StatementKind::Dml,
+        _ => StatementKind::Other(command_tag(&words)),
+    }
+}
+
+/// Whether running a statement can change data, which EXPLAIN ANALYZE does since
+/// it runs the statement: anything but a query, or a query with INSERT, UPDATE,
+/// DELETE or MERGE anywhere in it (so `FOR UPDATE` counts too)
+pub fn modifies_data(query: &str) -> bool {
+    match classify(query) {
+        StatementKind::Query => tokenize(query).iter().any(|token| {
+            token.kind == TokenKind::Keyword
+                && matches!(query[token.range.clone()].to_ascii_uppercase().as_str(), "INSERT" | "UPDATE" | "DELETE" | "MERGE")
+        }),
+        _ => true,
+    }
+}
+
+/// Gets the effect of a transaction control statement, or `None` for any other statement.
+/// Savepoint names are folded to lower case like unquoted identifiers.
+pub fn transaction_control(query: &str) -> Option<TransactionControl> {
+    let words = leading_words(query, 5);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let name = |words: &[&str]| {
+        let words = words.strip_prefix(&["SAVEPOINT"]).unwrap_or(words);
+        words.first().map(|name| name.to_ascii_lowercase())
+    };
+
+    match words.as_slice() {
+        ["BEGIN" | "START", ..] => Some(TransactionControl::Begin),
+        ["COMMIT" | "ROLLBACK", "PREPARED", ..] => None,
+        ["ROLLBACK" | "ABORT", rest @ ..] => {
+            let rest = rest.strip_prefix(&["WORK"]).or(rest.strip_prefix(&["TRANSACTION"])).unwrap_or(rest);
+            match rest {
+                ["TO", rest @ ..] => name(rest).map(TransactionControl::RollbackTo),
+                _ => Some(TransactionControl::End),
+            }
+        }
+        ["COMMIT" | "END", ..] | ["PREPARE", "TRANSACTION", ..] => Some(TransactionControl::End),
+        ["SAVEPOINT", rest @ ..] => name(rest).map(TransactionControl::Savepoint),
+        ["RELEASE", rest @ ..] => name(rest).map(TransactionControl::Release),
+        _ => None,
+    }
+}
+
+/// Whether a statement can run inside a transaction block opened for it. Transaction
+/// control statements and commands Postgres refuses to run in a block (VACUUM,
+/// CREATE DATABASE, ... CONCURRENTLY) are run as they are.
+pub fn runs_in_transaction(query: &str) -> bool {
+    if matches!(transaction_control(query), Some(TransactionControl::Begin | TransactionControl::End)) {
+        return false;
+    }
+
+    let words = leading_words(query, 5);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    match words.as_slice() {
+        [] | ["VACUUM", ..] | ["ALTER", "SYSTEM", ..] | [_, "PREPARED", ..] => false,
+        ["CREATE" | "DROP", "DATABASE" | "TABLESPACE" | "SUBSCRIPTION", ..] => false,
+        _ => !words.contains(&"CONCURRENTLY"),
+    }
+}
+
+/// Detects DELETE and UPDATE without a WHERE clause, TRUNCATE, DROP and ALTER,
+/// including in the common table expressions of a WITH query
+pub fn destructive(query: &str, driver: Driver) -> Option<DestructiveStatement> {
+    // Data-modifying CTEs run whether or not the main statement reads them
+    if let Some(statement) = cte_bodies(query).into_iter().find_map(|body| destructive(body, driver)) {
+        return Some(statement);
+    }
+
+    let tokens = top_level_tokens(skip_comments(query, driver), driver);
+    let upper: Vec<String> = tokens.iter().map(|token| token.to_ascii_uppercase()).collect();
+    let upper: Vec<&str> = upper.iter().map(String::as_str).collect();
+
+    // The main statement of a WITH query follows its common table expressions
+    let start = match upper.first() {
+        Some(&"WITH") => upper.iter().position(|word| matches!(*word, "SELECT" | "INSERT" | "UPDATE" | "DELETE" | "MERGE"))?,
+        _ => 0,
+    };
+    let (tokens, upper) = (&tokens[start..], &upper[start..]);
+    let has_where = upper.contains(&"WHERE");
+
+    let (action, rest, affects_rows) = match upper.first()? {
+        &"DELETE" if !has_where => ("DELETE without WHERE".to_string(), skip_keywords(upper, 1, &["FROM", "ONLY"]), true),
+        &"UPDATE" if !has_where => ("UPDATE without WHERE".to_string(), skip_keywords(upper, 1, &["ONLY"]), true),
+        &"TRUNCATE" => ("TRUNCATE TABLE".to_string(), skip_keywords(upper, 1, &["TABLE", "ONLY"]), true),
+        &"DROP" | &"ALTER" => {
+            let words: Vec<String> = upper.iter().take(6).map(|word| word.to_string()).collect();
+            let tag = command_tag(&words);
+            let object_words = tag.split(' ').count();
+            let skip = &["IF", "EXISTS", "ONLY", "CONCURRENTLY"];
+            (tag.clone(), skip_keywords(upper, object_words, skip), tag == "DROP TABLE")
+        }
+        _ => return None,
+    };
+
+    Some(DestructiveStatement {
+        action,
+        targets: object_names(&tokens[rest..]),
+        affects_rows,
+    })
+}
+
+/// Gets the table a query reads from when it selects from exactly one table,
+/// e.g. `public.users` for `SELECT * FROM public.users u WHERE ...`. Joins,
+/// comma-separated FROM lists, set operations, CTEs, subqueries and function
+/// calls give `None`.
+pub fn source_table(query: &str, driver: Driver) -> Option<String> {
+    let statement = skip_comments(query, driver);
+    let tokens = top_level_tokens(statement, driver);
+    let upper: Vec<String> = tokens.iter().map(|token| token.to_ascii_uppercase()).collect();
+    let upper: Vec<&str> = upper.iter().map(String::as_str).collect();
+
+    if upper.iter().any(|word| matches!(*word, "UNION" | "INTERSECT" | "EXCEPT")) {
+        return None;
+    }
+    let from = match *upper.first()? {
+        "TABLE" => 1,
+        "SELECT" => 1 + upper.iter().position(|word| *word == "FROM")?,
+        _ => return None,
+    };
+    let start = skip_keywords(&upper, from, &["ONLY"]);
+    if upper[from..].iter().any(|word| matches!(*word, "JOIN" | ",")) {
+        return None;
+    }
+
+    // Parentheses are not among the tokens, so look at the text after the FROM
+    // keyword for a subquery and after the name for a function call
+    let mut end = start;
+    while tokens.get(end + 1) == Some(&".") && end + 2 < tokens.len() {
+        end += 2;
+    }
+    let followed_by_parenthesis = |token: &str| {
+        let offset = token.as_ptr() as usize - statement.as_ptr() as usize + token.len();
+        statement[offset..].trim_start().starts_with('(')
+    };
+    if followed_by_parenthesis(tokens[start - 1]) || followed_by_parenthesis(tokens.get(end)?) {
+        return None;
+    }
+    object_names(&tokens[start..]).into_iter().next()
+}
+
+/// Gets the statements inside the common table expressions of a WITH query,
+/// e.g. `DELETE FROM t RETURNING *` for `WITH d AS (DELETE FROM t RETURNING *) SELECT ...`
+fn cte_bodies(query: &str) -> Vec<&str> {
+    let tokens: Vec<_> = tokenize(query).into_iter().filter(|token| token.kind != TokenKind::Comment).collect();
+    let text = |index: usize| &query[tokens[index].range.clone()];
+    if tokens.is_empty() || !text(0).eq_ignore_ascii_case("WITH") {
+        return Vec::new();
+    }
+
+    let mut bodies = Vec::new();
+    let mut depth = 0;
+    let mut body_start = None;
+    for index in 1..tokens.len() {
+        match text(index) {
+            "(" => {
+                let previous = text(index - 1).to_ascii_uppercase();
+                if depth == 0 && matches!(previous.as_str(), "AS" | "MATERIALIZED") {
+                    body_start = Some(tokens[index].range.end);
+                }
+                depth += 1;
+            }
+            ")" => {
+                depth -= 1;
+                if depth == 0 {
+                    if let Some(start) = body_start.take() {
+                        bodies.push(&query[start..tokens[index].range.start]);
+                    }
+                }
+            }
+            word if depth == 0 && tokens[index].kind == TokenKind::Keyword => {
+                // The main statement follows the last CTE
+                if matches!(word.to_ascii_uppercase().as_str(), "SELECT" | "INSERT" | "UPDATE" | "DELETE" | "MERGE" | "VALUES" | "TABLE") {
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    bodies
+}
+
+/// Gets the index of the first token from `start` that is not one of `keywords`
+fn skip_keywords(upper: &[&str], start: usize, keywords: &[&str]) -> usize {
+    start + upper[start.min(upper.len())..].iter().take_while(|word| keywords.contains(word)).count()
+}
+
+/// Reads a comma separated list of possibly qualified names, e.g. `a, public.b`
+fn object_names(tokens: &[&str]) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut i = 0;
+    while let Some(first) = tokens.get(i) {
+        let mut name = first.to_string();
+        i += 1;
+        while tokens.get(i) == Some(&".") && i + 1 < tokens.len() {
+            name.push('.');
+            name.push_str(tokens[i + 1]);
+            i += 2;
+        }
+        names.push(name);
+        if tokens.get(i) != Some(&",") {
+            break;
+        }
+        i += 1;
+    }
+    names
+}
+
+/// Builds the command tag Postgres reports for a utility statement, e.g.
+/// `CREATE UNIQUE INDEX ...` reports `CREATE INDEX`
+fn command_tag(words: &[String]) -> String {
+    let verb = words[0].as_str();
+    match verb {
+        "CREATE" | "ALTER" | "DROP" => {
+            let object: Vec<&str> = words[1..]
+                .iter()
+                .map(String::as_str)
+                .skip_while(|word| CREATE_MODIFIERS.contains(word))
+                .collect();
+            let length = match object.as_slice() {
+                ["FOREIGN", "DATA", ..] | ["TEXT", "SEARCH", ..] => 3,
+                ["MATERIALIZED" | "FOREIGN" | "EVENT" | "ACCESS" | "USER", ..] => 2,
+                ["OPERATOR", "CLASS" | "FAMILY", ..] => 2,
+                _ => 1,
+            };
+            let object = object[..length.min(object.len())].join(" ");
+            format!("{} {}", verb, object).trim_end().to_string()
+        }
+        "TRUNCATE" => "TRUNCATE TABLE".to_string(),
+        "REFRESH" => "REFRESH MATERIALIZED VIEW".to_string(),
+        "START" => "START TRANSACTION".to_string(),
+        "END" => "COMMIT".to_string(),
+        "ABORT" => "ROLLBACK".to_string(),
+        _ => verb.to_string(),
+    }
+}
+
+/// Gets up to `count` leading keywords in upper case, skipping comments
+fn leading_words(query: &str, count: usize) -> Vec<String> {
+    skip_comments(query, Driver::Postgres)
+        .trim_start_matches(|c: char| c == '(' || c.is_whitespace())
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .take(count)
+        .map(str::to_ascii_uppercase)
+        .collect()
+}
+
+/// Skips leading whitespace and comments
+pub(super) fn skip_comments(mut query: &str, driver: Driver) -> &str {
+    loop {
+        query = query.trim_start();
+        match skip_comment(query.as_bytes(), 0, driver) {
+            Some(end) => query = &query[end..],
+            None => return query,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("SELECT 1"), StatementKind::Query);
+        assert_eq!(classify("  -- comment\n/* block */ (select 1) UNION (SELECT 2)"), StatementKind::Query);
+        assert_eq!(classify("WITH x AS (SELECT 1) SELECT * FROM x"), StatementKind::Query);
+        assert_eq!(classify("-- SELECT\ndelete FROM users"), StatementKind::Dml);
+        assert_eq!(classify("UPDATE users SET name = 'a' RETURNING id"), StatementKind::Dml);
+
+        assert!(!modifies_data("SELECT 'delete' FROM users"));
+        assert!(modifies_data("WITH d AS (DELETE FROM users RETURNING id) SELECT count(*) FROM d"));
+        assert!(modifies_data("insert into users default values"));
+        assert!(modifies_data("CREATE TABLE t (id int)"));
+
+        let tag = |query| match classify(query) {
+            StatementKind::Other(tag) => tag,
+            kind => panic!("{:?} is not a utility statement", kind),
+        };
+        assert_eq!(tag("create table t (id int)"), "CREATE TABLE");
+        assert_eq!(tag("CREATE UNIQUE INDEX CONCURRENTLY i ON t (id)"), "CREATE INDEX");
+        assert_eq!(tag("CREATE OR REPLACE FUNCTION f() ..."), "CREATE FUNCTION");
+        assert_eq!(tag("create temp table t (id int)"), "CREATE TABLE");
+        assert_eq!(tag("DROP MATERIALIZED VIEW IF EXISTS v"), "DROP MATERIALIZED VIEW");
+        assert_eq!(tag("DROP TABLE IF EXISTS t"), "DROP TABLE");
+        assert_eq!(tag("ALTER INDEX i RENAME TO j"), "ALTER INDEX");
+        assert_eq!(tag("truncate users"), "TRUNCATE TABLE");
+        assert_eq!(tag("GRANT SELECT ON t TO bob"), "GRANT");
+    }
+
+    #[test]
+    fn test_destructive() {
+        let check = |query| destructive(query, Driver::Postgres).map(|statement| (statement.action, statement.targets));
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(check("DELETE FROM users"), Some(("DELETE without WHERE".to_string(), names(&["users"]))));
+        assert_eq!(check("DELETE FROM users WHERE id = 1"), None);
+        assert_eq!(
+            check("update only public.\"Users\" set name = (select 'x' where true)"),
+            Some(("UPDATE without WHERE".to_string(), names(&["public.\"Users\""])))
+        );
+        assert_eq!(
+            check("WITH old AS (SELECT 1 WHERE false) DELETE FROM events"),
+            Some(("DELETE without WHERE".to_string(), names(&["events"])))
+        );
+        assert_eq!(
+            check("WITH d AS (DELETE FROM t RETURNING *) SELECT * FROM d"),
+            Some(("DELETE without WHERE".to_string(), names(&["t"])))
+        );
+        assert_eq!(
+            check("with recursive a (n) as (select 1), b as not materialized (update s.t set x = 1 returning x) table b"),
+            Some(("UPDATE without WHERE".to_string(), names(&["s.t"])))
+        );
+        assert_eq!(check("WITH d AS (DELETE FROM t WHERE id = 1 RETURNING *) SELECT * FROM d"), None);
+        assert_eq!(
+            check("TRUNCATE TABLE a, b CASCADE"),
+            Some(("TRUNCATE TABLE".to_string(), names(&["a", "b"])))
+        );
+        assert_eq!(
+            check("drop table if exists a, s.b cascade"),
+            Some(("DROP TABLE".to_string(), names(&["a", "s.b"])))
+        );
+        assert_eq!(
+            check("ALTER TABLE ONLY users ADD COLUMN age int"),
+            Some(("ALTER TABLE".to_string(), names(&["users"])))
+        );
+        assert_eq!(check("SELECT * FROM users"), None);
+        assert_eq!(check("INSERT INTO users DEFAULT VALUES"), None);
+
+        let check = |query| destructive(query, Driver::Mysql).map(|statement| (statement.action, statement.targets));
+        assert_eq!(
+            check("# where\nUPDATE `Users` SET name = \"where\""),
+            Some(("UPDATE without WHERE".to_string(), names(&["`Users`"])))
+        );
+    }
+
+    #[test]
+    fn test_source_table() {
+        assert_eq!(source_table("SELECT * FROM users", Driver::Postgres), Some("users".to_string()));
+        assert_eq!(
+            source_table("select id, (select 1 from b) from only public.\"Users\" u where id in (select id from c)", Driver::Postgres),
+            Some("public.\"Users\"".to_string())
+        );
+        assert_eq!(source_table("TABLE events", Driver::Postgres), Some("events".to_string()));
+        assert_eq!(source_table("SELECT * FROM a JOIN b ON a.id = b.id", Driver::Postgres), None);
+        assert_eq!(source_table("SELECT * FROM a, b", Driver::Postgres), None);
+        assert_eq!(source_table("SELECT 1 UNION SELECT id FROM a", Driver::Postgres), None);
+        assert_eq!(source_table("WITH x AS (SELECT 1) SELECT * FROM x", Driver::Postgres), None);
+        assert_eq!(source_table("SELECT now()", Driver::Postgres), None);
+        assert_eq!(source_table("SELECT * FROM (SELECT * FROM users) x", Driver::Postgres), None);
+        assert_eq!(source_table("SELECT * FROM only (SELECT 1) x", Driver::Postgres), None);
+        assert_eq!(source_table("SELECT * FROM generate_series(1, 3) g", Driver::Postgres), None);
+        assert_eq!(source_table("SELECT * FROM pg_catalog.pg_get_keywords ()", Driver::Postgres), None);
+        assert_eq!(source_table("SELECT * FROM s.t (a, b)", Driver::Postgres), None);
+        assert_eq!(source_table("SELECT * FROM `shop`.`Order` -- latest\n", Driver::Mysql), Some("`shop`.`Order`".to_string()));
+    }
+
+    #[test]
+    fn test_transaction_control() {
+        use TransactionControl::*;
+
+        assert_eq!(transaction_control("begin"), Some(Begin));
+        assert_eq!(transaction_control("START TRANSACTION ISOLATION LEVEL SERIALIZABLE"), Some(Begin));
+        assert_eq!(transaction_control("ROLLBACK"), Some(End));
+        assert_eq!(transaction_control("end work"), Some(End));
+        assert_eq!(transaction_control("SAVEPOINT Before_Delete"), Some(Savepoint("before_delete".to_string())));
+        assert_eq!(transaction_control("RELEASE SAVEPOINT a"), Some(Release("a".to_string())));
+        assert_eq!(transaction_control("ROLLBACK WORK TO SAVEPOINT a"), Some(RollbackTo("a".to_string())));
+        assert_eq!(transaction_control("rollback to a"), Some(RollbackTo("a".to_string())));
+        assert_eq!(transaction_control("ROLLBACK PREPARED 'x'"), None);
+        assert_eq!(transaction_control("SELECT 1"), None);
+
+        assert!(runs_in_transaction("UPDATE users SET name = 'a'"));
+        assert!(runs_in_transaction("SAVEPOINT a"));
+        assert!(!runs_in_transaction("COMMIT"));
+        assert!(!runs_in_transaction("VACUUM ANALYZE users"));
+        assert!(!runs_in_transaction("CREATE INDEX CONCURRENTLY i ON t (id)"));
+        assert!(!runs_in_transaction("create database test"));
+    }
+}
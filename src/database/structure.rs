@@ -0,0 +1,439 @@
+use anyhow::{anyhow, Result};
+use sqlx::Row;
+
+use super::postgres::PostgresBackend;
+use super::{quote_identifier, Driver};
+
+/// The definition of a table, view or materialized view as read from the catalog
+#[derive(Debug, Clone, Default)]
+pub struct TableStructure {
+    /// Driver of the database, whose quoting rules names are written with
+    pub driver: Driver,
+    /// Schema name
+    pub schema: String,
+    /// Relation name
+    pub name: String,
+    /// Kind of relation, e.g. `table` or `partitioned table`
+    pub kind: String,
+    /// Comment on the relation
+    pub comment: Option<String>,
+    /// Whether the table is unlogged
+    pub unlogged: bool,
+    /// Storage parameters, e.g. `fillfactor=70`
+    pub options: Vec<String>,
+    /// Partition key of a partitioned table, e.g. `RANGE (created)`
+    pub partition_key: Option<String>,
+    /// Parent of a partition
+    pub parent: Option<String>,
+    /// Partition bound of a partition, e.g. `FOR VALUES IN (1)` or `DEFAULT`
+    pub partition_bound: Option<String>,
+    /// Foreign server of a foreign table
+    pub server: Option<String>,
+    /// Query of a view or materialized view
+    pub view_definition: Option<String>,
+    /// Columns in table order
+    pub columns: Vec<ColumnDetails>,
+    /// Constraints other than NOT NULL
+    pub constraints: Vec<ConstraintDetails>,
+    /// Indexes, including those backing constraints
+    pub indexes: Vec<IndexDetails>,
+    /// Triggers created by users
+    pub triggers: Vec<TriggerDetails>,
+    /// Partitions of a partitioned table
+    pub partitions: Vec<PartitionDetails>,
+    /// DDL as the server reports it, e.g. from MySQL's `SHOW CREATE TABLE`, used
+    /// instead of reconstructing it
+    pub reported_ddl: Option<String>,
+}
+
+/// A column with everything that appears in its definition
+#[derive(Debug, Clone, Default)]
+pub struct ColumnDetails {
+    /// Column name
+    pub name: String,
+    /// Formatted column type
+    pub data_type: String,
+    /// Whether the column accepts NULL
+    pub nullable: bool,
+    /// Default expression
+    pub default: Option<String>,
+    /// Identity or generation clause, e.g. `GENERATED ALWAYS AS IDENTITY`
+    pub generated: Option<String>,
+    /// Collation, when it differs from the type's
+    pub collation: Option<String>,
+    /// Comment on the column
+    pub comment: Option<String>,
+}
+
+/// A primary key, foreign key, unique, check or exclusion constraint
+#[derive(Debug, Clone, Default)]
+pub struct ConstraintDetails {
+    /// Constraint name
+    pub name: String,
+    /// Definition as written after the constraint name, e.g. `PRIMARY KEY (id)`
+    pub definition: String,
+    /// Whether the constraint comes from a parent table
+    pub inherited: bool,
+}
+
+/// An index of a table or materialized view
+#[derive(Debug, Clone, Default)]
+pub struct IndexDetails {
+    /// Index name
+    pub name: String,
+    /// The `CREATE INDEX` statement
+    pub definition: String,
+    /// Whether the index is created by a constraint
+    pub constraint: bool,
+    /// Whether the index is a partition of an index on the parent table
+    pub inherited: bool,
+    /// Whether the index can be used, false after a failed concurrent build
+    pub valid: bool,
+}
+
+/// A trigger on a table or view
+#[derive(Debug, Clone, Default)]
+pub struct TriggerDetails {
+    /// Trigger name
+    pub name: String,
+    /// The `CREATE TRIGGER` statement
+    pub definition: String,
+    /// Whether the trigger fires
+    pub enabled: bool,
+    /// Whether the trigger is cloned from a trigger on the parent table
+    pub inherited: bool,
+}
+
+/// A partition of a partitioned table
+#[derive(Debug, Clone, Default)]
+pub struct PartitionDetails {
+    /// Partition name, schema-qualified when not on the search path
+    pub name: String,
+    /// Partition bound, e.g. `FOR VALUES FROM (1) TO (10)`
+    pub bound: String,
+}
+
+impl TableStructure {
+    /// Gets the quoted, schema-qualified relation name
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", quote_identifier(&self.schema, self.driver), quote_identifier(&self.name, self.driver))
+    }
+
+    /// Gets the DDL the server reported, or else reconstructs the DDL of the relation:
+    /// the `CREATE` statement followed by its indexes, triggers and comments. Constraints, indexes and triggers that
+    /// come from a parent table are left out, since attaching creates them.
+    pub fn ddl(&self) -> String {
+        if let Some(ddl) = &self.reported_ddl {
+            return ddl.clone();
+        }
+        let name = self.qualified_name();
+        let mut statements = Vec::new();
+
+        let object = if let Some(query) = &self.view_definition {
+            let object = if self.kind == "materialized view" { "MATERIALIZED VIEW" } else { "VIEW" };
+            statements.push(format!("CREATE {} {} AS\n{};", object, name, query.trim_end().trim_end_matches(';')));
+            object
+        } else {
+            let object = if self.server.is_some() { "FOREIGN TABLE" } else { "TABLE" };
+            let unlogged = if self.unlogged { "UNLOGGED " } else { "" };
+            let constraints = self
+                .constraints
+                .iter()
+                .filter(|constraint| !constraint.inherited)
+                .map(|constraint| format!("CONSTRAINT {} {}", quote_identifier(&constraint.name, self.driver), constraint.definition));
+
+            let mut create = format!("CREATE {}{} {}", unlogged, object, name);
+            // Partitions take their columns from the parent
+            let elements: Vec<String> = match &self.parent {
+                Some(parent) => {
+                    create.push_str(&format!(" PARTITION OF {}", parent));
+                    constraints.collect()
+                }
+                None => self.columns.iter().map(|column| column_definition(column, self.driver)).chain(constraints).collect(),
+            };
+            if !elements.is_empty() || self.parent.is_none() {
+                create.push_str(&format!(" (\n    {}\n)", elements.join(",\n    ")));
+            }
+            if let Some(bound) = &self.partition_bound {
+                create.push_str(&format!("\n{}", bound));
+            }
+            if let Some(key) = &self.partition_key {
+                create.push_str(&format!("\nPARTITION BY {}", key));
+            }
+            if let Some(server) = &self.server {
+                create.push_str(&format!("\nSERVER {}", quote_identifier(server, self.driver)));
+            }
+            if !self.options.is_empty() {
+                create.push_str(&format!("\nWITH ({})", self.options.join(", ")));
+            }
+            create.push(';');
+            statements.push(create);
+            object
+        };
+
+        let indexes = self.indexes.iter().filter(|index| !index.constraint && !index.inherited);
+        statements.extend(indexes.map(|index| format!("{};", index.definition)));
+        let triggers = self.triggers.iter().filter(|trigger| !trigger.inherited);
+        for trigger in triggers {
+            statements.push(format!("{};", trigger.definition));
+            if !trigger.enabled {
+                // ALTER VIEW cannot disable triggers, ALTER TABLE can
+                let altered = if self.view_definition.is_some() { "TABLE" } else { object };
+                statements.push(format!("ALTER {} {} DISABLE TRIGGER {};", altered, name, quote_identifier(&trigger.name, self.driver)));
+            }
+        }
+
+        if let Some(comment) = &self.comment {
+            statements.push(format!("COMMENT ON {} {} IS {};", object, name, quote_literal(comment)));
+        }
+        for column in &self.columns {
+            if let Some(comment) = &column.comment {
+                statements.push(format!(
+                    "COMMENT ON COLUMN {}.{} IS {};",
+                    name,
+                    quote_identifier(&column.name, self.driver),
+                    quote_literal(comment)
+                ));
+            }
+        }
+        statements.join("\n\n")
+    }
+}
+
+/// Formats a column as written in `CREATE TABLE`
+fn column_definition(column: &ColumnDetails, driver: Driver) -> String {
+    let mut definition = format!("{} {}", quote_identifier(&column.name, driver), column.data_type);
+    if let Some(collation) = &column.collation {
+        definition.push_str(&format!(" COLLATE {}", quote_identifier(collation, driver)));
+    }
+    if let Some(generated) = &column.generated {
+        definition.push_str(&format!(" {}", generated));
+    } else if let Some(default) = &column.default {
+        definition.push_str(&format!(" DEFAULT {}", default));
+    }
+    if !column.nullable {
+        definition.push_str(" NOT NULL");
+    }
+    definition
+}
+
+/// Quotes text as an SQL string literal
+fn quote_literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+impl PostgresBackend {
+    /// Reads the definition of a table, view or materialized view, given by a
+    /// possibly schema-qualified name as accepted by `to_regclass`
+    pub async fn table_structure(&self, table: &str) -> Result<TableStructure> {
+        let row = sqlx::query(
+            "SELECT n.nspname AS schema, c.relname AS name, \
+             CASE c.relkind \
+                 WHEN 'r' THEN 'table' WHEN 'p' THEN 'partitioned table' \
+                 WHEN 'f' THEN 'foreign table' WHEN 'v' THEN 'view' \
+                 WHEN 'm' THEN 'materialized view' ELSE 'relation' END AS kind, \
+             obj_description(c.oid, 'pg_class') AS comment, \
+             c.relpersistence = 'u' AS unlogged, \
+             coalesce(c.reloptions, '{}') AS options, \
+             CASE WHEN c.relkind = 'p' THEN pg_get_partkeydef(c.oid) END AS partition_key, \
+             CASE WHEN c.relispartition THEN \
+                 (SELECT i.inhparent::regclass::text FROM pg_inherits i WHERE i.inhrelid = c.oid) END AS parent, \
+             CASE WHEN c.relispartition THEN pg_get_expr(c.relpartbound, c.oid) END AS partition_bound, \
+             (SELECT s.srvname::text FROM pg_foreign_table ft \
+                 JOIN pg_foreign_server s ON s.oid = ft.ftserver WHERE ft.ftrelid = c.oid) AS server, \
+             CASE WHEN c.relkind IN ('v', 'm') THEN pg_get_viewdef(c.oid, true) END AS view_definition \
+             FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+             WHERE c.oid = to_regclass($1) AND c.relkind IN ('r', 'p', 'f', 'v', 'm');",
+        )
+            .bind(table)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| anyhow!("No table or view named {}", table))?;
+
+        let mut structure = TableStructure {
+            driver: Driver::Postgres,
+            schema: row.get("schema"),
+            name: row.get("name"),
+            kind: row.get("kind"),
+            comment: row.get("comment"),
+            unlogged: row.get("unlogged"),
+            options: row.get("options"),
+            partition_key: row.get("partition_key"),
+            parent: row.get("parent"),
+            partition_bound: row.get("partition_bound"),
+            server: row.get("server"),
+            view_definition: row.get("view_definition"),
+            ..Default::default()
+        };
+
+        structure.columns = sqlx::query(
+            "SELECT a.attname::text AS name, format_type(a.atttypid, a.atttypmod) AS data_type, \
+             NOT a.attnotnull AS nullable, \
+             CASE WHEN a.attgenerated = '' THEN pg_get_expr(d.adbin, d.adrelid) END AS default_value, \
+             CASE WHEN a.attidentity = 'a' THEN 'GENERATED ALWAYS AS IDENTITY' \
+                 WHEN a.attidentity = 'd' THEN 'GENERATED BY DEFAULT AS IDENTITY' \
+                 WHEN a.attgenerated = 's' THEN \
+                     'GENERATED ALWAYS AS (' || pg_get_expr(d.adbin, d.adrelid) || ') STORED' END AS generated, \
+             CASE WHEN a.attcollation <> t.typcollation THEN co.collname::text END AS collation, \
+             col_description(a.attrelid, a.attnum) AS comment \
+             FROM pg_attribute a \
+             JOIN pg_type t ON t.oid = a.atttypid \
+             LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum \
+             LEFT JOIN pg_collation co ON co.oid = a.attcollation \
+             WHERE a.attrelid = to_regclass($1) AND a.attnum > 0 AND NOT a.attisdropped \
+             ORDER BY a.attnum;",
+        )
+            .bind(table)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| ColumnDetails {
+                name: row.get("name"),
+                data_type: row.get("data_type"),
+                nullable: row.get("nullable"),
+                default: row.get("default_value"),
+                generated: row.get("generated"),
+                collation: row.get("collation"),
+                comment: row.get("comment"),
+            })
+            .collect();
+
+        structure.constraints = sqlx::query(
+            "SELECT con.conname::text AS name, pg_get_constraintdef(con.oid, true) AS definition, \
+             NOT con.conislocal AS inherited \
+             FROM pg_constraint con \
+             WHERE con.conrelid = to_regclass($1) AND con.contype IN ('p', 'u', 'f', 'c', 'x') \
+             ORDER BY array_position(ARRAY['p', 'u', 'f', 'c', 'x'], con.contype::text), con.conname;",
+        )
+            .bind(table)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| ConstraintDetails {
+                name: row.get("name"),
+                definition: row.get("definition"),
+                inherited: row.get("inherited"),
+            })
+            .collect();
+
+        structure.indexes = sqlx::query(
+            "SELECT c.relname::text AS name, pg_get_indexdef(i.indexrelid) AS definition, \
+             EXISTS (SELECT 1 FROM pg_constraint con \
+                 WHERE con.conindid = i.indexrelid AND con.conrelid = i.indrelid) AS constraint_index, \
+             c.relispartition AS inherited, i.indisvalid AS valid \
+             FROM pg_index i JOIN pg_class c ON c.oid = i.indexrelid \
+             WHERE i.indrelid = to_regclass($1) \
+             ORDER BY NOT i.indisprimary, c.relname;",
+        )
+            .bind(table)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| IndexDetails {
+                name: row.get("name"),
+                definition: row.get("definition"),
+                constraint: row.get("constraint_index"),
+                inherited: row.get("inherited"),
+                valid: row.get("valid"),
+            })
+            .collect();
+
+        structure.triggers = sqlx::query(
+            "SELECT t.tgname::text AS name, pg_get_triggerdef(t.oid) AS definition, \
+             t.tgenabled <> 'D' AS enabled, t.tgparentid <> 0 AS inherited \
+             FROM pg_trigger t \
+             WHERE t.tgrelid = to_regclass($1) AND NOT t.tgisinternal \
+             ORDER BY t.tgname;",
+        )
+            .bind(table)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| TriggerDetails {
+                name: row.get("name"),
+                definition: row.get("definition"),
+                enabled: row.get("enabled"),
+                inherited: row.get("inherited"),
+            })
+            .collect();
+
+        structure.partitions = sqlx::query(
+            "SELECT i.inhrelid::regclass::text AS name, pg_get_expr(c.relpartbound, c.oid) AS bound \
+             FROM pg_inherits i JOIN pg_class c ON c.oid = i.inhrelid \
+             WHERE i.inhparent = to_regclass($1) AND c.relispartition \
+             ORDER BY pg_get_expr(c.relpartbound, c.oid) = 'DEFAULT', 1;",
+        )
+            .bind(table)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| PartitionDetails {
+                name: row.get("name"),
+                bound: row.get("bound"),
+            })
+            .collect();
+
+        Ok(structure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Executor;
+    use crate::database::DatabaseConfig;
+
+    #[tokio::test]
+    async fn test_table_structure() {
+        let db = PostgresBackend::connect(&DatabaseConfig::new()).await.unwrap();
+        db.pool.execute(
+            "DROP TABLE IF EXISTS ferrum_test_structure, ferrum_test_events;
+             CREATE TABLE ferrum_test_structure (
+                 id int GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+                 \"Name\" text COLLATE \"C\" NOT NULL DEFAULT 'x',
+                 total numeric CHECK (total > 0),
+                 twice numeric GENERATED ALWAYS AS (total * 2) STORED
+             ) WITH (fillfactor=70);
+             CREATE INDEX ferrum_test_structure_total ON ferrum_test_structure (total);
+             COMMENT ON COLUMN ferrum_test_structure.total IS 'it''s the total';
+             CREATE TABLE ferrum_test_events (day date NOT NULL, kind int) PARTITION BY RANGE (day);
+             CREATE TABLE ferrum_test_events_2024 PARTITION OF ferrum_test_events
+                 FOR VALUES FROM ('2024-01-01') TO ('2025-01-01');",
+        )
+            .await
+            .unwrap();
+
+        let structure = db.table_structure("ferrum_test_structure").await.unwrap();
+        assert_eq!(structure.kind, "table");
+        assert_eq!(structure.columns[1].collation.as_deref(), Some("C"));
+        assert_eq!(structure.indexes.len(), 2);
+        assert_eq!(
+            structure.ddl(),
+            "CREATE TABLE public.ferrum_test_structure (\n    \
+                 id integer GENERATED ALWAYS AS IDENTITY NOT NULL,\n    \
+                 \"Name\" text COLLATE \"C\" DEFAULT 'x'::text NOT NULL,\n    \
+                 total numeric,\n    \
+                 twice numeric GENERATED ALWAYS AS ((total * (2)::numeric)) STORED,\n    \
+                 CONSTRAINT ferrum_test_structure_pkey PRIMARY KEY (id),\n    \
+                 CONSTRAINT ferrum_test_structure_total_check CHECK (total > 0::numeric)\n\
+             )\nWITH (fillfactor=70);\n\n\
+             CREATE INDEX ferrum_test_structure_total ON public.ferrum_test_structure USING btree (total);\n\n\
+             COMMENT ON COLUMN public.ferrum_test_structure.total IS 'it''s the total';"
+        );
+
+        let events = db.table_structure("ferrum_test_events").await.unwrap();
+        assert_eq!(events.partition_key.as_deref(), Some("RANGE (day)"));
+        assert_eq!(events.partitions[0].name, "ferrum_test_events_2024");
+        let partition = db.table_structure("ferrum_test_events_2024").await.unwrap();
+        assert_eq!(
+            partition.ddl(),
+            "CREATE TABLE public.ferrum_test_events_2024 PARTITION OF ferrum_test_events\n\
+             FOR VALUES FROM ('2024-01-01') TO ('2025-01-01');"
+        );
+        assert!(db.table_structure("ferrum_test_missing").await.is_err());
+
+        db.pool.execute("DROP TABLE ferrum_test_structure, ferrum_test_events;")
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,288 @@
+use std::ops::Range;
+
+use super::backend::Driver;
+use super::splitter::{dollar_tag, find, identifier_quote, is_escape_string, is_identifier_byte, skip_block_comment, skip_quoted};
+
+/// Kinds of tokens in Postgres SQL
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    /// A key word such as SELECT or VARCHAR
+    Keyword,
+    /// An unquoted identifier
+    Identifier,
+    /// A double-quoted identifier
+    QuotedIdentifier,
+    /// A string literal, including E'', B'', X'' and U&'' strings
+    String,
+    /// A dollar-quoted string literal
+    DollarString,
+    /// A numeric literal
+    Number,
+    /// A `--` or `/* */` comment
+    Comment,
+    /// An operator such as `=`, `<>` or `::`
+    Operator,
+    /// A positional parameter such as `$1`
+    Parameter,
+    /// Parentheses, brackets, commas, semicolons, dots and anything unrecognized
+    Punctuation,
+}
+
+/// A token with its byte range in the text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    /// The kind of token
+    pub kind: TokenKind,
+    /// Byte range of the token in the text
+    pub range: Range<usize>,
+}
+
+/// Key words highlighted and completed in the editor, sorted for binary search
+pub const KEYWORDS: &[&str] = &[
+    "ABORT", "ADD", "ALL", "ALTER", "ALWAYS", "ANALYZE", "AND", "ANY", "ARRAY", "AS", "ASC",
+    "ASYMMETRIC", "AUTHORIZATION", "BEGIN", "BETWEEN", "BIGINT", "BIGSERIAL", "BOOLEAN", "BOTH", "BY",
+    "CASCADE", "CASE", "CAST", "CHAR", "CHARACTER", "CHECK", "CHECKPOINT", "CLOSE", "CLUSTER",
+    "COALESCE", "COLLATE", "COLUMN", "COMMENT", "COMMIT", "CONCURRENTLY", "CONFLICT", "CONSTRAINT",
+    "COPY", "CREATE", "CROSS", "CUBE", "CURRENT", "CURRENT_DATE", "CURRENT_TIME", "CURRENT_TIMESTAMP",
+    "CURRENT_USER", "CURSOR", "DATABASE", "DEALLOCATE", "DECIMAL", "DECLARE", "DEFAULT", "DEFERRABLE",
+    "DEFERRED", "DELETE", "DESC", "DISCARD", "DISTINCT", "DO", "DOUBLE", "DROP", "ELSE", "END",
+    "ESCAPE", "EXCEPT", "EXCLUDE", "EXECUTE", "EXISTS", "EXPLAIN", "EXTENSION", "FALSE", "FETCH",
+    "FILTER", "FIRST", "FOLLOWING", "FOR", "FOREIGN", "FROM", "FULL", "FUNCTION", "GENERATED", "GRANT",
+    "GROUP", "GROUPING", "HAVING", "IF", "ILIKE", "IMMEDIATE", "IN", "INDEX", "INHERITS", "INITIALLY",
+    "INNER", "INSERT", "INT", "INTEGER", "INTERSECT", "INTERVAL", "INTO", "IS", "ISNULL", "JOIN",
+    "JSONB", "KEY", "LANGUAGE", "LAST", "LATERAL", "LEADING", "LEFT", "LIKE", "LIMIT", "LISTEN", "LOCAL",
+    "LOCK", "MATERIALIZED", "MERGE", "NATURAL", "NO", "NOT", "NOTHING", "NOTIFY", "NOTNULL", "NOWAIT",
+    "NULL", "NULLIF", "NULLS", "NUMERIC", "OF", "OFFSET", "ON", "ONLY", "OR", "ORDER", "OUTER", "OVER",
+    "OWNER", "PARTITION", "PLACING", "POLICY", "PRECEDING", "PRECISION", "PREPARE", "PRIMARY",
+    "PROCEDURE", "RANGE", "REAL", "RECURSIVE", "REFERENCES", "REFRESH", "REINDEX", "RELEASE", "RENAME",
+    "REPLACE", "RESET", "RESTRICT", "RETURNING", "RETURNS", "REVOKE", "RIGHT", "ROLE", "ROLLBACK",
+    "ROLLUP", "ROW", "ROWS", "SAVEPOINT", "SCHEMA", "SELECT", "SEQUENCE", "SERIAL", "SESSION_USER",
+    "SET", "SETS", "SHOW", "SIMILAR", "SKIP", "SMALLINT", "SOME", "START", "SYMMETRIC", "TABLE",
+    "TABLESAMPLE", "TEMP", "TEMPORARY", "THEN", "TIES", "TIMESTAMP", "TIMESTAMPTZ", "TO", "TRAILING",
+    "TRANSACTION", "TRIGGER", "TRUE", "TRUNCATE", "TYPE", "UNBOUNDED", "UNION", "UNIQUE", "UNLISTEN",
+    "UNLOGGED", "UPDATE", "USER", "USING", "VACUUM", "VALUES", "VARCHAR", "VARIADIC", "VARYING", "VERBOSE",
+    "VIEW", "WHEN", "WHERE", "WINDOW", "WITH", "WITHIN", "WITHOUT", "WORK", "ZONE",
+];
+
+/// Characters that make up operators
+const OPERATOR_CHARS: &[u8] = b"+-*/<>=~!@#%^&|`?:";
+
+/// Whether a word is a highlighted key word, ignoring case
+pub fn is_keyword(word: &str) -> bool {
+    KEYWORDS.binary_search(&word.to_ascii_uppercase().as_str()).is_ok()
+}
+
+/// Quotes a name unless it can be written as an unquoted identifier that is not a key word,
+/// with backticks for MySQL and double quotes otherwise
+pub fn quote_identifier(name: &str, driver: Driver) -> String {
+    let plain = name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '$')
+        && !is_keyword(name);
+    if plain {
+        name.to_string()
+    } else {
+        let quote = char::from(identifier_quote(driver));
+        format!("{}{}{}", quote, name.replace(quote, &quote.to_string().repeat(2)), quote)
+    }
+}
+
+/// Splits a possibly qualified name as written in a query, e.g. `public."Order"`,
+/// into its schema and name. Double quotes and MySQL's backticks are removed;
+/// unquoted parts are kept as written.
+pub fn split_qualified_name(name: &str) -> (Option<String>, String) {
+    let mut parts = vec![String::new()];
+    let mut quote = None;
+    let mut chars = name.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            // A doubled quote inside quotes stands for the quote itself
+            (Some(q), _) if c == q && chars.peek() == Some(&q) => {
+                chars.next();
+                parts.last_mut().unwrap().push(c);
+            }
+            (Some(q), _) if c == q => quote = None,
+            (None, '"' | '`') => quote = Some(c),
+            (None, '.') => parts.push(String::new()),
+            _ => parts.last_mut().unwrap().push(c),
+        }
+    }
+
+    let name = parts.pop().unwrap_or_default();
+    (parts.pop(), name)
+}
+
+/// Splits SQL text into tokens, skipping whitespace. Unterminated strings and
+/// comments extend to the end of the text.
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start = i;
+        let byte = bytes[i];
+        let next = bytes.get(i + 1).copied();
+
+        let kind = match byte {
+            byte if byte.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'-' if next == Some(b'-') => {
+                i = bytes[i..].iter().position(|b| *b == b'\n').map_or(bytes.len(), |end| i + end);
+                TokenKind::Comment
+            }
+            b'/' if next == Some(b'*') => {
+                i = skip_block_comment(bytes, i);
+                TokenKind::Comment
+            }
+            b'\'' => {
+                i = skip_quoted(bytes, i, b'\'', false);
+                TokenKind::String
+            }
+            b'"' => {
+                i = skip_quoted(bytes, i, b'"', false);
+                TokenKind::QuotedIdentifier
+            }
+            b'$' if next.is_some_and(|b| b.is_ascii_digit()) => {
+                i += 1 + bytes[i + 1..].iter().take_while(|b| b.is_ascii_digit()).count();
+                TokenKind::Parameter
+            }
+            b'$' => match dollar_tag(bytes, i) {
+                Some(tag) => {
+                    let body = i + tag.len();
+                    i = find(&bytes[body..], tag).map_or(bytes.len(), |end| body + end + tag.len());
+                    TokenKind::DollarString
+                }
+                None => {
+                    i += 1;
+                    TokenKind::Punctuation
+                }
+            },
+            byte if byte.is_ascii_digit() || (byte == b'.' && next.is_some_and(|b| b.is_ascii_digit())) => {
+                i = skip_number(bytes, i);
+                TokenKind::Number
+            }
+            byte if is_identifier_byte(byte) => {
+                i += bytes[i..]
+                    .iter()
+                    .take_while(|b| is_identifier_byte(**b) || **b == b'$')
+                    .count();
+                match (&text[start..i], bytes.get(i)) {
+                    // Prefixed string literals: E'', B'', X'', N'' and U&''
+                    ("E" | "e" | "B" | "b" | "X" | "x" | "N" | "n", Some(b'\'')) => {
+                        i = skip_quoted(bytes, i, b'\'', is_escape_string(bytes, i));
+                        TokenKind::String
+                    }
+                    ("U" | "u", Some(b'&')) if matches!(bytes.get(i + 1), Some(b'\'' | b'"')) => {
+                        let quote = bytes[i + 1];
+                        i = skip_quoted(bytes, i + 1, quote, false);
+                        if quote == b'"' {
+                            TokenKind::QuotedIdentifier
+                        } else {
+                            TokenKind::String
+                        }
+                    }
+                    (word, _) if is_keyword(word) => TokenKind::Keyword,
+                    _ => TokenKind::Identifier,
+                }
+            }
+            byte if OPERATOR_CHARS.contains(&byte) => {
+                i += 1;
+                while i < bytes.len()
+                    && OPERATOR_CHARS.contains(&bytes[i])
+                    && !bytes[i..].starts_with(b"--")
+                    && !bytes[i..].starts_with(b"/*")
+                {
+                    i += 1;
+                }
+                TokenKind::Operator
+            }
+            _ => {
+                // Step over a whole character so ranges stay on character boundaries
+                i += text[i..].chars().next().map_or(1, char::len_utf8);
+                TokenKind::Punctuation
+            }
+        };
+
+        tokens.push(Token { kind, range: start..i });
+    }
+    tokens
+}
+
+/// Skips a numeric literal such as `42`, `1_000`, `3.14`, `.5` or `6.02e23`
+fn skip_number(bytes: &[u8], start: usize) -> usize {
+    let digits = |i: usize| i + bytes[i..].iter().take_while(|b| b.is_ascii_digit() || **b == b'_').count();
+
+    let mut i = digits(start);
+    if bytes.get(i) == Some(&b'.') && bytes.get(i + 1) != Some(&b'.') {
+        i = digits(i + 1);
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(i + 1), Some(b'+' | b'-')));
+        if bytes.get(i + 1 + sign).is_some_and(|b| b.is_ascii_digit()) {
+            i = digits(i + 1 + sign);
+        }
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let text = "SELECT u.\"Name\", 1.5e3 FROM users u -- note\nWHERE id = $1 AND s <> E'it\\'s' /* a /* b */ */ AND f::text = $x$;$x$;";
+        let tokens: Vec<(TokenKind, &str)> = tokenize(text)
+            .into_iter()
+            .map(|token| (token.kind, &text[token.range]))
+            .collect();
+
+        use TokenKind::*;
+        assert_eq!(
+            tokens,
+            vec![
+                (Keyword, "SELECT"),
+                (Identifier, "u"),
+                (Punctuation, "."),
+                (QuotedIdentifier, "\"Name\""),
+                (Punctuation, ","),
+                (Number, "1.5e3"),
+                (Keyword, "FROM"),
+                (Identifier, "users"),
+                (Identifier, "u"),
+                (Comment, "-- note"),
+                (Keyword, "WHERE"),
+                (Identifier, "id"),
+                (Operator, "="),
+                (Parameter, "$1"),
+                (Keyword, "AND"),
+                (Identifier, "s"),
+                (Operator, "<>"),
+                (String, "E'it\\'s'"),
+                (Comment, "/* a /* b */ */"),
+                (Keyword, "AND"),
+                (Identifier, "f"),
+                (Operator, "::"),
+                (Identifier, "text"),
+                (Operator, "="),
+                (DollarString, "$x$;$x$"),
+                (Punctuation, ";"),
+            ]
+        );
+        assert!(KEYWORDS.windows(2).all(|pair| pair[0] < pair[1]));
+
+        assert_eq!(split_qualified_name("users"), (None, "users".to_string()));
+        assert_eq!(
+            split_qualified_name("public.\"Order \"\"A\"\".b\""),
+            (Some("public".to_string()), "Order \"A\".b".to_string())
+        );
+        assert_eq!(split_qualified_name("`shop`.`items`"), (Some("shop".to_string()), "items".to_string()));
+
+        assert_eq!(quote_identifier("order_items", Driver::Postgres), "order_items");
+        assert_eq!(quote_identifier("Order \"A\"", Driver::Postgres), "\"Order \"\"A\"\"\"");
+        assert_eq!(quote_identifier("order", Driver::Mysql), "`order`");
+        assert_eq!(quote_identifier("a`b\"c", Driver::Mysql), "`a``b\"c`");
+    }
+}
@@ -21,13 +21,19 @@ async fn main() -> Result<()> {
     info!("Configuration loaded");
 
     // Initialize application state
-    let mut app = app::App::new()?;
+    let default_profile = config.default_profile_name();
+    let mut app = app::App::new(config)?;
     
     // Initialize database connection
-    if let Err(e) = app.init_database(config.database.into()).await {
-        info!("Failed to connect to database: {}", e);
-    } else {
-        info!("Connected to database");
+    match default_profile {
+        Some(profile) => {
+            if let Err(e) = app.connect(&profile).await {
+                info!("Failed to connect to database: {}", e);
+            } else {
+                info!("Connected to database using profile {}", profile);
+            }
+        }
+        None => info!("No connection profiles configured"),
     }
 
     // Run the application
@@ -0,0 +1,43 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+    Frame,
+};
+
+use crate::app::{browse::BrowseClause, AppState};
+use super::popup::centered_lines_rect;
+
+/// Draws the prompt for a clause of the browsed table's query
+pub fn draw(frame: &mut Frame, state: &AppState, clause: BrowseClause, text: &str, area: Rect) {
+    let table = state.browser.as_ref().map_or_else(String::new, |browser| browser.qualified_name());
+    let hint = match clause {
+        BrowseClause::Where => "Empty for all rows",
+        BrowseClause::OrderBy => "Empty to order by the primary key",
+    };
+
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(format!("{} ", clause.label()), Style::default().fg(Color::Cyan)),
+            Span::raw(text.to_string()),
+            Span::raw("█"),
+        ]),
+        Line::from(""),
+        Line::styled(hint, Style::default().fg(Color::Gray)),
+        Line::styled("Enter: run    Esc: cancel    Ctrl+U: clear", Style::default().fg(Color::Gray)),
+    ];
+
+    let area = centered_lines_rect(70, lines.len(), area);
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(format!(" Browse {} ", table))
+            .padding(Padding::uniform(1)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
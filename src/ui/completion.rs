@@ -0,0 +1,59 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::completion::CompletionState;
+
+/// Most candidates shown at once
+const MAX_VISIBLE: u16 = 10;
+
+/// Widest candidate text shown before it is cut off
+const MAX_TEXT_WIDTH: usize = 40;
+
+/// Draws the completion popup with its left edge at column `x`, below the cursor
+/// row `y` if it fits there and above it otherwise
+pub fn draw(frame: &mut Frame, completion: &CompletionState, x: u16, y: u16) {
+    let screen = frame.size();
+    let text_width = completion.items.iter().map(|item| item.text.width()).max().unwrap_or(0).min(MAX_TEXT_WIDTH);
+    let label_width = completion.items.iter().map(|item| item.kind.label().len()).max().unwrap_or(0);
+
+    let width = ((text_width + label_width + 5) as u16).min(screen.width);
+    let height = (completion.items.len() as u16).min(MAX_VISIBLE) + 2;
+    let below = screen.height.saturating_sub(y + 1);
+    let (top, height) = if height <= below || below >= y {
+        (y + 1, height.min(below))
+    } else {
+        (y.saturating_sub(height), height.min(y))
+    };
+    let left = x.min(screen.width.saturating_sub(width));
+    let area = Rect::new(left, top, width, height);
+    frame.render_widget(Clear, area);
+
+    let items: Vec<ListItem> = completion
+        .items
+        .iter()
+        .map(|item| {
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{:<text_width$} ", item.text)),
+                Span::styled(item.kind.label(), Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .style(Style::default().bg(Color::Black).fg(Color::White))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut list_state = ListState::default().with_selected(Some(completion.selected));
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
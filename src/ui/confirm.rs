@@ -0,0 +1,32 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
+    Frame,
+};
+
+use super::popup::centered_lines_rect;
+
+/// Draws a yes/no confirmation popup sized to its message
+pub fn draw(frame: &mut Frame, message: &str, area: Rect) {
+    let mut lines: Vec<Line> = message.lines().map(Line::from).collect();
+    lines.push(Line::from(""));
+    lines.push(Line::styled("y / Enter: yes    n / Esc: no", Style::default().fg(Color::Gray)));
+
+    let area = centered_lines_rect(60, lines.len(), area);
+
+    let popup = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title("Confirm")
+                .padding(Padding::vertical(1)),
+        );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
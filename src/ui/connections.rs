@@ -0,0 +1,59 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState},
+    Frame,
+};
+
+use crate::app::AppState;
+use crate::database::{redact_password, Driver};
+use super::popup::centered_rect;
+
+/// Draws the connection profile picker popup
+pub fn draw(frame: &mut Frame, state: &AppState, selected: usize, area: Rect) {
+    let area = centered_rect(50, 50, area);
+
+    let items: Vec<ListItem> = state
+        .config
+        .profile_names()
+        .into_iter()
+        .map(|name| {
+            let profile = state.config.profile(&name);
+            let details = profile
+                .map(|p| match (&p.url, p.driver) {
+                    (Some(url), _) => format!("  {}", redact_password(url)),
+                    (None, Driver::Sqlite) => format!("  sqlite:{}", p.database),
+                    (None, driver) => {
+                        let port = if p.port == 0 { driver.default_port() } else { p.port };
+                        format!("  {}://{}@{}:{}/{}", driver, p.username, p.host, port, p.database)
+                    }
+                })
+                .unwrap_or_default();
+            let marker = if state.active_profile.as_deref() == Some(name.as_str()) {
+                "● "
+            } else {
+                "  "
+            };
+
+            ListItem::new(Line::from(vec![
+                Span::styled(marker, Style::default().fg(Color::Green)),
+                Span::styled(name, Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
+                Span::styled(details, Style::default().fg(Color::Gray)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title("Connections (Enter to connect, Esc to close)"),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut list_state = ListState::default().with_selected(Some(selected));
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
@@ -0,0 +1,57 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+    Frame,
+};
+
+use crate::app::{clipboard::CopyFormat, AppState};
+use super::popup::centered_lines_rect;
+
+/// Keys and descriptions of the copy targets
+const TARGETS: &[(&str, &str)] = &[
+    ("c", "cell"),
+    ("r", "row (or selected rows)"),
+    ("l", "column (or selected columns)"),
+    ("s", "selection (or cell)"),
+];
+
+/// Draws the copy popup with the targets and the chosen format
+pub fn draw(frame: &mut Frame, state: &AppState, area: Rect) {
+    let key_style = Style::default().fg(Color::Cyan);
+    let mut lines: Vec<Line> = TARGETS
+        .iter()
+        .map(|(key, description)| {
+            Line::from(vec![
+                Span::styled(format!("{}  ", key), key_style),
+                Span::raw(*description),
+            ])
+        })
+        .collect();
+
+    let mut format_spans = vec![Span::styled("Tab", key_style), Span::raw(" format: ")];
+    for format in [CopyFormat::Text, CopyFormat::Tsv, CopyFormat::Json] {
+        let style = if format == state.copy_format {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        format_spans.push(Span::styled(format!(" {} ", format.label()), style));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(format_spans));
+
+    let area = centered_lines_rect(40, lines.len(), area);
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(" Copy to clipboard (Esc to cancel) ")
+            .padding(Padding::uniform(1)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
@@ -0,0 +1,80 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+    Frame,
+};
+
+use crate::app::{record::wrap, AppState};
+use super::popup::{centered_lines_rect, centered_rect};
+
+/// Draws the prompt for the new value of the selected cell
+pub fn draw_cell(frame: &mut Frame, state: &AppState, text: &str, area: Rect) {
+    let column = state
+        .result_tabs
+        .get(state.selected_tab)
+        .and_then(|tab| Some((tab.result.as_ref().ok()?, tab.grid.column())))
+        .and_then(|(result, column)| result.headers.get(column).cloned())
+        .unwrap_or_default();
+
+    let lines = vec![
+        Line::from(vec![
+            Span::styled("> ", Style::default().fg(Color::Cyan)),
+            Span::raw(text.to_string()),
+            Span::raw("█"),
+        ]),
+        Line::from(""),
+        Line::styled(
+            "Enter: set    Ctrl+N: set NULL    Ctrl+U: clear    Esc: cancel",
+            Style::default().fg(Color::Gray),
+        ),
+    ];
+
+    let area = centered_lines_rect(60, lines.len(), area);
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(format!(" Edit {} ", column))
+            .padding(Padding::uniform(1)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Draws the statements that apply the pending edits. Returns the scroll offset
+/// clamped to the statement lines.
+pub fn draw_preview(frame: &mut Frame, state: &AppState, scroll: usize, area: Rect) -> usize {
+    let statements = state.edit_statements().unwrap_or_default();
+
+    let area = centered_rect(80, 70, area);
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(
+            " {} statements, applied in one transaction (Enter: apply, j/k: scroll, Esc: back) ",
+            statements.len()
+        ))
+        .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+
+    let mut lines = Vec::new();
+    for statement in &statements {
+        let style = match statement.split(' ').next() {
+            Some("DELETE") => Style::default().fg(Color::Red),
+            Some("INSERT") => Style::default().fg(Color::Green),
+            _ => Style::default().fg(Color::Yellow),
+        };
+        lines.extend(wrap(statement, inner.width as usize).into_iter().map(|line| Line::styled(line, style)));
+    }
+
+    let scroll = scroll.min(lines.len().saturating_sub(inner.height as usize));
+    let preview = Paragraph::new(lines).block(block).scroll((scroll as u16, 0));
+    frame.render_widget(preview, area);
+    scroll
+}
@@ -0,0 +1,47 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+    Frame,
+};
+
+use crate::app::{export::ExportFormat, AppState};
+use super::popup::centered_lines_rect;
+
+/// Draws the export path prompt
+pub fn draw(frame: &mut Frame, state: &AppState, path: &str, full: bool, area: Rect) {
+    let title = if full {
+        " Export all rows (runs the query again) ".to_string()
+    } else {
+        let rows = state.query_result().map_or(0, |result| result.visible_count());
+        format!(" Export {} shown rows ", rows)
+    };
+
+    let lines = vec![
+        Line::from(vec![
+            Span::styled("> ", Style::default().fg(Color::Cyan)),
+            Span::raw(path.to_string()),
+            Span::raw("█"),
+        ]),
+        Line::from(""),
+        Line::styled(
+            format!("Format by extension: {}", ExportFormat::EXTENSIONS),
+            Style::default().fg(Color::Gray),
+        ),
+        Line::styled("Enter: export    Esc: cancel", Style::default().fg(Color::Gray)),
+    ];
+
+    let area = centered_lines_rect(60, lines.len(), area);
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(title)
+            .padding(Padding::uniform(1)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
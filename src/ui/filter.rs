@@ -0,0 +1,43 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use crate::app::AppState;
+
+/// Draws the filter bar over the bottom border of the results panel
+pub fn draw(frame: &mut Frame, state: &AppState, pattern: &str, column: Option<usize>, area: Rect) {
+    if area.height < 3 || area.width < 3 {
+        return;
+    }
+    let bar = Rect {
+        x: area.x + 1,
+        y: area.bottom() - 1,
+        width: area.width - 2,
+        height: 1,
+    };
+
+    let scope = match column {
+        Some(column) => state
+            .query_result()
+            .and_then(|result| result.headers.get(column))
+            .map_or_else(|| "column".to_string(), |header| format!("in {}", header)),
+        None => "in all columns".to_string(),
+    };
+    let kind = if pattern.starts_with('/') { "regex" } else { "text" };
+    let line = Line::from(vec![
+        Span::styled("/", Style::default().fg(Color::Cyan)),
+        Span::raw(pattern.to_string()),
+        Span::raw("█ "),
+        Span::styled(
+            format!("({} {})  Tab: column  Enter: keep  Esc: clear", kind, scope),
+            Style::default().fg(Color::Gray),
+        ),
+    ]);
+
+    frame.render_widget(Clear, bar);
+    frame.render_widget(Paragraph::new(line).style(Style::default().bg(Color::Black)), bar);
+}
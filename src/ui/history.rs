@@ -0,0 +1,76 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::app::AppState;
+use super::popup::centered_rect;
+
+/// Draws the fuzzy history search popup
+pub fn draw(frame: &mut Frame, state: &AppState, pattern: &str, selected: usize, area: Rect) {
+    let area = centered_rect(80, 70, area);
+    frame.render_widget(Clear, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),  // Search input
+            Constraint::Min(1),     // Matches
+        ])
+        .split(area);
+
+    let search = Paragraph::new(Line::from(vec![
+        Span::styled("> ", Style::default().fg(Color::Cyan)),
+        Span::raw(pattern.to_string()),
+        Span::raw("█"),
+    ]))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title("History Search (Enter to load, Esc to close)"),
+    );
+    frame.render_widget(search, chunks[0]);
+
+    let items: Vec<ListItem> = state
+        .history
+        .search(pattern)
+        .into_iter()
+        .map(|entry| {
+            let (marker, marker_style) = if entry.is_success() {
+                ("✓ ", Style::default().fg(Color::Green))
+            } else {
+                ("✗ ", Style::default().fg(Color::Red))
+            };
+            let details = format!(
+                "{} {} {}ms ",
+                entry.timestamp.format("%Y-%m-%d %H:%M"),
+                entry.profile.as_deref().unwrap_or("-"),
+                entry.duration_ms
+            );
+            let query = entry.query.split_whitespace().collect::<Vec<_>>().join(" ");
+
+            ListItem::new(Line::from(vec![
+                Span::styled(marker, marker_style),
+                Span::styled(details, Style::default().fg(Color::Gray)),
+                Span::styled(query, Style::default().fg(Color::White)),
+            ]))
+        })
+        .collect();
+
+    let title = format!("Matches ({})", items.len());
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(title),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut list_state = ListState::default().with_selected(Some(selected));
+    frame.render_stateful_widget(list, chunks[1], &mut list_state);
+}
@@ -1,16 +1,18 @@
 use ratatui::{
     layout::Rect,
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
-use crate::app::{AppState, input::Mode};
+use unicode_segmentation::UnicodeSegmentation;
+use crate::app::{AppState, Focus, input::{display_width, Mode, Position}};
+use crate::database::tokenize;
+use super::completion;
+use super::theme::Theme;
 
-pub struct InputPanel;
-
-/// Draws the query input panel
-pub fn draw(frame: &mut Frame, state: &AppState, area: Rect) {
+/// Draws the query editor panel
+pub fn draw(frame: &mut Frame, state: &mut AppState, area: Rect) {
     let mode_style = match state.mode() {
         Mode::Normal => Style::default().fg(Color::Gray),
         Mode::Insert => Style::default().fg(Color::Green),
@@ -21,30 +23,108 @@ pub fn draw(frame: &mut Frame, state: &AppState, area: Rect) {
         Mode::Insert => "INSERT",
     };
 
-    let input_text = state.input.buffer();
-    let cursor_position = state.input.cursor_position();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(if state.focus == Focus::Editor {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        })
+        .title(Line::from(vec![
+            Span::styled(format!(" -- {} -- ", mode_indicator), mode_style),
+            Span::raw("SQL Query (Ctrl+Enter / F5 to execute, F6/F7 to explain) "),
+        ]));
 
-    // Create text with cursor
-    let mut text = String::with_capacity(input_text.len() + 1);
-    text.push_str(&input_text[..cursor_position]);
-    text.push('█'); // Cursor character
-    if cursor_position < input_text.len() {
-        text.push_str(&input_text[cursor_position..]);
-    }
+    let inner = block.inner(area);
+    let scroll = state
+        .input
+        .scroll_to_cursor(inner.height as usize, inner.width as usize);
+    let cursor = state.input.cursor();
+    let selection = state.input.selection();
+    let show_cursor = state.mode() == Mode::Insert;
+    let theme = Theme::named(state.config.theme);
+
+    // Tokens are found on the whole buffer, since strings and comments can span lines
+    let tokens = tokenize(&state.input.buffer());
+    let mut next_token = 0;
+    let mut line_start = 0;
+    let line_starts: Vec<usize> = state
+        .input
+        .lines()
+        .iter()
+        .map(|line| {
+            let start = line_start;
+            line_start += line.len() + 1;
+            start
+        })
+        .collect();
+
+    let lines: Vec<Line> = state
+        .input
+        .lines()
+        .iter()
+        .enumerate()
+        .skip(scroll.line)
+        .take(inner.height as usize)
+        .map(|(line_index, line)| {
+            let mut spans = Vec::new();
+            let mut column = 0;
+            for (offset, grapheme) in line.grapheme_indices(true) {
+                let width = display_width(grapheme);
+                let start = column;
+                column += width;
+                if column <= scroll.column {
+                    continue;
+                }
+
+                let absolute = line_starts[line_index] + offset;
+                while tokens.get(next_token).is_some_and(|token| token.range.end <= absolute) {
+                    next_token += 1;
+                }
+                let mut style = match tokens.get(next_token) {
+                    Some(token) if token.range.start <= absolute => theme.token(token.kind),
+                    _ => theme.text,
+                };
+
+                let position = Position { line: line_index, column: offset };
+                if selection.is_some_and(|(start, end)| start <= position && position < end) {
+                    style = style.patch(theme.selection);
+                }
+                if show_cursor && position == cursor {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+
+                if start < scroll.column {
+                    // Wide character cut off by the left edge of the viewport
+                    spans.push(Span::styled(" ".repeat(column - scroll.column), style));
+                } else if grapheme.chars().any(char::is_control) {
+                    spans.push(Span::styled(" ", style));
+                } else {
+                    spans.push(Span::styled(grapheme.to_string(), style));
+                }
+            }
+            if show_cursor && cursor.line == line_index && cursor.column == line.len() {
+                spans.push(Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)));
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    let input = Paragraph::new(lines)
+        .style(theme.text)
+        .block(block);
 
-    let input = Paragraph::new(vec![
-        Line::from(vec![
-            Span::styled(format!("-- {} -- ", mode_indicator), mode_style),
-            Span::raw("Press ESC to switch to Normal mode, i to switch to Insert mode")
-        ]),
-        Line::from(text),
-    ])
-    .style(Style::default().fg(Color::White))
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title("SQL Query (Press Enter to execute)")
-    );
-    
     frame.render_widget(input, area);
-}
\ No newline at end of file
+
+    if let Some(popup) = &state.completion {
+        // Line the popup up with the start of the word being completed
+        let line = &state.input.lines()[cursor.line];
+        let Some(before_word) = cursor.column.checked_sub(popup.prefix_len).and_then(|start| line.get(..start)) else {
+            return;
+        };
+        let word_start = display_width(before_word);
+        let x = inner.x + word_start.saturating_sub(scroll.column).min(inner.width as usize) as u16;
+        let y = inner.y + (cursor.line - scroll.line) as u16;
+        completion::draw(frame, popup, x, y);
+    }
+}
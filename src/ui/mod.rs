@@ -1,32 +1,39 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    layout::{Constraint, Direction, Layout},
     Frame,
 };
 
-use crate::app::AppState;
+use crate::app::{AppState, Popup};
 
+mod browse;
+mod completion;
+mod confirm;
+mod connections;
+mod copy;
+mod edit;
+mod export;
+mod filter;
+mod history;
 mod input;
+mod plan;
+mod popup;
+mod record;
 mod results;
 mod sidebar;
 mod status;
+mod structure;
 mod table;
-
-pub use input::InputPanel;
-pub use results::ResultsPanel;
-pub use sidebar::SidebarPanel;
-pub use status::StatusPanel;
+mod theme;
 
 /// Draws the entire user interface
-pub fn draw(frame: &mut Frame, state: &AppState) {
+pub fn draw(frame: &mut Frame, state: &mut AppState) {
     // Create the layout
+    let sidebar_width = if state.show_sidebar { 20 } else { 0 };
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Percentage(20),  // Sidebar
-            Constraint::Percentage(80),  // Main content
+            Constraint::Percentage(sidebar_width),        // Sidebar
+            Constraint::Percentage(100 - sidebar_width),  // Main content
         ])
         .split(frame.size());
 
@@ -35,13 +42,58 @@ pub fn draw(frame: &mut Frame, state: &AppState) {
         .constraints([
             Constraint::Length(3),   // Status bar
             Constraint::Min(1),      // Results area
-            Constraint::Length(5),   // Input area
+            Constraint::Length(state.editor_height), // Input area
         ])
         .split(chunks[1]);
 
     // Draw each panel
-    sidebar::draw(frame, state, chunks[0]);
+    if state.show_sidebar {
+        sidebar::draw(frame, state, chunks[0]);
+    }
     status::draw(frame, state, main_chunks[0]);
     results::draw(frame, state, main_chunks[1]);
     input::draw(frame, state, main_chunks[2]);
+
+    // Draw popups over the main layout
+    match &state.popup {
+        Some(Popup::ConnectionPicker { selected }) => {
+            connections::draw(frame, state, *selected, frame.size());
+        }
+        Some(Popup::HistorySearch { pattern, selected }) => {
+            history::draw(frame, state, pattern, *selected, frame.size());
+        }
+        Some(Popup::Confirm { message, .. }) => {
+            confirm::draw(frame, message, frame.size());
+        }
+        Some(Popup::Record { scroll }) => {
+            // Scrolling past the last field is undone once the field lines are known
+            let scroll = record::draw(frame, state, *scroll, frame.size());
+            state.popup = Some(Popup::Record { scroll });
+        }
+        Some(Popup::Copy) => {
+            copy::draw(frame, state, frame.size());
+        }
+        Some(Popup::Export { path, full }) => {
+            export::draw(frame, state, path, *full, frame.size());
+        }
+        Some(Popup::BrowseClause { clause, text }) => {
+            browse::draw(frame, state, *clause, text, frame.size());
+        }
+        Some(Popup::EditCell { text }) => {
+            edit::draw_cell(frame, state, text, frame.size());
+        }
+        Some(Popup::EditPreview { scroll }) => {
+            let scroll = edit::draw_preview(frame, state, *scroll, frame.size());
+            state.popup = Some(Popup::EditPreview { scroll });
+        }
+        Some(Popup::Structure { ddl, scroll }) => {
+            let ddl = *ddl;
+            let scroll = structure::draw(frame, state, ddl, *scroll, frame.size());
+            state.popup = Some(Popup::Structure { ddl, scroll });
+        }
+        Some(Popup::Filter { pattern, column }) => {
+            filter::draw(frame, state, pattern, *column, main_chunks[1]);
+        }
+        None => {}
+    }
 }
\ No newline at end of file
@@ -0,0 +1,184 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+
+use crate::app::{input::display_width, plan::{PlanNode, PlanView, EXPENSIVE_SHARE}};
+
+/// Most lines the details of the selected node take below the tree
+const MAX_DETAIL_LINES: u16 = 8;
+
+/// Draws a query plan as a tree with a row per node and the details of the
+/// selected node below it. The node spending the largest share of the query
+/// is shown in red and the others above `EXPENSIVE_SHARE` in yellow; row
+/// estimates off by `MISESTIMATE_FACTOR` or more are shown in magenta.
+pub fn draw(frame: &mut Frame, view: &mut PlanView, focused: bool, border_style: Style, area: Rect) {
+    let visible = view.visible();
+    let plan = &view.plan;
+
+    let mut title = if plan.analyzed {
+        let time = |label: &str, ms: Option<f64>| ms.map_or_else(String::new, |ms| format!(", {} {:.3} ms", label, ms));
+        format!(
+            "Plan (EXPLAIN ANALYZE{}{})",
+            time("planning", plan.planning_time),
+            time("execution", plan.execution_time)
+        )
+    } else {
+        format!("Plan (EXPLAIN, total cost {:.2})", plan.root.total_cost)
+    };
+    title.push_str(&format!(" node {}/{}", view.selected() + 1, visible.len()));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let selected = visible.get(view.selected()).map(|node| node.node);
+    let details = selected.map(detail_lines).unwrap_or_default();
+    let detail_height = (details.len() as u16 + 1).min(MAX_DETAIL_LINES).min(inner.height / 2);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(detail_height)])
+        .split(inner);
+
+    // The node with the largest share stands out from the other expensive ones
+    let shares: Vec<f64> = visible.iter().map(|node| plan.share(node.node)).collect();
+    let largest = shares.iter().copied().fold(0.0, f64::max);
+
+    let header = if plan.analyzed {
+        vec!["Node", "Self", "Time ms", "Rows", "Estimate", "Loops", "Buffers"]
+    } else {
+        vec!["Node", "Self", "Cost", "Rows", "Width"]
+    };
+    let rows: Vec<Row> = visible
+        .iter()
+        .zip(&shares)
+        .map(|(visible, share)| {
+            let node = visible.node;
+            let marker = match (node.children.is_empty(), visible.collapsed) {
+                (true, _) => "  ",
+                (false, true) => "▸ ",
+                (false, false) => "▾ ",
+            };
+            let cost_style = if *share >= EXPENSIVE_SHARE && *share == largest {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            } else if *share >= EXPENSIVE_SHARE {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            let estimate_style = if node.is_misestimated() {
+                Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+
+            let mut cells = vec![
+                Cell::from(format!("{}{}{}", "  ".repeat(visible.depth), marker, node.label)).style(cost_style),
+                Cell::from(format!("{:.0}%", share * 100.0)).style(cost_style),
+            ];
+            if plan.analyzed {
+                cells.extend([
+                    Cell::from(match node.total_time() {
+                        _ if node.never_executed() => "never run".to_string(),
+                        Some(ms) => format!("{:.3}", ms),
+                        None => String::new(),
+                    }),
+                    Cell::from(node.actual_rows.map_or_else(String::new, format_number)).style(estimate_style),
+                    Cell::from(estimate_text(node)).style(estimate_style),
+                    Cell::from(node.actual_loops.map_or_else(String::new, format_number)),
+                    Cell::from(buffers_text(node)),
+                ]);
+            } else {
+                cells.extend([
+                    Cell::from(format!("{:.2}..{:.2}", node.startup_cost, node.total_cost)),
+                    Cell::from(format_number(node.plan_rows)),
+                    Cell::from(format_number(node.plan_width)),
+                ]);
+            }
+            Row::new(cells)
+        })
+        .collect();
+
+    // The node column fits the widest label in the space the numbers leave
+    let number_widths: &[u16] = if plan.analyzed { &[5, 11, 10, 18, 6, 18] } else { &[5, 20, 10, 6] };
+    let numbers_width: u16 = number_widths.iter().map(|width| width + 1).sum();
+    let label_width = visible
+        .iter()
+        .map(|node| display_width(&node.node.label) + node.depth * 2 + 2)
+        .max()
+        .unwrap_or(0) as u16;
+    let node_width = label_width.min(chunks[0].width.saturating_sub(numbers_width)).max(20);
+    let widths: Vec<Constraint> = std::iter::once(Constraint::Length(node_width))
+        .chain(number_widths.iter().map(|width| Constraint::Length(*width)))
+        .collect();
+    let highlight = if focused {
+        Style::default().add_modifier(Modifier::REVERSED)
+    } else {
+        Style::default().bg(Color::Rgb(40, 40, 60))
+    };
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(header).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD).bg(Color::DarkGray)),
+        )
+        .highlight_style(highlight);
+
+    let mut table_state = TableState::default()
+        .with_offset(view.offset)
+        .with_selected(Some(view.selected()));
+    frame.render_stateful_widget(table, chunks[0], &mut table_state);
+
+    if detail_height > 0 {
+        let details = Paragraph::new(details)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::TOP).border_style(Style::default().fg(Color::DarkGray)));
+        frame.render_widget(details, chunks[1]);
+    }
+    view.offset = table_state.offset();
+}
+
+/// Lists the attributes of a node that have no column in the tree
+fn detail_lines(node: &PlanNode) -> Vec<Line<'static>> {
+    let key_style = Style::default().fg(Color::Cyan);
+    let mut lines = vec![Line::styled(node.label.clone(), Style::default().add_modifier(Modifier::BOLD))];
+    lines.extend(node.details.iter().map(|(key, value)| {
+        Line::from(vec![
+            Span::styled(format!("{}: ", key), key_style),
+            Span::raw(value.clone()),
+        ])
+    }));
+    lines
+}
+
+/// Shows the estimated rows and how far the actual rows are from them, e.g. `10 (200× more)`
+fn estimate_text(node: &PlanNode) -> String {
+    let estimate = format_number(node.plan_rows);
+    match node.estimate_ratio() {
+        Some(ratio) if ratio >= 2.0 => format!("{} ({:.0}× more)", estimate, ratio),
+        Some(ratio) if ratio <= 0.5 => format!("{} ({:.0}× fewer)", estimate, 1.0 / ratio),
+        _ => estimate,
+    }
+}
+
+/// Shows the shared buffers a node hit in the cache and read from disk
+fn buffers_text(node: &PlanNode) -> String {
+    match (node.shared_hit, node.shared_read) {
+        (Some(hit), Some(read)) if read > 0 => format!("hit {} read {}", hit, read),
+        (Some(hit), _) => format!("hit {}", hit),
+        _ => String::new(),
+    }
+}
+
+/// Formats a row count or width, without decimals when it is whole
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
@@ -0,0 +1,32 @@
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+
+/// Computes a rectangle centered in `area` taking the given percentages of its size
+pub fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+/// Computes a rectangle centered in `area`, `percent_x` of its width wide and tall
+/// enough for `lines` lines of text inside borders and one line of padding
+pub fn centered_lines_rect(percent_x: u16, lines: usize, area: Rect) -> Rect {
+    let mut area = centered_rect(percent_x, 100, area);
+    let height = (lines as u16 + 4).min(area.height);
+    area.y += (area.height - height) / 2;
+    area.height = height;
+    area
+}
@@ -0,0 +1,84 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::app::{input::display_width, record::{field_text, wrap}, AppState};
+use crate::database::CellValue;
+use super::popup::centered_rect;
+
+/// Widest the column name column grows, in display columns
+const MAX_NAME_WIDTH: usize = 30;
+
+/// Draws the selected row vertically as column name and value pairs, like psql's
+/// expanded display. Returns the scroll offset clamped to the field lines.
+pub fn draw(frame: &mut Frame, state: &AppState, scroll: usize, area: Rect) -> usize {
+    let Some(tab) = state.result_tabs.get(state.selected_tab) else {
+        return 0;
+    };
+    let Ok(result) = &tab.result else {
+        return 0;
+    };
+    let row_index = tab.grid.row();
+    let Some(row) = result.visible_row(row_index) else {
+        return 0;
+    };
+
+    let area = centered_rect(80, 80, area);
+    frame.render_widget(Clear, area);
+
+    let more = if state.result_cursor.is_some() { "+" } else { "" };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(
+            " Row {} of {}{} (n/p: next/previous row, j/k: scroll, Esc: close) ",
+            row_index + 1,
+            result.visible_count(),
+            more
+        ));
+    let inner = block.inner(area);
+
+    let name_width = result
+        .headers
+        .iter()
+        .map(|header| display_width(header))
+        .max()
+        .unwrap_or(0)
+        .min(MAX_NAME_WIDTH);
+    // Name, a separator and the value
+    let value_width = (inner.width as usize).saturating_sub(name_width + 3);
+
+    let name_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let separator_style = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    for (column, (header, cell)) in result.headers.iter().zip(row).enumerate() {
+        let column_type = result.column_types.get(column).map_or("", String::as_str);
+        let value_style = match cell {
+            CellValue::Null | CellValue::Undecodable(_) => Style::default().fg(Color::DarkGray),
+            CellValue::Value(_) => Style::default(),
+        };
+
+        let name_lines = wrap(header, name_width);
+        let value_lines = wrap(&field_text(cell, column_type), value_width);
+        for index in 0..name_lines.len().max(value_lines.len()) {
+            let name = name_lines.get(index).map_or("", String::as_str);
+            let padding = " ".repeat(name_width.saturating_sub(display_width(name)));
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}{}", name, padding), name_style),
+                Span::styled(" │ ", separator_style),
+                Span::styled(value_lines.get(index).cloned().unwrap_or_default(), value_style),
+            ]));
+        }
+    }
+
+    let scroll = scroll.min(lines.len().saturating_sub(inner.height as usize));
+    let record = Paragraph::new(lines)
+        .block(block)
+        .scroll((scroll as u16, 0));
+    frame.render_widget(record, area);
+    scroll
+}
@@ -1,39 +1,202 @@
 use ratatui::{
-    layout::Rect,
-    style::{Color, Style},
-    widgets::{Block, Borders, Paragraph},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Tabs, Wrap},
     Frame,
 };
 
-use crate::app::AppState;
+use crate::app::{AppState, Focus};
+use super::plan;
 use super::table::create_table;
 
-pub struct ResultsPanel;
+/// Draws the query results panel, with a tab bar when a script produced several results
+pub fn draw(frame: &mut Frame, state: &mut AppState, area: Rect) {
+    let border_style = if state.focus == Focus::Results {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default()
+    };
 
-/// Draws the query results panel
-pub fn draw(frame: &mut Frame, state: &AppState, area: Rect) {
-    match &state.query_result {
-        Some(result) => {
+    if state.result_tabs.is_empty() {
+        let message = if let Some(error) = &state.last_error {
+            format!("Error: {}", error)
+        } else if let Some(msg) = &state.status_message {
+            msg.clone()
+        } else {
+            "No query results yet".to_string()
+        };
+
+        let results = Paragraph::new(message)
+            .style(Style::default().fg(Color::White))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(border_style)
+                    .title("Results")
+            );
+
+        frame.render_widget(results, area);
+        return;
+    }
+
+    let area = if state.result_tabs.len() > 1 {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1)])
+            .split(area);
+
+        let titles: Vec<Line> = state
+            .result_tabs
+            .iter()
+            .enumerate()
+            .map(|(index, tab)| {
+                let style = if tab.result.is_err() {
+                    Style::default().fg(Color::Red)
+                } else {
+                    Style::default()
+                };
+                Line::styled(format!("{}: {}", index + 1, tab.label()), style)
+            })
+            .collect();
+        let tabs = Tabs::new(titles)
+            .select(state.selected_tab)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_widget(tabs, chunks[0]);
+        chunks[1]
+    } else {
+        area
+    };
+
+    let focused = state.focus == Focus::Results;
+    let more_available = state.result_cursor.is_some();
+    let browsing = state.browsing().map(|browser| {
+        let estimate = browser.estimated_rows.map_or_else(String::new, |rows| format!(", ~{} in table", rows));
+        (browser.qualified_name(), browser.page, browser.page * browser.page_size, estimate)
+    });
+    let tab = &mut state.result_tabs[state.selected_tab];
+
+    if let Some(view) = &mut tab.plan {
+        plan::draw(frame, view, focused, border_style, area);
+        return;
+    }
+
+    match &tab.result {
+        Ok(result) if !result.headers.is_empty() => {
+            // A filter shows how many of the loaded rows match
+            let rows = if result.filter.is_some() {
+                format!("{} of {}", result.visible_count(), result.row_count())
+            } else {
+                result.row_count().to_string()
+            };
+            let mut title = if more_available {
+                format!(
+                    "Results ({} rows so far, more available, {}ms)",
+                    rows,
+                    result.execution_time
+                )
+            } else if result.more_rows {
+                format!(
+                    "Results (first {} rows, more available, {}ms)",
+                    rows,
+                    result.execution_time
+                )
+            } else {
+                format!(
+                    "Results ({} rows, {}ms)",
+                    rows,
+                    result.execution_time
+                )
+            };
+            if let Some(affected) = result.affected_rows {
+                title = format!("Results ({} rows affected, {}ms)", affected, result.execution_time);
+            }
+            // A browsed table shows which of its rows the page holds
+            if let Some((table, page, offset, estimate)) = &browsing {
+                let rows = match result.row_count() {
+                    0 => "no rows".to_string(),
+                    count => format!("rows {}-{}", offset + 1, offset + count),
+                };
+                title = format!("{} (page {}, {}{}, {}ms)", table, page + 1, rows, estimate, result.execution_time);
+                if result.filter.is_some() {
+                    title.push_str(&format!(" {} of {} shown", result.visible_count(), result.row_count()));
+                }
+            }
+            if let Some(edit) = &tab.edit {
+                let (updated, inserted, deleted) = edit.counts(result.rows.len());
+                title.push_str(&format!(
+                    " [editing {}: {} changed, {} new, {} deleted]",
+                    edit.table, updated, inserted, deleted
+                ));
+            }
+            if result.visible_count() > 0 {
+                title.push_str(&format!(
+                    " row {}/{} col {}/{}",
+                    tab.grid.row() + 1,
+                    result.visible_count(),
+                    tab.grid.column() + 1,
+                    result.column_count()
+                ));
+            }
+
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(border_style)
+                .title(title);
+
+            // One line of the inner area is taken by the header
+            let inner = block.inner(area);
+            let visible = tab.grid.scroll_to_cursor(
+                result.visible_count(),
+                inner.height.saturating_sub(1) as usize,
+                inner.width as usize,
+            );
             let table = create_table(
-                result.headers.clone(),
-                result.rows.clone()
+                result,
+                &tab.grid,
+                tab.edit.as_ref(),
+                visible,
+                focused,
+                inner.width as usize,
+                block
             );
             frame.render_widget(table, area);
         }
-        None => {
-            let message = if let Some(error) = &state.last_error {
-                format!("Error: {}", error)
-            } else if let Some(msg) = &state.status_message {
-                msg.clone()
+        Ok(result) => {
+            let message = if result.command_tag.is_some() || result.affected_rows.is_some() {
+                format!("{} ({}ms)", result.summary(), result.execution_time)
             } else {
-                "No query results yet".to_string()
+                format!("Query returned no rows ({}ms)", result.execution_time)
             };
-
-            let results = Paragraph::new(message)
+            let mut lines = vec![Line::from(message), Line::from("")];
+            lines.extend(tab.statement.lines().map(Line::from));
+            let results = Paragraph::new(lines)
+                .style(Style::default().fg(Color::White))
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(border_style)
+                        .title("Results")
+                );
+            frame.render_widget(results, area);
+        }
+        Err(error) => {
+            let mut lines = vec![
+                Line::styled(format!("Error: {}", error), Style::default().fg(Color::Red)),
+                Line::from(""),
+            ];
+            lines.extend(tab.statement.lines().map(Line::from));
+            let results = Paragraph::new(lines)
                 .style(Style::default().fg(Color::White))
-                .block(Block::default().borders(Borders::ALL).title("Results"));
-            
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(border_style)
+                        .title("Results")
+                );
             frame.render_widget(results, area);
         }
     }
-}
\ No newline at end of file
+}
@@ -1,23 +1,70 @@
 use ratatui::{
     layout::Rect,
-    style::{Color, Style},
-    widgets::{Block, Borders, Paragraph},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
     Frame,
 };
 
-use crate::app::AppState;
-
-pub struct SidebarPanel;
+use crate::app::{schema_tree::NodeKind, AppState, Focus};
 
 /// Draws the navigation sidebar
 pub fn draw(frame: &mut Frame, state: &AppState, area: Rect) {
-    let sidebar = Paragraph::new("Databases will be listed here")
-        .style(Style::default().fg(Color::White))
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Navigation")
-        );
-    
-    frame.render_widget(sidebar, area);
-}
\ No newline at end of file
+    let border_style = if state.focus == Focus::Sidebar {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default()
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title("Navigation");
+
+    let visible = state.schema_tree.visible();
+    if visible.is_empty() {
+        let sidebar = Paragraph::new("No databases loaded")
+            .style(Style::default().fg(Color::White))
+            .block(block);
+        frame.render_widget(sidebar, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = visible
+        .iter()
+        .map(|visible| {
+            let node = visible.node;
+            let marker = if !node.kind.is_expandable() {
+                "  "
+            } else if node.expanded {
+                "▾ "
+            } else {
+                "▸ "
+            };
+
+            let style = match node.kind {
+                NodeKind::Database(ref name) if state.current_database.as_deref() == Some(name.as_str()) => {
+                    Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+                }
+                NodeKind::Database(_) | NodeKind::Schema(_) => Style::default().fg(Color::White),
+                NodeKind::Category { .. } => Style::default().fg(Color::Yellow),
+                NodeKind::Object { .. } => Style::default().fg(Color::White),
+                NodeKind::Column => Style::default().fg(Color::Gray),
+                NodeKind::Index => Style::default().fg(Color::Magenta),
+                NodeKind::Constraint => Style::default().fg(Color::Blue),
+            };
+
+            ListItem::new(Line::from(vec![
+                Span::raw("  ".repeat(visible.depth)),
+                Span::styled(marker, Style::default().fg(Color::DarkGray)),
+                Span::styled(node.label.clone(), style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut list_state = ListState::default().with_selected(Some(state.schema_tree.selected()));
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use ratatui::{
     layout::Rect,
     style::{Color, Style},
@@ -7,25 +9,88 @@ use ratatui::{
 };
 
 use crate::app::AppState;
+use crate::database::TransactionStatus;
 
-pub struct StatusPanel;
+/// Frames of the spinner shown while a query or export runs
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
 /// Draws the status bar panel
 pub fn draw(frame: &mut Frame, state: &AppState, area: Rect) {
     let db_name = state.current_database.as_deref().unwrap_or("Not Connected");
-    let schema = state.current_schema.as_deref().unwrap_or("public");
+    let schema = state
+        .current_schema
+        .as_deref()
+        .or(state.db_manager.as_ref().map(|db| db.default_schema()))
+        .unwrap_or("public");
     
-    let status = Line::from(vec![
+    let profile = state.active_profile.as_deref().unwrap_or("none");
+
+    let mut spans = vec![
+        Span::styled("Profile: ", Style::default().fg(Color::Gray)),
+        Span::styled(profile, Style::default().fg(Color::Green)),
+        Span::raw(" | "),
         Span::styled("DB: ", Style::default().fg(Color::Gray)),
         Span::styled(db_name, Style::default().fg(Color::Green)),
         Span::raw(" | "),
         Span::styled("Schema: ", Style::default().fg(Color::Gray)),
         Span::styled(schema, Style::default().fg(Color::Green)),
-    ]);
+    ];
+
+    if state.db_manager.as_ref().is_some_and(|db| db.config().read_only) {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled("read-only", Style::default().fg(Color::Black).bg(Color::Cyan)));
+    }
+
+    if let Some(session) = &state.session {
+        let transaction = session.state();
+        let (label, color) = match transaction.status {
+            TransactionStatus::Idle => ("TX idle".to_string(), Color::Green),
+            TransactionStatus::Active => ("TX in transaction".to_string(), Color::Yellow),
+            TransactionStatus::Failed => ("TX failed, roll back (R/U)".to_string(), Color::Red),
+        };
+        let savepoints = match transaction.savepoints.len() {
+            0 => String::new(),
+            1 => " (1 savepoint)".to_string(),
+            count => format!(" ({} savepoints)", count),
+        };
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled(
+            format!("{}{}", label, savepoints),
+            Style::default().fg(Color::Black).bg(color),
+        ));
+    }
+
+    if let Some(running) = &state.running_query {
+        spans.push(Span::raw(" | "));
+        spans.push(spinner(running.elapsed(), "Running", "Ctrl+C"));
+    } else if let Some(export) = &state.running_export {
+        spans.push(Span::raw(" | "));
+        spans.push(spinner(export.elapsed(), "Exporting", "Esc"));
+    } else if !state.result_tabs.is_empty() {
+        // Without results the message is shown in the results panel instead
+        if let Some(error) = &state.last_error {
+            spans.push(Span::raw(" | "));
+            spans.push(Span::styled(error.as_str(), Style::default().fg(Color::Red)));
+        } else if let Some(message) = &state.status_message {
+            spans.push(Span::raw(" | "));
+            spans.push(Span::styled(message.as_str(), Style::default().fg(Color::Gray)));
+        }
+    }
+
+    let status = Line::from(spans);
 
     let status_widget = Paragraph::new(status)
         .style(Style::default())
         .block(Block::default().borders(Borders::ALL));
     
     frame.render_widget(status_widget, area);
-}
\ No newline at end of file
+}
+
+/// Gets the spinner span for work running for `elapsed`, naming the key that cancels it
+fn spinner(elapsed: Duration, label: &str, cancel_key: &str) -> Span<'static> {
+    let frame_index = (elapsed.as_millis() / 100) as usize % SPINNER.len();
+    Span::styled(
+        format!("{} {} {:.1}s ({} to cancel)", SPINNER[frame_index], label, elapsed.as_secs_f64(), cancel_key),
+        Style::default().fg(Color::Yellow),
+    )
+}
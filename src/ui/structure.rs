@@ -0,0 +1,236 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+    Frame,
+};
+
+use crate::app::{input::display_width, record::wrap, AppState};
+use crate::database::{quote_identifier, tokenize, TableStructure};
+use super::popup::centered_rect;
+use super::theme::Theme;
+
+/// Widest the name column grows, in display columns
+const MAX_NAME_WIDTH: usize = 30;
+
+/// An entry of a section: a name and the paragraphs describing it
+type Entry = (String, Vec<(String, Style)>);
+
+/// Draws the definition of the inspected table, either as its columns,
+/// constraints, indexes, triggers and partitions or as DDL. Returns the scroll
+/// offset clamped to the lines.
+pub fn draw(frame: &mut Frame, state: &AppState, ddl: bool, scroll: usize, area: Rect) -> usize {
+    let Some(structure) = &state.structure else {
+        return 0;
+    };
+
+    let area = centered_rect(90, 85, area);
+    frame.render_widget(Clear, area);
+
+    let view = if ddl { "structure" } else { "DDL" };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(
+            " {} {} (Tab: {}, y: copy DDL, j/k: scroll, Esc: close) ",
+            structure.kind,
+            structure.qualified_name(),
+            view
+        ))
+        .padding(Padding::horizontal(1));
+    let inner = block.inner(area);
+
+    let width = inner.width as usize;
+    let lines = if ddl {
+        highlight(&structure.ddl(), &Theme::named(state.config.theme), width)
+    } else {
+        structure_lines(structure, width)
+    };
+
+    let scroll = scroll.min(lines.len().saturating_sub(inner.height as usize));
+    let paragraph = Paragraph::new(lines).block(block).scroll((scroll as u16, 0));
+    frame.render_widget(paragraph, area);
+    scroll
+}
+
+/// Lays out the parts of a table as sections of names and their descriptions
+fn structure_lines(structure: &TableStructure, width: usize) -> Vec<Line<'static>> {
+    let plain = Style::default();
+    let dim = Style::default().fg(Color::DarkGray);
+    let comment = Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC);
+
+    let mut lines = Vec::new();
+    let mut notes = Vec::new();
+    if let Some(text) = &structure.comment {
+        notes.push((text.clone(), comment));
+    }
+    if let (Some(parent), Some(bound)) = (&structure.parent, &structure.partition_bound) {
+        notes.push((format!("Partition of {} {}", parent, bound), plain));
+    }
+    if let Some(key) = &structure.partition_key {
+        notes.push((format!("Partitioned by {}", key), plain));
+    }
+    if let Some(server) = &structure.server {
+        notes.push((format!("Foreign table on server {}", server), plain));
+    }
+    if structure.unlogged {
+        notes.push(("Unlogged".to_string(), plain));
+    }
+    if !structure.options.is_empty() {
+        notes.push((format!("Storage parameters: {}", structure.options.join(", ")), plain));
+    }
+    for (text, style) in notes {
+        lines.extend(wrap(&text, width).into_iter().map(|line| Line::styled(line, style)));
+    }
+
+    let columns: Vec<Entry> = structure
+        .columns
+        .iter()
+        .map(|column| {
+            let mut details = column.data_type.clone();
+            if !column.nullable {
+                details.push_str(" NOT NULL");
+            }
+            if let Some(generated) = &column.generated {
+                details.push_str(&format!(" {}", generated));
+            } else if let Some(default) = &column.default {
+                details.push_str(&format!(" DEFAULT {}", default));
+            }
+            if let Some(collation) = &column.collation {
+                details.push_str(&format!(" COLLATE {}", quote_identifier(collation, structure.driver)));
+            }
+            let mut paragraphs = vec![(details, plain)];
+            paragraphs.extend(column.comment.clone().map(|text| (text, comment)));
+            (column.name.clone(), paragraphs)
+        })
+        .collect();
+    let constraints: Vec<Entry> = structure
+        .constraints
+        .iter()
+        .map(|constraint| {
+            let inherited = if constraint.inherited { " (inherited)" } else { "" };
+            (constraint.name.clone(), vec![(format!("{}{}", constraint.definition, inherited), plain)])
+        })
+        .collect();
+    let indexes: Vec<Entry> = structure
+        .indexes
+        .iter()
+        .map(|index| {
+            // The name and table are already known, keep the method, columns and options
+            let unique = if index.definition.starts_with("CREATE UNIQUE") { "UNIQUE " } else { "" };
+            let method = match index.definition.split_once(" USING ") {
+                Some((_, rest)) => rest,
+                // Without a method, e.g. from SQLite, keep the columns
+                None => index.definition.find('(').map_or(index.definition.as_str(), |start| &index.definition[start..]),
+            };
+            let mut details = format!("{}{}", unique, method);
+            if !index.valid {
+                details.push_str(" (invalid)");
+            }
+            (index.name.clone(), vec![(details, plain)])
+        })
+        .collect();
+    let triggers: Vec<Entry> = structure
+        .triggers
+        .iter()
+        .map(|trigger| {
+            let prefix = format!("CREATE TRIGGER {} ", quote_identifier(&trigger.name, structure.driver));
+            let mut details = trigger.definition.strip_prefix(&prefix).unwrap_or(&trigger.definition).to_string();
+            if !trigger.enabled {
+                details.push_str(" (disabled)");
+            }
+            (trigger.name.clone(), vec![(details, plain)])
+        })
+        .collect();
+    let partitions: Vec<Entry> = structure
+        .partitions
+        .iter()
+        .map(|partition| (partition.name.clone(), vec![(partition.bound.clone(), plain)]))
+        .collect();
+
+    let sections = [
+        ("Columns", columns),
+        ("Constraints", constraints),
+        ("Indexes", indexes),
+        ("Triggers", triggers),
+        ("Partitions", partitions),
+    ];
+    let name_width = sections
+        .iter()
+        .flat_map(|(_, entries)| entries.iter().map(|(name, _)| display_width(name)))
+        .max()
+        .unwrap_or(0)
+        .min(MAX_NAME_WIDTH);
+    // Name, a separator and the description
+    let details_width = width.saturating_sub(name_width + 2);
+
+    let heading = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let name_style = Style::default().fg(Color::Cyan);
+    for (title, entries) in sections.iter().filter(|(_, entries)| !entries.is_empty()) {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(vec![
+            Span::styled(title.to_string(), heading),
+            Span::styled(format!(" ({})", entries.len()), dim),
+        ]));
+        for (name, paragraphs) in entries {
+            let name_lines = wrap(name, name_width);
+            let details_lines: Vec<(String, Style)> = paragraphs
+                .iter()
+                .flat_map(|(text, style)| wrap(text, details_width).into_iter().map(|line| (line, *style)))
+                .collect();
+            for index in 0..name_lines.len().max(details_lines.len()) {
+                let name = name_lines.get(index).map_or("", String::as_str);
+                let padding = " ".repeat(name_width.saturating_sub(display_width(name)));
+                let (details, style) = details_lines.get(index).cloned().unwrap_or_default();
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{}{}  ", name, padding), name_style),
+                    Span::styled(details, style),
+                ]));
+            }
+        }
+    }
+
+    if let Some(query) = &structure.view_definition {
+        lines.push(Line::from(""));
+        lines.push(Line::styled("Query", heading));
+        lines.extend(wrap(query.trim_end(), width).into_iter().map(Line::from));
+    }
+    lines
+}
+
+/// Wraps DDL to the width of the view and highlights it like the editor does
+fn highlight(ddl: &str, theme: &Theme, width: usize) -> Vec<Line<'static>> {
+    // Wrapping expands tabs, so the lines only keep the offsets of tokens without them
+    let ddl = ddl.replace('\t', "    ").replace('\r', "");
+    let tokens = tokenize(&ddl);
+
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for paragraph in ddl.split('\n') {
+        for line in wrap(paragraph, width) {
+            let end = offset + line.len();
+            let mut spans = Vec::new();
+            let mut position = offset;
+            for token in tokens.iter().filter(|token| token.range.end > offset && token.range.start < end) {
+                let start = token.range.start.max(offset);
+                let stop = token.range.end.min(end);
+                if start > position {
+                    spans.push(Span::styled(ddl[position..start].to_string(), theme.text));
+                }
+                spans.push(Span::styled(ddl[start..stop].to_string(), theme.token(token.kind)));
+                position = stop;
+            }
+            if position < end {
+                spans.push(Span::styled(ddl[position..end].to_string(), theme.text));
+            }
+            lines.push(Line::from(spans));
+            offset = end;
+        }
+        // The line break
+        offset += 1;
+    }
+    lines
+}
@@ -1,20 +1,47 @@
+use std::ops::Range;
+
 use ratatui::{
     layout::Constraint,
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, Cell, Row, Table},
-    text::Text,
+    widgets::{Block, Cell, Row, Table},
 };
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-/// Creates a styled table widget from query results
-pub fn create_table(headers: Vec<String>, data: Vec<Vec<String>>) -> Table<'static> {
-    // Get column count before consuming headers
-    let column_count = headers.len();
+use crate::app::edit::EditSession;
+use crate::app::grid::{single_line, GridState, COLUMN_SPACING};
+use crate::app::query::QueryResult;
+
+/// Creates a styled table widget showing the visible part of a query result,
+/// with the pending changes of edit mode highlighted. `visible` holds the ranges
+/// of rows and columns to show.
+pub fn create_table<'a>(
+    result: &QueryResult,
+    grid: &GridState,
+    edit: Option<&EditSession>,
+    (rows, columns): (Range<usize>, Range<usize>),
+    focused: bool,
+    area_width: usize,
+    block: Block<'a>,
+) -> Table<'a> {
+    // Shrink the last visible column to the space that is left
+    let mut remaining = area_width;
+    let widths: Vec<usize> = grid.widths()[columns.clone()]
+        .iter()
+        .map(|width| {
+            let width = (*width).min(remaining);
+            remaining = remaining.saturating_sub(width + COLUMN_SPACING);
+            width
+        })
+        .collect();
 
     // Convert headers into owned Cells
-    let header_cells: Vec<Cell<'static>> = headers
-        .into_iter()
-        .map(|h| {
-            Cell::from(h.to_string()).style(
+    let header_cells: Vec<Cell> = result.headers[columns.clone()]
+        .iter()
+        .zip(&widths)
+        .zip(columns.clone())
+        .map(|((header, width), column)| {
+            Cell::from(header_text(result, header, column, *width)).style(
                 Style::default()
                     .fg(Color::Yellow)
                     .add_modifier(Modifier::BOLD)
@@ -22,29 +49,109 @@ pub fn create_table(headers: Vec<String>, data: Vec<Vec<String>>) -> Table<'stat
         })
         .collect();
 
-    // Create header row
+    // Create header row, which stays in place while the rows scroll
     let header = Row::new(header_cells)
         .style(Style::default().bg(Color::DarkGray));
 
-    // Convert data into owned Rows
-    let rows: Vec<Row<'static>> = data
-        .into_iter()
-        .map(|row| {
-            let cells = row
-                .into_iter()
-                .map(|c| Cell::from(c.to_string()));
-            Row::new(cells).style(Style::default().fg(Color::White))
+    let selection = grid.selection();
+    let table_rows: Vec<Row> = rows
+        .filter_map(|index| Some((result.row_index(index)?, index)))
+        .map(|(row_index, index)| {
+            let row = &result.rows[row_index];
+            let selected_row = index == grid.row();
+            let inserted = edit.is_some_and(|edit| edit.is_inserted(row_index));
+            let deleted = edit.is_some_and(|edit| edit.deleted.contains(&row_index));
+            let cells = row[columns.clone()]
+                .iter()
+                .zip(&widths)
+                .zip(columns.clone())
+                .map(|((cell, width), column)| {
+                    let change = edit.and_then(|edit| edit.change(row_index, column));
+                    let text = match change {
+                        Some(value) => value.to_string(),
+                        // Cells of inserted rows that are not set take the column default
+                        None if inserted => "DEFAULT".to_string(),
+                        None => cell.to_string(),
+                    };
+                    let cell = Cell::from(truncate(&text, *width));
+                    let in_selection = selection
+                        .as_ref()
+                        .is_some_and(|(rows, columns)| rows.contains(&index) && columns.contains(&column));
+                    if focused && selected_row && column == grid.column() {
+                        cell.style(Style::default().add_modifier(Modifier::REVERSED))
+                    } else if in_selection {
+                        cell.style(Style::default().bg(Color::Rgb(70, 70, 110)))
+                    } else if change.is_some() {
+                        cell.style(Style::default().fg(Color::Black).bg(Color::Yellow))
+                    } else if inserted {
+                        cell.style(Style::default().fg(Color::DarkGray))
+                    } else {
+                        cell
+                    }
+                });
+            let mut style = if selected_row {
+                Style::default().fg(Color::White).bg(Color::Rgb(40, 40, 60))
+            } else {
+                Style::default().fg(Color::White)
+            };
+            if deleted {
+                style = style.fg(Color::Red).add_modifier(Modifier::CROSSED_OUT);
+            } else if inserted {
+                style = style.fg(Color::Green);
+            }
+            Row::new(cells).style(style)
         })
         .collect();
 
-    // Calculate column widths
-    let width = 100 / column_count as u16;
-    let constraints = vec![Constraint::Percentage(width); column_count];
+    let constraints: Vec<Constraint> = widths
+        .iter()
+        .map(|width| Constraint::Length(*width as u16))
+        .collect();
 
-    // Create and return the table
-    Table::new(rows, constraints)
+    Table::new(table_rows, constraints)
         .header(header)
-        .block(Block::default().borders(Borders::ALL).title("Results"))
-        .column_spacing(1)
-        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
-}
\ No newline at end of file
+        .block(block)
+        .column_spacing(COLUMN_SPACING as u16)
+}
+
+/// Gets the text of a column header, ending with an arrow when the rows are sorted
+/// by the column and with its position among the sort keys when there are several
+fn header_text(result: &QueryResult, header: &str, column: usize, width: usize) -> String {
+    let Some(position) = result.sort.iter().position(|key| key.column == column) else {
+        return truncate(header, width);
+    };
+
+    let arrow = if result.sort[position].ascending { "▲" } else { "▼" };
+    let marker = if result.sort.len() > 1 {
+        format!("{}{}", arrow, position + 1)
+    } else {
+        arrow.to_string()
+    };
+    // Shorten the name rather than widen the column, so sorting does not shift the grid
+    let name_width = width.saturating_sub(marker.width() + 1);
+    if name_width == 0 {
+        return truncate(&marker, width);
+    }
+    format!("{} {}", truncate(header, name_width), marker)
+}
+
+/// Shortens text to `width` display columns, marking cut-off text with an ellipsis
+fn truncate(text: &str, width: usize) -> String {
+    let text = single_line(text);
+    if text.width() <= width {
+        return text.into_owned();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    for grapheme in text.graphemes(true) {
+        let grapheme_width = grapheme.width();
+        if used + grapheme_width + 1 > width {
+            break;
+        }
+        used += grapheme_width;
+        truncated.push_str(grapheme);
+    }
+    truncated.push('…');
+    truncated
+}
@@ -0,0 +1,76 @@
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::config::ThemeName;
+use crate::database::TokenKind;
+
+/// Colors of the query editor
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// Plain text and punctuation
+    pub text: Style,
+    /// Key words
+    pub keyword: Style,
+    /// Unquoted identifiers
+    pub identifier: Style,
+    /// Double-quoted identifiers
+    pub quoted_identifier: Style,
+    /// String and dollar-quoted literals
+    pub string: Style,
+    /// Numeric literals
+    pub number: Style,
+    /// Comments
+    pub comment: Style,
+    /// Operators
+    pub operator: Style,
+    /// Positional parameters
+    pub parameter: Style,
+    /// Selected text
+    pub selection: Style,
+}
+
+impl Theme {
+    /// Gets a built-in theme
+    pub fn named(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Dark => Self {
+                text: Style::default().fg(Color::White),
+                keyword: Style::default().fg(Color::LightBlue).add_modifier(Modifier::BOLD),
+                identifier: Style::default().fg(Color::White),
+                quoted_identifier: Style::default().fg(Color::LightYellow),
+                string: Style::default().fg(Color::LightGreen),
+                number: Style::default().fg(Color::LightMagenta),
+                comment: Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                operator: Style::default().fg(Color::Yellow),
+                parameter: Style::default().fg(Color::LightRed),
+                selection: Style::default().bg(Color::DarkGray),
+            },
+            ThemeName::Light => Self {
+                text: Style::default().fg(Color::Black),
+                keyword: Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+                identifier: Style::default().fg(Color::Black),
+                quoted_identifier: Style::default().fg(Color::Cyan),
+                string: Style::default().fg(Color::Green),
+                number: Style::default().fg(Color::Magenta),
+                comment: Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+                operator: Style::default().fg(Color::Red),
+                parameter: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                selection: Style::default().bg(Color::Gray),
+            },
+        }
+    }
+
+    /// Gets the style of a token
+    pub fn token(&self, kind: TokenKind) -> Style {
+        match kind {
+            TokenKind::Keyword => self.keyword,
+            TokenKind::Identifier => self.identifier,
+            TokenKind::QuotedIdentifier => self.quoted_identifier,
+            TokenKind::String | TokenKind::DollarString => self.string,
+            TokenKind::Number => self.number,
+            TokenKind::Comment => self.comment,
+            TokenKind::Operator => self.operator,
+            TokenKind::Parameter => self.parameter,
+            TokenKind::Punctuation => self.text,
+        }
+    }
+}